@@ -0,0 +1,245 @@
+use crate::types::FileCategory;
+use anyhow::{Context, Result, bail};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+static BUILTIN: Lazy<CategorySet> = Lazy::new(|| {
+    let category = |category: FileCategory, extensions: &[&str]| CategoryDef {
+        category,
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+    };
+    CategorySet::from_defs(vec![
+        // Office Documents
+        category(FileCategory::Documents, &["doc", "docx", "odt", "rtf", "txt"]),
+        // PDFs
+        category(FileCategory::PDFs, &["pdf"]),
+        category(FileCategory::Presentations, &["ppt", "pptx"]),
+        category(FileCategory::Spreadsheets, &["xls", "xlsx"]),
+        // Images
+        category(
+            FileCategory::Images,
+            &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp", "heic", "raw", "cr2", "nef", "arw"],
+        ),
+        // Videos
+        category(
+            FileCategory::Videos,
+            &["mp4", "mov", "avi", "mkv", "wmv", "flv", "webm", "m4v", "3gp"],
+        ),
+        // Audio
+        category(
+            FileCategory::Audio,
+            &["mp3", "wav", "aac", "ogg", "flac", "m4a", "wma", "aiff"],
+        ),
+        // Source code and scripts
+        category(
+            FileCategory::Code,
+            &[
+                "rs", "py", "js", "ts", "c", "cpp", "h", "go", "java", "rb", "sh", "json", "cs",
+                "php", "swift", "kt", "ps1", "lua", "r", "scala",
+            ],
+        ),
+        // Compressed archives. Only the last extension is looked at: `tar.gz`, `tar.bz2`
+        // and `tar.xz` resolve through `gz`, `bz2` and `xz`, which are archives themselves.
+        category(
+            FileCategory::Archives,
+            &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "cab", "iso", "dmg"],
+        ),
+    ])
+    .expect("built-in categories are valid")
+});
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CategoriesFile {
+    #[serde(default)]
+    category: Vec<RawCategory>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCategory {
+    name: String,
+    #[serde(default)]
+    extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct CategoryDef {
+    category: FileCategory,
+    extensions: Vec<String>,
+}
+
+// The full set of categories and the extensions that map to them. The built-in
+// taxonomy is just the default set; a config file replaces it wholesale.
+#[derive(Debug, Clone)]
+pub struct CategorySet {
+    defs: Vec<CategoryDef>,
+    by_extension: HashMap<String, FileCategory>,
+}
+
+impl Default for CategorySet {
+    fn default() -> Self {
+        BUILTIN.clone()
+    }
+}
+
+impl CategorySet {
+    pub fn builtin() -> &'static CategorySet {
+        &BUILTIN
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read categories config {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid categories config {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: CategoriesFile = toml::from_str(content)?;
+        if file.category.is_empty() {
+            bail!("at least one category must be defined");
+        }
+        let defs = file
+            .category
+            .into_iter()
+            .map(|raw| CategoryDef {
+                category: FileCategory::from_name(&raw.name)
+                    .unwrap_or(FileCategory::Custom(raw.name)),
+                extensions: raw.extensions,
+            })
+            .collect();
+        Self::from_defs(defs)
+    }
+
+    fn from_defs(mut defs: Vec<CategoryDef>) -> Result<Self> {
+        let mut by_extension = HashMap::new();
+        let mut seen_names = Vec::new();
+        for def in &mut defs {
+            let name = def.category.as_str().to_string();
+            if name.trim().is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+                bail!("category name '{}' is not a valid folder name", name);
+            }
+            if name.eq_ignore_ascii_case("Duplicates") {
+                bail!("'Duplicates' is reserved for duplicate files");
+            }
+            if seen_names.iter().any(|n: &String| n.eq_ignore_ascii_case(&name)) {
+                bail!("category '{}' is defined more than once", name);
+            }
+            seen_names.push(name.clone());
+
+            for ext in &mut def.extensions {
+                *ext = ext.trim_start_matches('.').to_lowercase();
+                if let Some(existing) = by_extension.insert(ext.clone(), def.category.clone()) {
+                    bail!(
+                        "extension '{}' is mapped to both {} and {}",
+                        ext,
+                        existing.as_str(),
+                        name
+                    );
+                }
+            }
+        }
+        Ok(Self { defs, by_extension })
+    }
+
+    // Layers extra mappings on top of this set. Each key is a folder name, reusing a
+    // category of the same name or adding a new one after the existing categories;
+    // its extensions are taken away from whichever category had them before.
+    pub fn with_overrides(&self, overrides: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let mut defs = self.defs.clone();
+        for (name, extensions) in overrides {
+            let extensions: Vec<String> = extensions
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect();
+            for def in &mut defs {
+                def.extensions.retain(|e| !extensions.contains(e));
+            }
+            match defs
+                .iter_mut()
+                .find(|d| d.category.as_str().eq_ignore_ascii_case(name))
+            {
+                Some(def) => def.extensions.extend(extensions),
+                None => defs.push(CategoryDef {
+                    category: FileCategory::from_name(name)
+                        .unwrap_or_else(|| FileCategory::Custom(name.clone())),
+                    extensions,
+                }),
+            }
+        }
+        Self::from_defs(defs)
+    }
+
+    // Categories in display order
+    pub fn order(&self) -> Vec<FileCategory> {
+        self.defs.iter().map(|d| d.category.clone()).collect()
+    }
+
+    pub fn contains(&self, category: &FileCategory) -> bool {
+        self.defs.iter().any(|d| &d.category == category)
+    }
+
+    pub fn category_for_extension(&self, ext: &str) -> Option<FileCategory> {
+        self.by_extension.get(&ext.to_lowercase()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_matches_category_order() {
+        assert_eq!(CategorySet::builtin().order(), FileCategory::order());
+        assert_eq!(
+            CategorySet::builtin().category_for_extension("JPG"),
+            Some(FileCategory::Images)
+        );
+    }
+
+    #[test]
+    fn test_custom_categories_replace_builtins() -> Result<()> {
+        let set = CategorySet::parse(
+            "[[category]]\nname = \"Work\"\nextensions = [\"docx\", \".XLSX\"]\n\n\
+             [[category]]\nname = \"Personal\"\nextensions = [\"jpg\"]\n",
+        )?;
+        let work = FileCategory::Custom("Work".to_string());
+        assert_eq!(set.order(), vec![work.clone(), FileCategory::Custom("Personal".to_string())]);
+        assert_eq!(set.category_for_extension("xlsx"), Some(work));
+        assert_eq!(set.category_for_extension("pdf"), None);
+        assert!(!set.contains(&FileCategory::Images));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overrides_merge_with_builtins() -> Result<()> {
+        let overrides = BTreeMap::from([
+            ("Design".to_string(), vec!["sketch".to_string(), "png".to_string()]),
+            ("documents".to_string(), vec![".md".to_string()]),
+        ]);
+        let set = CategorySet::builtin().with_overrides(&overrides)?;
+
+        let design = FileCategory::Custom("Design".to_string());
+        assert_eq!(set.category_for_extension("sketch"), Some(design.clone()));
+        // User entries win over the built-in mapping
+        assert_eq!(set.category_for_extension("png"), Some(design.clone()));
+        assert_eq!(set.category_for_extension("jpg"), Some(FileCategory::Images));
+        assert_eq!(set.category_for_extension("md"), Some(FileCategory::Documents));
+        assert_eq!(set.order().last(), Some(&design));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_categories_rejected() {
+        assert!(CategorySet::parse("").is_err());
+        assert!(CategorySet::parse("[[category]]\nname = \"a/b\"\n").is_err());
+        assert!(CategorySet::parse("[[category]]\nname = \"Duplicates\"\n").is_err());
+        let shared = "[[category]]\nname = \"A\"\nextensions = [\"txt\"]\n\
+                      [[category]]\nname = \"B\"\nextensions = [\"txt\"]\n";
+        assert!(CategorySet::parse(shared).is_err());
+    }
+}
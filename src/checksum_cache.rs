@@ -0,0 +1,150 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+
+struct CachedChecksums {
+    size: u64,
+    modified: u128,
+    md5: String,
+    sha256: String,
+}
+
+// Append-only cache of checksums keyed by path, size and mtime. Every record is
+// flushed as soon as it is computed so an interrupted run keeps its progress.
+pub struct ChecksumCache {
+    entries: Mutex<HashMap<PathBuf, CachedChecksums>>,
+    file: Mutex<File>,
+    hits: AtomicUsize,
+}
+
+impl ChecksumCache {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut entries = HashMap::new();
+        let mut needs_newline = false;
+        if let Ok(contents) = fs::read_to_string(path) {
+            needs_newline = !contents.is_empty() && !contents.ends_with('\n');
+            for line in contents.lines() {
+                // A crash mid-write can leave a truncated last line; skip anything malformed
+                let fields: Vec<&str> = line.split('\t').collect();
+                if let [file_path, size, modified, md5, sha256] = fields[..]
+                    && let (Ok(size), Ok(modified)) = (size.parse(), modified.parse())
+                    && md5.len() == 32
+                    && sha256.len() == 64
+                {
+                    entries.insert(
+                        PathBuf::from(file_path),
+                        CachedChecksums {
+                            size,
+                            modified,
+                            md5: md5.to_string(),
+                            sha256: sha256.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if needs_newline {
+            writeln!(file)?;
+        }
+        Ok(Self {
+            entries: Mutex::new(entries),
+            file: Mutex::new(file),
+            hits: AtomicUsize::new(0),
+        })
+    }
+
+    fn fingerprint(path: &Path) -> Option<(u64, u128)> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some((metadata.len(), modified))
+    }
+
+    pub fn get(&self, path: &Path) -> Option<(String, String)> {
+        let (size, modified) = Self::fingerprint(path)?;
+        let entries = self.entries.lock().ok()?;
+        let cached = entries.get(path)?;
+        if cached.size != size || cached.modified != modified {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some((cached.md5.clone(), cached.sha256.clone()))
+    }
+
+    pub fn record(&self, path: &Path, md5: &str, sha256: &str) -> Result<()> {
+        let Some((size, modified)) = Self::fingerprint(path) else {
+            return Ok(());
+        };
+        {
+            let mut file = self
+                .file
+                .lock()
+                .map_err(|_| anyhow::anyhow!("checksum cache lock poisoned"))?;
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}\t{}",
+                path.display(),
+                size,
+                modified,
+                md5,
+                sha256
+            )?;
+            file.flush()?;
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                path.to_path_buf(),
+                CachedChecksums {
+                    size,
+                    modified,
+                    md5: md5.to_string(),
+                    sha256: sha256.to_string(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_reload() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file = temp_dir.path().join("a.pdf");
+        fs::write(&file, b"content")?;
+        let cache_path = temp_dir.path().join("cache.tsv");
+
+        let md5 = "0".repeat(32);
+        let sha256 = "1".repeat(64);
+        ChecksumCache::open(&cache_path)?.record(&file, &md5, &sha256)?;
+
+        let reopened = ChecksumCache::open(&cache_path)?;
+        assert_eq!(reopened.get(&file), Some((md5, sha256)));
+        assert_eq!(reopened.hits(), 1);
+
+        // A changed file invalidates its cached entry
+        fs::write(&file, b"different content")?;
+        assert_eq!(reopened.get(&file), None);
+
+        Ok(())
+    }
+}
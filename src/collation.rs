@@ -0,0 +1,123 @@
+use anyhow::{Result, bail};
+use feruca::{Collator, Locale, Tailoring};
+use std::cmp::Ordering;
+
+// How file names are ordered in listings. `Bytes` is plain code point order, as with
+// the C/POSIX locale; everything else uses the Unicode Collation Algorithm. Only the
+// CLDR root order and an Arabic-first tailoring are available, not per-language ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortLocale {
+    Bytes,
+    Cldr(Locale),
+}
+
+impl Default for SortLocale {
+    fn default() -> Self {
+        SortLocale::Cldr(Locale::Root)
+    }
+}
+
+impl SortLocale {
+    // The orders `--locale` names directly: `root`, `bytes` or `arabic`
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "root" => SortLocale::Cldr(Locale::Root),
+            "bytes" => SortLocale::Bytes,
+            "arabic" => SortLocale::Cldr(Locale::ArabicScript),
+            _ => bail!("unknown sort order '{}', expected root, bytes or arabic", name),
+        })
+    }
+
+    // Maps a system locale, a POSIX-style name such as `fr_FR.UTF-8` or a BCP 47 tag
+    // such as `de-AT`, onto one of the orders above. Languages other than Arabic get the
+    // root order, which already sorts accented Latin letters next to their base letters.
+    pub fn from_system_name(name: &str) -> Result<Self> {
+        let base = name.split(['.', '@']).next().unwrap_or_default();
+        if base.is_empty()
+            || !base
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!("invalid locale '{}'", name);
+        }
+        let language = base
+            .split(['_', '-'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Ok(match language.as_str() {
+            "c" | "posix" => SortLocale::Bytes,
+            "ar" => SortLocale::Cldr(Locale::ArabicScript),
+            _ => SortLocale::Cldr(Locale::Root),
+        })
+    }
+
+    // Follows the usual precedence of LC_ALL, LC_COLLATE, then LANG
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_COLLATE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_system_name(&value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            SortLocale::Bytes => a.cmp(b),
+            SortLocale::Cldr(locale) => {
+                Collator::new(Tailoring::Cldr(*locale), true, true).collate(a, b)
+            }
+        }
+    }
+
+    pub fn sort_by_key<T>(&self, items: &mut [T], key: impl Fn(&T) -> String) {
+        match self {
+            SortLocale::Bytes => items.sort_by_key(&key),
+            SortLocale::Cldr(locale) => {
+                let mut collator = Collator::new(Tailoring::Cldr(*locale), true, true);
+                items.sort_by(|a, b| collator.collate(&key(a), &key(b)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accented_names_sort_with_their_base_letter() -> Result<()> {
+        let mut names = vec!["zebra.txt", "élan.txt", "Apple.txt", "Éclair.txt", "eagle.txt"];
+
+        SortLocale::from_system_name("fr_FR.UTF-8")?.sort_by_key(&mut names, |n| n.to_string());
+        assert_eq!(
+            names,
+            vec!["Apple.txt", "eagle.txt", "Éclair.txt", "élan.txt", "zebra.txt"]
+        );
+
+        SortLocale::parse("bytes")?.sort_by_key(&mut names, |n| n.to_string());
+        assert_eq!(
+            names,
+            vec!["Apple.txt", "eagle.txt", "zebra.txt", "Éclair.txt", "élan.txt"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_locale_names() -> Result<()> {
+        assert_eq!(SortLocale::parse("Root")?, SortLocale::Cldr(Locale::Root));
+        assert_eq!(SortLocale::parse("arabic")?, SortLocale::Cldr(Locale::ArabicScript));
+        // Language names would promise tailorings that do not exist
+        assert!(SortLocale::parse("sv_SE").is_err());
+        assert!(SortLocale::parse("C").is_err());
+
+        assert_eq!(SortLocale::from_system_name("POSIX")?, SortLocale::Bytes);
+        assert_eq!(SortLocale::from_system_name("ar-EG")?, SortLocale::Cldr(Locale::ArabicScript));
+        assert_eq!(SortLocale::from_system_name("de_AT@euro")?, SortLocale::Cldr(Locale::Root));
+        assert!(SortLocale::from_system_name("").is_err());
+        assert!(SortLocale::from_system_name("en US").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::categories::CategorySet;
+use crate::types::FileCategory;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// A config kept at the root of the folder being organized, used instead of the user's
+pub const FOLDER_CONFIG_FILE_NAME: &str = "desktidy.toml";
+
+// User settings read from `~/.config/desktidy/config.toml`. `[categories]` maps folder
+// names to extensions and is merged over the built-in mapping, with user entries winning:
+//
+//     [categories]
+//     Design = ["sketch", "fig"]
+//     Downloads = ["torrent"]
+//
+// `[destinations]` sends a category somewhere other than its folder at the root.
+// Relative paths are taken from the folder being organized:
+//
+//     [destinations]
+//     Images = "/mnt/photos"
+//     Videos = "/mnt/media/videos"
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub categories: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub destinations: BTreeMap<String, PathBuf>,
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Config = toml::from_str(content)?;
+        let mut owners: HashMap<String, &str> = HashMap::new();
+        for (name, extensions) in &config.categories {
+            for ext in extensions {
+                let ext = ext.trim_start_matches('.').to_lowercase();
+                if let Some(other) = owners.insert(ext.clone(), name) {
+                    bail!("extension '{}' is listed under both {} and {}", ext, other, name);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    // `$XDG_CONFIG_HOME/desktidy/config.toml`, falling back to `~/.config`
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("desktidy").join("config.toml"))
+    }
+
+    // The config at the default path, or an empty one when there is no such file
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::from_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    // The folder's own `desktidy.toml` when it has one, otherwise the default config
+    pub fn load_for_folder(folder: &Path) -> Result<Self> {
+        let path = folder.join(FOLDER_CONFIG_FILE_NAME);
+        match path.is_file() {
+            true => Self::from_file(&path),
+            false => Self::load_default(),
+        }
+    }
+
+    // The `[destinations]` folders by category, with relative paths resolved against `folder`
+    // and `..` applied, so planned moves name the folder files actually go to
+    pub fn category_roots(
+        &self,
+        categories: &CategorySet,
+        folder: &Path,
+    ) -> Result<HashMap<FileCategory, PathBuf>> {
+        let mut roots = HashMap::new();
+        for (name, root) in &self.destinations {
+            let Some(category) = categories
+                .order()
+                .into_iter()
+                .find(|c| c.as_str().eq_ignore_ascii_case(name))
+            else {
+                bail!("config [destinations] names unknown category {}", name);
+            };
+            let mut resolved = PathBuf::new();
+            for component in folder.join(root).components() {
+                match component {
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    Component::CurDir => {}
+                    other => resolved.push(other),
+                }
+            }
+            roots.insert(category, resolved);
+        }
+        Ok(roots)
+    }
+
+    pub fn apply(&self, categories: &CategorySet) -> Result<CategorySet> {
+        categories
+            .with_overrides(&self.categories)
+            .context("config [categories] conflicts with the category set")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_file_merges_over_builtins() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[categories]\nDesign = [\"sketch\"]\nDownloads = [\"torrent\", \"zip\"]\n")?;
+
+        let categories = Config::from_file(&path)?.apply(CategorySet::builtin())?;
+        let downloads = FileCategory::Custom("Downloads".to_string());
+        assert_eq!(
+            categories.category_for_extension("sketch"),
+            Some(FileCategory::Custom("Design".to_string()))
+        );
+        assert_eq!(categories.category_for_extension("zip"), Some(downloads));
+        assert_eq!(categories.category_for_extension("pdf"), Some(FileCategory::PDFs));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config_rejected() {
+        assert!(Config::parse("[categories]\nA = [\"x\"]\nB = [\".X\"]\n").is_err());
+        assert!(Config::parse("[options]\nverbose = true\n").is_err());
+        assert!(Config::parse("").is_ok_and(|c| c.categories.is_empty()));
+    }
+
+    #[test]
+    fn test_destinations_resolve_to_category_roots() -> Result<()> {
+        let config = Config::parse(
+            "[destinations]\nimages = \"/mnt/photos\"\nVideos = \"media\"\nAudio = \"../music\"\n",
+        )?;
+        let roots = config.category_roots(CategorySet::builtin(), Path::new("/desk"))?;
+        assert_eq!(roots[&FileCategory::Images], PathBuf::from("/mnt/photos"));
+        assert_eq!(roots[&FileCategory::Videos], PathBuf::from("/desk/media"));
+        assert_eq!(roots[&FileCategory::Audio], PathBuf::from("/music"));
+        assert_eq!(roots.len(), 3);
+
+        let config = Config::parse("[destinations]\nPhotos = \"/mnt/photos\"\n")?;
+        assert!(config.category_roots(CategorySet::builtin(), Path::new("/desk")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CivilDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl CivilDateTime {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms (UTC, proleptic Gregorian)
+    pub fn from_unix(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let rem = secs.rem_euclid(86_400);
+
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (rem / 3_600) as u32,
+            minute: (rem % 3_600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+        Self::from_unix(secs)
+    }
+
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    pub fn to_utc_string(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DateGranularity {
+    Decade,
+    Year,
+    Month,
+    Day,
+}
+
+impl DateGranularity {
+    pub fn format(&self) -> DateFormat {
+        let pattern = match self {
+            DateGranularity::Decade => "%Ds",
+            DateGranularity::Year => "%Y",
+            DateGranularity::Month => "%Y/%m",
+            DateGranularity::Day => "%Y/%m/%d",
+        };
+        DateFormat(pattern.to_string())
+    }
+}
+
+// A validated folder pattern: %Y year, %m month, %d day, %D decade (e.g. 1990), %% literal.
+// `/` separates nested folders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat(String);
+
+impl DateFormat {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if pattern.trim().is_empty() {
+            bail!("date format must not be empty");
+        }
+        if pattern.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            bail!("date format '{}' contains an empty or relative folder name", pattern);
+        }
+        if pattern.contains('\\') {
+            bail!("date format '{}' must use '/' to separate folders", pattern);
+        }
+
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                match chars.next() {
+                    Some('Y' | 'm' | 'd' | 'D' | '%') => {}
+                    Some(other) => bail!("unknown date format token '%{}'", other),
+                    None => bail!("date format '{}' ends with a lone '%'", pattern),
+                }
+            }
+        }
+        Ok(Self(pattern.to_string()))
+    }
+
+    pub fn render(&self, date: &CivilDateTime) -> String {
+        let mut out = String::new();
+        let mut chars = self.0.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&format!("{:04}", date.year)),
+                Some('m') => out.push_str(&format!("{:02}", date.month)),
+                Some('d') => out.push_str(&format!("{:02}", date.day)),
+                Some('D') => out.push_str(&format!("{:04}", date.year - date.year.rem_euclid(10))),
+                Some('%') => out.push('%'),
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+// An age such as "12h", "30d" or "2w"
+pub fn parse_age(age: &str) -> Result<Duration> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (count, unit) = age.split_at(split);
+    let Ok(count) = count.parse::<u64>() else {
+        bail!("age '{}' must start with a whole number", age);
+    };
+    let unit_secs = match unit.to_lowercase().as_str() {
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => bail!("age '{}' must end in h, d or w", age),
+    };
+    Ok(Duration::from_secs(count.saturating_mul(unit_secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_unix() {
+        assert_eq!(
+            CivilDateTime::from_unix(0).to_utc_string(),
+            "1970-01-01 00:00:00 UTC"
+        );
+        // 2023-06-15 12:34:56 UTC
+        let dt = CivilDateTime::from_unix(1_686_832_496);
+        assert_eq!((dt.year, dt.month, dt.day), (2023, 6, 15));
+        assert_eq!((dt.hour, dt.minute, dt.second), (12, 34, 56));
+        // Leap day
+        let dt = CivilDateTime::from_unix(951_782_400);
+        assert_eq!((dt.year, dt.month, dt.day), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_date_format_render() -> Result<()> {
+        let date = CivilDateTime::from_unix(1_686_832_496);
+        assert_eq!(DateFormat::parse("%Y/%m")?.render(&date), "2023/06");
+        assert_eq!(DateGranularity::Decade.format().render(&date), "2020s");
+        assert_eq!(DateFormat::parse("Q-%Y-%d%%")?.render(&date), "Q-2023-15%");
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_format_validation() {
+        assert!(DateFormat::parse("%Y/%q").is_err());
+        assert!(DateFormat::parse("%Y%").is_err());
+        assert!(DateFormat::parse("../%Y").is_err());
+        assert!(DateFormat::parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_age() -> Result<()> {
+        assert_eq!(parse_age("30d")?, Duration::from_secs(30 * 86_400));
+        assert_eq!(parse_age("2W")?, Duration::from_secs(14 * 86_400));
+        assert_eq!(parse_age("12h")?, Duration::from_secs(12 * 3_600));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("1.5d").is_err());
+        assert!(parse_age("3y").is_err());
+        Ok(())
+    }
+}
@@ -1,86 +1,1083 @@
-use crate::types::{DuplicateGroup, FileCategory, FileEntry};
-use comfy_table::presets::UTF8_FULL;
+use crate::categories::CategorySet;
+use crate::collation::SortLocale;
+use crate::types::{
+    DuplicateGroup, FileCategory, FileEntry, OrganizationSummary, PlannedAction, Warning,
+};
+use clap::ValueEnum;
+use comfy_table::presets::{ASCII_FULL, NOTHING, UTF8_FULL};
 use comfy_table::Table;
-use std::path::Path;
+use once_cell::sync::Lazy;
+use owo_colors::{AnsiColors, OwoColorize};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-pub struct DisplayFormatter;
+// A folder (with children) or a file in the projected tree
+#[derive(Default)]
+struct TreeNode {
+    children: BTreeMap<String, TreeNode>,
+    note: Option<String>,
+}
+
+impl TreeNode {
+    fn render(&self, prefix: &str, lines: &mut Vec<String>) {
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            let label = match (&child.note, child.children.is_empty()) {
+                (_, false) => format!("{}/", name),
+                (Some(note), true) => format!("{} ({})", name, note),
+                (None, true) => name.clone(),
+            };
+            lines.push(format!("{}{} {}", prefix, if last { "└──" } else { "├──" }, label));
+            child.render(&format!("{}{}", prefix, if last { "    " } else { "│   " }), lines);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TableStyle {
+    #[default]
+    Full,
+    Ascii,
+    Plain,
+}
+
+impl TableStyle {
+    fn preset(&self) -> &'static str {
+        match self {
+            TableStyle::Full => UTF8_FULL,
+            TableStyle::Ascii => ASCII_FULL,
+            TableStyle::Plain => NOTHING,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ActionsMode {
+    #[default]
+    All,
+    Summary,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    // The planned actions of a dry run, one per line like a diff
+    Diff,
+}
+
+// Each pattern captures the verb and, where there is one, the destination; a matching
+// action is counted under "<verb> N <noun> <preposition> <destination>"
+static ACTION_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (r"^(Moved|Would move) duplicate .+ to (Duplicates) folder", "duplicates to"),
+        (r"^(Linked|Would link) duplicate .+ in (Duplicates) folder", "duplicates in"),
+        (r"^(Moved|Would move) .+ to (.+) folder$", "files to"),
+        (r"^(Archived|Would archive) .+ into (.+)$", "files into"),
+        (r"^(Deleted|Would delete) .+ \((matched hash denylist)\)$", "files that"),
+    ]
+    .into_iter()
+    .map(|(pattern, noun)| (Regex::new(pattern).expect("valid action pattern"), noun))
+    .collect()
+});
+
+#[derive(Default)]
+pub struct DisplayFormatter {
+    table_style: TableStyle,
+    summary_only: bool,
+    duplicates_relative_to: Option<PathBuf>,
+    categories: CategorySet,
+    chart: bool,
+    actions_mode: ActionsMode,
+    duplicates_tree: bool,
+    locale: SortLocale,
+    changes_only: bool,
+    file_sizes: HashMap<PathBuf, u64>,
+}
+
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
 
 impl DisplayFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_table_style(mut self, style: TableStyle) -> Self {
+        self.table_style = style;
+        self
+    }
+
+    pub fn with_summary_only(mut self, enabled: bool) -> Self {
+        self.summary_only = enabled;
+        self
+    }
+
+    pub fn with_duplicates_relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.duplicates_relative_to = base.map(|b| b.canonicalize().unwrap_or(b));
+        self
+    }
+
+    // Categories listed in the table, in order
+    pub fn with_categories(mut self, categories: CategorySet) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    pub fn with_actions_mode(mut self, mode: ActionsMode) -> Self {
+        self.actions_mode = mode;
+        self
+    }
+
+    // Collapses per-file actions into one count per kind and destination, in order of
+    // first appearance; actions that fit no pattern are kept as they are
+    pub fn summarize_actions(actions: &[String]) -> Vec<String> {
+        let mut order: Vec<String> = Vec::new();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut templates: HashMap<String, (String, &str, String)> = HashMap::new();
+
+        for action in actions {
+            let matched = ACTION_PATTERNS.iter().find_map(|(regex, noun)| {
+                regex
+                    .captures(action)
+                    .map(|c| (c[1].to_string(), *noun, c[2].to_string()))
+            });
+            let key = match &matched {
+                Some((verb, noun, target)) => format!("{}\0{}\0{}", verb, noun, target),
+                None => action.clone(),
+            };
+            if !counts.contains_key(&key) {
+                order.push(key.clone());
+                if let Some(template) = matched {
+                    templates.insert(key.clone(), template);
+                }
+            }
+            *counts.entry(key).or_default() += 1;
+        }
+
+        order
+            .into_iter()
+            .map(|key| match templates.get(&key) {
+                Some((verb, noun, target)) => {
+                    format!("{} {} {} {}", verb, counts[&key], noun, target)
+                }
+                None if counts[&key] > 1 => format!("{} (x{})", key, counts[&key]),
+                None => key,
+            })
+            .collect()
+    }
+
+    pub fn with_chart(mut self, enabled: bool) -> Self {
+        self.chart = enabled;
+        self
+    }
+
+    fn terminal_width() -> usize {
+        crossterm::terminal::size()
+            .map(|(columns, _)| columns as usize)
+            .ok()
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+    }
+
+    fn ansi_color(category: &FileCategory) -> AnsiColors {
+        match category.color_code() {
+            "blue" => AnsiColors::Blue,
+            "red" => AnsiColors::Red,
+            "magenta" => AnsiColors::Magenta,
+            "green" => AnsiColors::Green,
+            "cyan" => AnsiColors::Cyan,
+            "yellow" => AnsiColors::Yellow,
+            _ => AnsiColors::White,
+        }
+    }
+
+    // One bar per category, scaled so the largest category fills the available width
+    pub fn category_chart(&self, entries: &[FileEntry], width: usize, color: bool) -> String {
+        let counts: Vec<(FileCategory, usize)> = self
+            .categories
+            .order()
+            .into_iter()
+            .map(|c| {
+                let count = entries.iter().filter(|e| e.category == c).count();
+                (c, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        let Some(max) = counts.iter().map(|(_, count)| *count).max() else {
+            return String::new();
+        };
+
+        let label_width = counts.iter().map(|(c, _)| c.as_str().len()).max().unwrap_or(0);
+        let count_width = max.to_string().len();
+        let bar_width = width.saturating_sub(label_width + count_width + 2).max(10);
+
+        counts
+            .iter()
+            .map(|(category, count)| {
+                let length = ((*count * bar_width) as f64 / max as f64).round().max(1.0) as usize;
+                let bar = "█".repeat(length);
+                let bar = if color {
+                    bar.color(Self::ansi_color(category)).to_string()
+                } else {
+                    bar
+                };
+                format!(
+                    "{:<label_width$} {} {}",
+                    category.as_str(),
+                    bar,
+                    count,
+                    label_width = label_width
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn duplicates_report(&self, duplicates: &[DuplicateGroup], folder_path: &Path) -> String {
+        let base = self.duplicates_relative_to.as_deref().unwrap_or(folder_path);
+        let mut lines = vec!["[!] Duplicate Files Found:".to_string()];
+        for dup_group in duplicates {
+            lines.push(format!(
+                "[!] Group {}: {} files ({})",
+                dup_group.checksum_key.get(..8).unwrap_or(&dup_group.checksum_key),
+                dup_group.files.len(),
+                if dup_group.confirmed { "confirmed" } else { "probable" }
+            ));
+            for file in &dup_group.files {
+                lines.push(format!(
+                    "[!]   - {}",
+                    file.path.strip_prefix(base).unwrap_or(&file.path).display()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn prefix_matches_report(
+        &self,
+        groups: &[DuplicateGroup],
+        length: u64,
+        folder_path: &Path,
+    ) -> String {
+        let base = self.duplicates_relative_to.as_deref().unwrap_or(folder_path);
+        let mut lines = vec![format!("[~] Files Sharing Their First {} Bytes:", length)];
+        if groups.is_empty() {
+            lines.push("[~]   (none)".to_string());
+        }
+        for (i, group) in groups.iter().enumerate() {
+            lines.push(format!("[~] Prefix group {}: {} files", i + 1, group.files.len()));
+            for file in &group.files {
+                lines.push(format!(
+                    "[~]   - {}",
+                    file.path.strip_prefix(base).unwrap_or(&file.path).display()
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn diff_report(&self, actions: &[PlannedAction], folder_path: &Path) -> String {
+        let relative = |path: &Path| path.strip_prefix(folder_path).unwrap_or(path).display().to_string();
+        if actions.is_empty() {
+            return "[~] Nothing to change.".to_string();
+        }
+        actions
+            .iter()
+            .map(|action| match action {
+                PlannedAction::CreateDirectory { path } => format!("+ create {}/", relative(path)),
+                PlannedAction::MoveFile { from, to } => {
+                    format!("→ move {} -> {}", relative(from), relative(to))
+                }
+                PlannedAction::MoveDuplicate { from, to, original } => format!(
+                    "→ move {} -> {} (duplicate of {})",
+                    relative(from),
+                    relative(to),
+                    relative(original)
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // The folder tree a dry run would leave behind, with each planned file nested under
+    // its destination folder. Files that stay where they are are not shown.
+    pub fn tree_report(&self, actions: &[PlannedAction], folder_path: &Path) -> String {
+        let mut root = TreeNode::default();
+        for action in actions {
+            let (to, note) = match action {
+                PlannedAction::CreateDirectory { .. } => continue,
+                PlannedAction::MoveFile { to, .. } => (to, None),
+                PlannedAction::MoveDuplicate { to, original, .. } => (
+                    to,
+                    Some(format!(
+                        "duplicate of {}",
+                        original.strip_prefix(folder_path).unwrap_or(original).display()
+                    )),
+                ),
+            };
+            let relative = to.strip_prefix(folder_path).unwrap_or(to);
+            let mut node = &mut root;
+            for component in relative.components() {
+                node = node
+                    .children
+                    .entry(component.as_os_str().to_string_lossy().to_string())
+                    .or_default();
+            }
+            node.note = note;
+        }
+        if root.children.is_empty() {
+            return "[~] Nothing to change.".to_string();
+        }
+
+        let name = folder_path.file_name().unwrap_or(folder_path.as_os_str());
+        let mut lines = vec![format!("{}/", name.to_string_lossy())];
+        root.render("", &mut lines);
+        lines.join("\n")
+    }
+
+    pub fn display_tree(&self, actions: &[PlannedAction], folder_path: &Path) {
+        println!("\n[*] Folder tree after organizing:");
+        println!("{}", self.tree_report(actions, folder_path));
+    }
+
+    pub fn warnings_report(&self, warnings: &[Warning]) -> String {
+        let mut lines = vec![format!("[!] Warnings ({}):", warnings.len())];
+        lines.extend(warnings.iter().map(|w| format!("[!]   - {}", w)));
+        lines.join("\n")
+    }
+
+    // Compact JSON for scripts; `skipped` counts unsupported files plus files left in place
+    pub fn porcelain_summary(
+        &self,
+        summary: &OrganizationSummary,
+        unsupported_files: usize,
+        warnings: &[Warning],
+    ) -> String {
+        let skipped = unsupported_files
+            + warnings
+                .iter()
+                .filter(|w| matches!(w, Warning::Skipped(..)))
+                .count();
+        let errors = warnings
+            .iter()
+            .filter(|w| matches!(w, Warning::Unreadable(_) | Warning::MoveFailed(..)))
+            .count();
+        let duplicates: usize = summary
+            .duplicates_found
+            .iter()
+            .map(|g| g.files.len() - 1)
+            .sum();
+
+        serde_json::json!({
+            "moved": summary.files_moved,
+            "copied": summary.files_copied,
+            "skipped": skipped,
+            "duplicates": duplicates,
+            "bytes_moved": summary.bytes_moved,
+            "errors": errors,
+        })
+        .to_string()
+    }
+
+    pub fn with_locale(mut self, locale: SortLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    // Leave out files and categories this run did not touch
+    pub fn with_changes_only(mut self, enabled: bool) -> Self {
+        self.changes_only = enabled;
+        self
+    }
+
+    // The entries and duplicate copies among the given changed paths; a group keeps its
+    // original and only the copies that were moved
+    pub fn changed_view(
+        entries: &[FileEntry],
+        duplicates: &[DuplicateGroup],
+        changed: &[PathBuf],
+    ) -> (Vec<FileEntry>, Vec<DuplicateGroup>) {
+        let changed: HashSet<&PathBuf> = changed.iter().collect();
+        let entries = entries
+            .iter()
+            .filter(|e| changed.contains(&e.path))
+            .cloned()
+            .collect();
+        let duplicates = duplicates
+            .iter()
+            .filter_map(|group| {
+                let copies: Vec<FileEntry> = group.files[1..]
+                    .iter()
+                    .filter(|e| changed.contains(&e.path))
+                    .cloned()
+                    .collect();
+                (!copies.is_empty()).then(|| {
+                    DuplicateGroup::new(
+                        group.checksum_key.clone(),
+                        std::iter::once(group.files[0].clone()).chain(copies).collect(),
+                        group.confirmed,
+                    )
+                })
+            })
+            .collect();
+        (entries, duplicates)
+    }
+
+    // Sizes measured before organizing, for entries whose files have since been moved
+    pub fn with_file_sizes(mut self, file_sizes: HashMap<PathBuf, u64>) -> Self {
+        self.file_sizes = file_sizes;
+        self
+    }
+
+    fn file_size(&self, entry: &FileEntry) -> Option<u64> {
+        self.file_sizes
+            .get(&entry.path)
+            .copied()
+            .or_else(|| entry.meta().ok().map(|m| m.size))
+    }
+
+    // The same information as the text summary, for scripts and other front ends
+    pub fn to_json(
+        &self,
+        entries: &[FileEntry],
+        duplicates: &[DuplicateGroup],
+        actions: &[String],
+        dry_run: bool,
+    ) -> serde_json::Value {
+        let mut categories = serde_json::Map::new();
+        let mut counts = serde_json::Map::new();
+        for category in self.categories.order() {
+            let files: Vec<serde_json::Value> = entries
+                .iter()
+                .filter(|e| e.category == category)
+                .map(|e| {
+                    serde_json::json!({
+                        "path": e.path.display().to_string(),
+                        "size": self.file_size(e),
+                    })
+                })
+                .collect();
+            if !files.is_empty() {
+                counts.insert(category.as_str().to_string(), files.len().into());
+                categories.insert(category.as_str().to_string(), files.into());
+            }
+        }
+        let duplicates: Vec<serde_json::Value> = duplicates
+            .iter()
+            .map(|group| {
+                serde_json::json!({
+                    "checksum": group.checksum_key,
+                    "confirmed": group.confirmed,
+                    "files": group
+                        .files
+                        .iter()
+                        .map(|e| e.path.display().to_string())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "dry_run": dry_run,
+            "counts": counts,
+            "total_bytes": entries.iter().map(|e| e.size_bytes).sum::<u64>(),
+            "categories": categories,
+            "duplicates": duplicates,
+            "actions": actions,
+        })
+    }
+
+    // The duplicate groups on their own, for dashboards: each file is marked as the
+    // kept original or not, and a group's reclaimable bytes are the sizes of the
+    // copies that are not kept
+    pub fn dedup_report_json(&self, duplicates: &[DuplicateGroup]) -> serde_json::Value {
+        let groups: Vec<serde_json::Value> = duplicates
+            .iter()
+            .map(|group| {
+                let files: Vec<serde_json::Value> = group
+                    .files
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| {
+                        serde_json::json!({
+                            "path": e.path.display().to_string(),
+                            "size": e.size_bytes,
+                            "kept": i == 0,
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "checksum": group.checksum_key,
+                    "confirmed": group.confirmed,
+                    "files": files,
+                    "reclaimable_bytes": group.files[1..].iter().map(|e| e.size_bytes).sum::<u64>(),
+                })
+            })
+            .collect();
+        let reclaimable: u64 = groups.iter().filter_map(|g| g["reclaimable_bytes"].as_u64()).sum();
+
+        serde_json::json!({
+            "groups": groups,
+            "reclaimable_bytes": reclaimable,
+        })
+    }
+
+    pub fn with_duplicates_tree(mut self, enabled: bool) -> Self {
+        self.duplicates_tree = enabled;
+        self
+    }
+
+    // Groups the report by the kept original's folder, listing each original with the
+    // locations of its copies nested underneath
+    pub fn duplicates_tree_report(&self, duplicates: &[DuplicateGroup], folder_path: &Path) -> String {
+        let base = self.duplicates_relative_to.as_deref().unwrap_or(folder_path);
+        let relative = |path: &Path| path.strip_prefix(base).unwrap_or(path).display().to_string();
+
+        let mut by_folder: BTreeMap<String, Vec<&DuplicateGroup>> = BTreeMap::new();
+        for group in duplicates {
+            let folder = group.files[0]
+                .path
+                .parent()
+                .map(&relative)
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            by_folder.entry(folder).or_default().push(group);
+        }
+
+        let mut lines = vec!["[!] Duplicate Files by Original Location:".to_string()];
+        for (folder, groups) in by_folder {
+            lines.push(format!("[!] {}/", folder));
+            for (i, group) in groups.iter().enumerate() {
+                let last_group = i + 1 == groups.len();
+                let original = group.files[0].path.file_name().unwrap_or_default().to_string_lossy();
+                lines.push(format!(
+                    "[!] {} {}",
+                    if last_group { "└──" } else { "├──" },
+                    original
+                ));
+                let copies = &group.files[1..];
+                for (j, copy) in copies.iter().enumerate() {
+                    lines.push(format!(
+                        "[!] {}   {} {}",
+                        if last_group { " " } else { "│" },
+                        if j + 1 == copies.len() { "└──" } else { "├──" },
+                        relative(&copy.path)
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn summary_line(
+        &self,
+        entries: &[FileEntry],
+        duplicates: &[DuplicateGroup],
+        dry_run: bool,
+    ) -> String {
+        let category_count = self
+            .categories
+            .order()
+            .iter()
+            .filter(|c| entries.iter().any(|e| e.category == **c))
+            .count();
+        let duplicate_count: usize = duplicates.iter().map(|g| g.files.len() - 1).sum();
+
+        format!(
+            "{} {} files into {} categories, found {} duplicates.",
+            if dry_run { "Would organize" } else { "Organized" },
+            entries.len(),
+            category_count,
+            duplicate_count
+        )
+    }
+
     pub fn display_summary(
+        &self,
         entries: &[FileEntry],
         duplicates: &[DuplicateGroup],
         actions: &[String],
+        warnings: &[Warning],
         dry_run: bool,
         folder_path: &Path,
     ) {
+        if self.summary_only {
+            println!("{}", self.summary_line(entries, duplicates, dry_run));
+            return;
+        }
+
         if dry_run {
             println!("\n[*] Analysis Mode (No files will be moved)");
         }
 
+        if self.changes_only && entries.is_empty() && duplicates.is_empty() && actions.is_empty() {
+            println!("\n[~] No changes in this run.");
+            if !warnings.is_empty() {
+                println!("\n{}", self.warnings_report(warnings));
+            }
+            return;
+        }
+
         // File categories summary
-        let mut table = Table::new();
-        table.load_preset(UTF8_FULL);
-        table.set_header(vec!["Category", "Count", "Files"]);
+        if !(self.changes_only && entries.is_empty()) {
+            println!("\n{}", self.category_table(entries, folder_path));
+        }
+
+        if self.chart {
+            let color = std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+            println!(
+                "\n{}",
+                self.category_chart(entries, Self::terminal_width(), color)
+            );
+        }
+
+        // Duplicates summary
+        if !duplicates.is_empty() {
+            let report = if self.duplicates_tree {
+                self.duplicates_tree_report(duplicates, folder_path)
+            } else {
+                self.duplicates_report(duplicates, folder_path)
+            };
+            println!("\n{}", report);
+            println!("\n[*] {}", Self::wasted_space_line(duplicates));
+        }
+
+        // Actions summary
+        if actions.is_empty() {
+            println!("\n[~] No files found to organize.");
+        } else if self.actions_mode != ActionsMode::None {
+            let action_label = if dry_run {
+                "Proposed Actions"
+            } else {
+                "Actions Taken"
+            };
+            println!("\n[+] {}:", action_label);
+            let lines = match self.actions_mode {
+                ActionsMode::Summary => Self::summarize_actions(actions),
+                _ => actions.to_vec(),
+            };
+            for action in lines {
+                println!("[+] ✓ {}", action);
+            }
+        }
+
+        if !warnings.is_empty() {
+            println!("\n{}", self.warnings_report(warnings));
+        }
+    }
 
-        for category in FileCategory::order() {
-            let files: Vec<_> = entries
+    // "Duplicates: 3 groups, 45.2 MB wasted"
+    pub fn wasted_space_line(duplicates: &[DuplicateGroup]) -> String {
+        format!(
+            "Duplicates: {} group{}, {} wasted",
+            duplicates.len(),
+            if duplicates.len() == 1 { "" } else { "s" },
+            Self::human_size(DuplicateGroup::total_wasted_bytes(duplicates))
+        )
+    }
+
+    // Binary units, with one decimal above bytes: "512 B", "1.5 KB", "2.0 GB"
+    pub fn human_size(bytes: u64) -> String {
+        const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+        if bytes < 1024 {
+            return format!("{} B", bytes);
+        }
+        let mut size = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+
+    pub fn category_table(&self, entries: &[FileEntry], folder_path: &Path) -> Table {
+        let mut table = Table::new();
+        table.load_preset(self.table_style.preset());
+        table.set_header(vec!["Category", "Count", "Size", "Files"]);
+        for category in self.categories.order() {
+            let in_category: Vec<&FileEntry> =
+                entries.iter().filter(|e| e.category == category).collect();
+            let mut files: Vec<String> = in_category
                 .iter()
-                .filter(|e| e.category == category)
+                .map(|f| {
+                    f.path
+                        .strip_prefix(folder_path)
+                        .unwrap_or(&f.path)
+                        .display()
+                        .to_string()
+                })
                 .collect();
 
             if !files.is_empty() {
-                let file_list = files
-                    .iter()
-                    .map(|f| {
-                        f.path
-                            .strip_prefix(folder_path)
-                            .unwrap_or(&f.path)
-                            .display()
-                            .to_string()
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n");
+                self.locale.sort_by_key(&mut files, |name| name.clone());
+                let file_list = files.join("\n");
 
+                let size: u64 = in_category.iter().map(|f| f.size_bytes).sum();
                 table.add_row(vec![
                     category.as_str(),
                     &files.len().to_string(),
+                    &Self::human_size(size),
                     &file_list,
                 ]);
             }
         }
 
-        println!("\n{}", table);
+        table
+    }
+}
 
-        // Duplicates summary
-        if !duplicates.is_empty() {
-            println!("\n[!] Duplicate Files Found:");
-            for dup_group in duplicates {
-                println!("[!] Group {}: {} files", &dup_group.checksum_key[..8], dup_group.files.len());
-                for file in &dup_group.files {
-                    println!(
-                        "[!]   - {}",
-                        file.path
-                            .strip_prefix(folder_path)
-                            .unwrap_or(&file.path)
-                            .display()
-                    );
-                }
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
 
-        // Actions summary
-        if !actions.is_empty() {
-            let action_label = if dry_run {
-                "Proposed Actions"
-            } else {
-                "Actions Taken"
-            };
-            println!("\n[+] {}:", action_label);
-            for action in actions {
-                println!("[+] ✓ {}", action);
+    fn sample_entries(root: &Path) -> Vec<FileEntry> {
+        vec![
+            FileEntry {
+                path: root.join("report.pdf"),
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 1_536,
+            },
+            FileEntry {
+                path: root.join("photo.jpg"),
+                category: FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_ascii_table_style_is_ascii_only() {
+        let root = PathBuf::from("/tmp/desk");
+        let formatter = DisplayFormatter::new().with_table_style(TableStyle::Ascii);
+        let rendered = formatter.category_table(&sample_entries(&root), &root).to_string();
+
+        assert!(rendered.is_ascii());
+        assert!(rendered.contains("report.pdf"));
+    }
+
+    #[test]
+    fn test_category_table_shows_sizes() {
+        let root = PathBuf::from("/tmp/desk");
+        let rendered = DisplayFormatter::new()
+            .category_table(&sample_entries(&root), &root)
+            .to_string();
+
+        assert!(rendered.contains("Size"));
+        assert!(rendered.contains("1.5 KB"));
+        assert!(rendered.contains("0 B"));
+        assert_eq!(DisplayFormatter::human_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn test_wasted_space_line() {
+        let root = PathBuf::from("/tmp/desk");
+        let group = |name: &str| {
+            let files = sample_entries(&root);
+            DuplicateGroup::new(name.to_string(), vec![files[0].clone(), files[0].clone()], true)
+        };
+        let groups = vec![group("a"), group("b")];
+        assert_eq!(
+            DisplayFormatter::wasted_space_line(&groups),
+            "Duplicates: 2 groups, 3.0 KB wasted"
+        );
+        assert_eq!(DisplayFormatter::wasted_space_line(&[]), "Duplicates: 0 groups, 0 B wasted");
+    }
+
+    #[test]
+    fn test_summary_line_counts() {
+        let root = PathBuf::from("/tmp/desk");
+        let entries = sample_entries(&root);
+        let duplicates = vec![DuplicateGroup {
+            checksum_key: "abcdef0123456789".to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
+            files: vec![entries[0].clone(), entries[0].clone(), entries[0].clone()],
+        }];
+
+        let formatter = DisplayFormatter::new().with_summary_only(true);
+        let line = formatter.summary_line(&entries, &duplicates, false);
+
+        assert_eq!(line, "Organized 2 files into 2 categories, found 2 duplicates.");
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn test_duplicates_report_relative_to_base() {
+        let root = PathBuf::from("/tmp/desk");
+        let backup = PathBuf::from("/mnt/backup/photos");
+        let group = DuplicateGroup {
+            checksum_key: "abcdef0123456789".to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
+            files: vec![
+                FileEntry {
+                    path: backup.join("2020").join("beach.jpg"),
+                    category: FileCategory::Images,
+                    content_category: None,
+                    size_bytes: 0,
+                },
+                FileEntry {
+                    path: backup.join("copies").join("beach.jpg"),
+                    category: FileCategory::Images,
+                    content_category: None,
+                    size_bytes: 0,
+                },
+            ],
+        };
+
+        let formatter = DisplayFormatter::new().with_duplicates_relative_to(Some(backup));
+        let report = formatter.duplicates_report(&[group], &root);
+
+        assert!(report.contains("[!]   - 2020/beach.jpg"));
+        assert!(report.contains("[!]   - copies/beach.jpg"));
+        assert!(!report.contains("/mnt/backup"));
+    }
+
+    #[test]
+    fn test_warnings_report_lists_each_warning() {
+        let formatter = DisplayFormatter::new();
+        let warnings = vec![
+            Warning::Unreadable(PathBuf::from("/tmp/desk/broken.pdf")),
+            Warning::Skipped(PathBuf::from("/tmp/desk/a.xlsx"), "ignored extension".to_string()),
+        ];
+
+        let report = formatter.warnings_report(&warnings);
+        assert!(report.starts_with("[!] Warnings (2):"));
+        assert!(report.contains("Could not read /tmp/desk/broken.pdf"));
+        assert!(report.contains("Skipped /tmp/desk/a.xlsx (ignored extension)"));
+    }
+
+    #[test]
+    fn test_category_chart_bars_are_proportional() {
+        let root = PathBuf::from("/tmp/desk");
+        let mut entries = Vec::new();
+        for (count, category) in [(4, FileCategory::Images), (2, FileCategory::PDFs)] {
+            for i in 0..count {
+                entries.push(FileEntry {
+                    path: root.join(format!("{}{}", category.as_str(), i)),
+                    category: category.clone(),
+                    content_category: None,
+                    size_bytes: 0,
+                });
             }
-        } else {
-            println!("\n[~] No files found to organize.");
         }
+
+        // "Images" + space + 32-cell bar + space + "4" fills 41 columns
+        let chart = DisplayFormatter::new().category_chart(&entries, 41, false);
+        let bars: Vec<usize> = chart
+            .lines()
+            .map(|line| line.chars().filter(|c| *c == '█').count())
+            .collect();
+        assert_eq!(bars, vec![16, 32]);
+        assert!(chart.lines().next().unwrap().starts_with("PDFs  "));
+        assert!(!chart.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn test_summarize_actions_counts_per_category() {
+        let actions: Vec<String> = [
+            "Moved a.jpg to Images folder",
+            "Moved b.pdf to PDFs folder",
+            "Moved c.jpg to Images folder",
+            "Moved d.jpg to Images folder",
+            "Moved duplicate e.jpg to Duplicates folder (identical to a.jpg)",
+            "Wrote index for Images folder",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+
+        assert_eq!(
+            DisplayFormatter::summarize_actions(&actions),
+            vec![
+                "Moved 3 files to Images",
+                "Moved 1 files to PDFs",
+                "Moved 1 duplicates to Duplicates",
+                "Wrote index for Images folder",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates_tree_nests_copies_under_originals() {
+        let root = PathBuf::from("/tmp/desk");
+        let entry = |path: &str| FileEntry {
+            path: root.join(path),
+            category: FileCategory::Images,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let groups = vec![
+            DuplicateGroup {
+                checksum_key: "aaaa0000".to_string(),
+                confirmed: true,
+                space_wasted_bytes: 0,
+                files: vec![entry("photos/beach.jpg"), entry("beach.jpg"), entry("old/beach.jpg")],
+            },
+            DuplicateGroup {
+                checksum_key: "bbbb0000".to_string(),
+                confirmed: true,
+                space_wasted_bytes: 0,
+                files: vec![entry("photos/cat.jpg"), entry("cat (1).jpg")],
+            },
+            DuplicateGroup {
+                checksum_key: "cccc0000".to_string(),
+                confirmed: true,
+                space_wasted_bytes: 0,
+                files: vec![entry("logo.png"), entry("web/logo.png")],
+            },
+        ];
+
+        let report = DisplayFormatter::new().duplicates_tree_report(&groups, &root);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "[!] Duplicate Files by Original Location:",
+                "[!] ./",
+                "[!] └── logo.png",
+                "[!]     └── web/logo.png",
+                "[!] photos/",
+                "[!] ├── beach.jpg",
+                "[!] │   ├── beach.jpg",
+                "[!] │   └── old/beach.jpg",
+                "[!] └── cat.jpg",
+                "[!]     └── cat (1).jpg",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_view_keeps_only_changed_files() {
+        let root = PathBuf::from("/tmp/desk");
+        let entries = sample_entries(&root);
+        let copy = FileEntry {
+            path: root.join("report (1).pdf"),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let groups = vec![DuplicateGroup {
+            checksum_key: "abcd1234".to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
+            files: vec![entries[0].clone(), copy.clone()],
+        }];
+
+        let (changed_entries, changed_groups) =
+            DisplayFormatter::changed_view(&entries, &groups, &[entries[1].path.clone()]);
+        assert_eq!(changed_entries.len(), 1);
+        assert_eq!(changed_entries[0].path, entries[1].path);
+        assert!(changed_groups.is_empty());
+
+        // A second run that changed nothing reports nothing
+        let (none, no_groups) = DisplayFormatter::changed_view(&entries, &groups, &[]);
+        assert!(none.is_empty() && no_groups.is_empty());
+
+        let (_, changed_groups) = DisplayFormatter::changed_view(&entries, &groups, &[copy.path]);
+        assert_eq!(changed_groups[0].files.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_report_lists_planned_actions() {
+        let root = PathBuf::from("/tmp/desk");
+        let actions = vec![
+            PlannedAction::CreateDirectory { path: root.join("Images") },
+            PlannedAction::MoveFile { from: root.join("a.jpg"), to: root.join("Images/a.jpg") },
+            PlannedAction::MoveDuplicate {
+                from: root.join("b.jpg"),
+                to: root.join("Duplicates/b.jpg"),
+                original: root.join("a.jpg"),
+            },
+        ];
+
+        let report = DisplayFormatter::new().diff_report(&actions, &root);
+        assert_eq!(
+            report.lines().collect::<Vec<_>>(),
+            vec![
+                "+ create Images/",
+                "→ move a.jpg -> Images/a.jpg",
+                "→ move b.jpg -> Duplicates/b.jpg (duplicate of a.jpg)",
+            ]
+        );
+        assert_eq!(DisplayFormatter::new().diff_report(&[], &root), "[~] Nothing to change.");
+    }
+
+    #[test]
+    fn test_tree_report_nests_files_under_destinations() {
+        let root = PathBuf::from("/tmp/desk");
+        let actions = vec![
+            PlannedAction::CreateDirectory { path: root.join("Images") },
+            PlannedAction::MoveFile { from: root.join("a.jpg"), to: root.join("Images/2024/a.jpg") },
+            PlannedAction::CreateDirectory { path: root.join("PDFs") },
+            PlannedAction::MoveFile { from: root.join("b.pdf"), to: root.join("PDFs/b.pdf") },
+            PlannedAction::MoveDuplicate {
+                from: root.join("c.pdf"),
+                to: root.join("Duplicates/c.pdf"),
+                original: root.join("b.pdf"),
+            },
+        ];
+
+        let report = DisplayFormatter::new().tree_report(&actions, &root);
+        assert_eq!(
+            report.lines().collect::<Vec<_>>(),
+            vec![
+                "desk/",
+                "├── Duplicates/",
+                "│   └── c.pdf (duplicate of b.pdf)",
+                "├── Images/",
+                "│   └── 2024/",
+                "│       └── a.jpg",
+                "└── PDFs/",
+                "    └── b.pdf",
+            ]
+        );
+        assert_eq!(DisplayFormatter::new().tree_report(&[], &root), "[~] Nothing to change.");
+    }
+
+    #[test]
+    fn test_to_json_structure() {
+        let root = PathBuf::from("/tmp/desk");
+        let entries = sample_entries(&root);
+        let groups = vec![DuplicateGroup {
+            checksum_key: "abcd1234".to_string(),
+            confirmed: false,
+            space_wasted_bytes: 0,
+            files: entries.clone(),
+        }];
+        let sizes = HashMap::from([(root.join("report.pdf"), 2048)]);
+        let actions = vec!["Moved photo.jpg to Images folder".to_string()];
+
+        let json = DisplayFormatter::new()
+            .with_file_sizes(sizes)
+            .to_json(&entries, &groups, &actions, true);
+
+        assert_eq!(json["dry_run"], true);
+        assert_eq!(json["counts"]["PDFs"], 1);
+        assert_eq!(json["categories"]["PDFs"][0]["path"], "/tmp/desk/report.pdf");
+        assert_eq!(json["categories"]["PDFs"][0]["size"], 2048);
+        // Not on disk and not measured beforehand
+        assert!(json["categories"]["Images"][0]["size"].is_null());
+        assert_eq!(json["duplicates"][0]["confirmed"], false);
+        assert_eq!(json["duplicates"][0]["files"].as_array().unwrap().len(), 2);
+        assert_eq!(json["actions"][0], "Moved photo.jpg to Images folder");
+    }
+
+    #[test]
+    fn test_plain_table_style_has_no_borders() {
+        let root = PathBuf::from("/tmp/desk");
+        let formatter = DisplayFormatter::new().with_table_style(TableStyle::Plain);
+        let rendered = formatter.category_table(&sample_entries(&root), &root).to_string();
+
+        assert!(!rendered.contains('|'));
+        assert!(!rendered.contains('│'));
+        assert!(rendered.contains("photo.jpg"));
     }
 }
@@ -0,0 +1,170 @@
+use crate::duplicate_finder::DuplicateFinder;
+use crate::file_analyzer::FileAnalyzer;
+use crate::organizer::Organizer;
+use crate::undo::UNDO_LOG_FILE_NAME;
+use crate::types::FileCategory;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LARGE_FILE_COUNT: usize = 10_000;
+
+pub struct DoctorReport {
+    pub folder_path: PathBuf,
+    pub writable: bool,
+    pub free_bytes: Option<u64>,
+    pub total_files: usize,
+    pub planned_moves: usize,
+    pub undo_journal_exists: bool,
+    pub issues: Vec<String>,
+}
+
+pub struct Doctor;
+
+impl Doctor {
+    // Runs the planning phase plus environment checks without moving anything
+    pub fn run(folder_path: &Path, verbose: bool) -> Result<DoctorReport> {
+        let folder_path = folder_path.canonicalize()?;
+        let mut issues = Vec::new();
+
+        let writable = Self::check_writable(&folder_path);
+        if !writable {
+            issues.push("Folder is not writable; files cannot be moved".to_string());
+        }
+
+        for category in FileCategory::order() {
+            let category_folder = folder_path.join(category.as_str());
+            if category_folder.is_file() {
+                issues.push(format!(
+                    "A file named {} exists where the category folder would go",
+                    category.as_str()
+                ));
+            } else if category_folder.is_dir() && !Self::check_writable(&category_folder) {
+                issues.push(format!("Category folder {} is read-only", category.as_str()));
+            }
+        }
+
+        let analysis = FileAnalyzer::new(folder_path.clone(), verbose).analyze()?;
+        if analysis.total_files > LARGE_FILE_COUNT {
+            issues.push(format!(
+                "{} files in the folder; hashing may take a while",
+                analysis.total_files
+            ));
+        }
+
+        let entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+        let duplicates = DuplicateFinder::new(verbose).find_duplicates(&entries)?;
+        let plan = Organizer::new(folder_path.clone(), verbose).organize_files(
+            &entries,
+            &duplicates,
+            true,
+        )?;
+
+        Ok(DoctorReport {
+            writable,
+            free_bytes: Self::free_space(&folder_path),
+            total_files: analysis.total_files,
+            planned_moves: plan.actions_taken.len(),
+            undo_journal_exists: folder_path.join(UNDO_LOG_FILE_NAME).exists(),
+            issues,
+            folder_path,
+        })
+    }
+
+    fn check_writable(dir: &Path) -> bool {
+        let probe = dir.join(format!(".desktidy-doctor-{}", std::process::id()));
+        let writable = fs::write(&probe, b"").is_ok();
+        let _ = fs::remove_file(&probe);
+        writable
+    }
+
+    #[cfg(unix)]
+    fn free_space(dir: &Path) -> Option<u64> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: c_path is a valid NUL-terminated string and stats is a valid out-pointer
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+            return None;
+        }
+        Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+
+    #[cfg(not(unix))]
+    fn free_space(_dir: &Path) -> Option<u64> {
+        None
+    }
+}
+
+impl DoctorReport {
+    pub fn render(&self) -> String {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        let mut lines = vec![
+            format!("[*] Folder: {}", self.folder_path.display()),
+            format!("[*] Writable: {}", yes_no(self.writable)),
+            format!(
+                "[*] Free space: {}",
+                self.free_bytes
+                    .map(|b| format!("{} bytes", b))
+                    .unwrap_or_else(|| "unknown".to_string())
+            ),
+            format!("[*] Files scanned: {}", self.total_files),
+            format!("[*] Planned moves: {}", self.planned_moves),
+            format!("[*] Undo journal present: {}", yes_no(self.undo_journal_exists)),
+        ];
+        if self.issues.is_empty() {
+            lines.push("[+] No issues found".to_string());
+        } else {
+            for issue in &self.issues {
+                lines.push(format!("[!] {}", issue));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_doctor_reports_plan_and_writability() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join("doc.docx"))?;
+        File::create(temp_dir.path().join("photo.jpg"))?;
+        File::create(temp_dir.path().join("notes.xyz"))?;
+
+        let report = Doctor::run(temp_dir.path(), false)?;
+
+        assert!(report.writable);
+        assert_eq!(report.total_files, 3);
+        assert_eq!(report.planned_moves, 2);
+        assert!(!report.undo_journal_exists);
+        assert!(report.issues.is_empty());
+        // Nothing was moved
+        assert!(temp_dir.path().join("doc.docx").exists());
+
+        let rendered = report.render();
+        assert!(rendered.contains("Writable: yes"));
+        assert!(rendered.contains("Planned moves: 2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_doctor_flags_file_blocking_category_folder() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join("Images"))?;
+
+        let report = Doctor::run(temp_dir.path(), false)?;
+
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].contains("Images"));
+
+        Ok(())
+    }
+}
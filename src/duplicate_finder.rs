@@ -1,35 +1,422 @@
-use crate::types::{DuplicateGroup, FileEntry};
+use crate::checksum_cache::ChecksumCache;
+use crate::metadata::FileMeta;
+use crate::throttle::Throttle;
+use crate::types::{DuplicateGroup, FileEntry, KeepStrategy};
 use anyhow::Result;
+use clap::ValueEnum;
 use md5;
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Computes the grouping key for a file; files sharing a key are treated as duplicates
+pub trait DuplicateKeyer: Send + Sync {
+    fn key(&self, path: &Path) -> Result<String>;
+}
+
+// The default keyer: MD5 and SHA256 of the full contents
+pub struct ContentHashKeyer;
+
+impl DuplicateKeyer for ContentHashKeyer {
+    fn key(&self, path: &Path) -> Result<String> {
+        let (md5, sha256) = DuplicateFinder::calculate_checksums(path)?;
+        Ok(format!("{}_{}", md5, sha256))
+    }
+}
+
+// Hashes the size plus up to SAMPLE_CHUNK bytes from the start, middle and end of
+// the file. Much faster on large media, but only shows files are probably identical.
+pub struct SampledHashKeyer;
+
+const SAMPLE_CHUNK: u64 = 1024 * 1024;
+
+impl DuplicateKeyer for SampledHashKeyer {
+    fn key(&self, path: &Path) -> Result<String> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut hasher = Sha256::new();
+        hasher.update(size.to_le_bytes());
+
+        let offsets = if size <= SAMPLE_CHUNK * 3 {
+            vec![0]
+        } else {
+            vec![0, size / 2 - SAMPLE_CHUNK / 2, size - SAMPLE_CHUNK]
+        };
+        let chunk = if offsets.len() == 1 { size } else { SAMPLE_CHUNK };
+        let mut buffer = Vec::new();
+        for offset in offsets {
+            file.seek(SeekFrom::Start(offset))?;
+            buffer.clear();
+            (&mut file).take(chunk).read_to_end(&mut buffer)?;
+            hasher.update(&buffer);
+        }
+        Ok(format!("sampled_{:x}", hasher.finalize()))
+    }
+}
+
+// How file contents are hashed into a duplicate key. `Md5Sha256` reads each file once
+// through both digests; `Blake3` computes a single, much faster digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum HashAlgorithm {
+    #[default]
+    Md5Sha256,
+    Blake3,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zip,
+}
+
+// Hashes only the first `length` bytes, so files sharing a header match even when they
+// differ further on. Used for diagnostics such as spotting truncated downloads.
+pub struct PrefixHashKeyer {
+    length: u64,
+}
+
+impl PrefixHashKeyer {
+    pub fn new(length: u64) -> Self {
+        Self { length }
+    }
+}
+
+impl DuplicateKeyer for PrefixHashKeyer {
+    fn key(&self, path: &Path) -> Result<String> {
+        let mut buffer = Vec::new();
+        File::open(path)?.take(self.length).read_to_end(&mut buffer)?;
+        Ok(format!("prefix_{:x}", Sha256::digest(&buffer)))
+    }
+}
+
+// Files confirmed identical under one checksum key, the file to keep first
+type ConfirmedSet<'a> = (String, Vec<&'a FileEntry>);
 
 pub struct DuplicateFinder {
     verbose: bool,
+    parallel_verify: bool,
+    cache: Option<ChecksumCache>,
+    symlink_aware: bool,
+    keyer: Option<Box<dyn DuplicateKeyer>>,
+    throttle: Option<Arc<Throttle>>,
+    min_count: usize,
+    min_size: u64,
+    group_limit: Option<usize>,
+    max_groups: Option<usize>,
+    compressed: bool,
+    algorithm: HashAlgorithm,
+    threads: usize,
+    keep: KeepStrategy,
+    demoted_folder: Option<PathBuf>,
 }
 
 impl DuplicateFinder {
     pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+        Self::new_with_strategy(verbose, KeepStrategy::default())
+    }
+
+    pub fn new_with_strategy(verbose: bool, keep: KeepStrategy) -> Self {
+        Self {
+            verbose,
+            parallel_verify: false,
+            cache: None,
+            symlink_aware: false,
+            keyer: None,
+            throttle: None,
+            min_count: 2,
+            min_size: 0,
+            group_limit: None,
+            max_groups: None,
+            compressed: false,
+            algorithm: HashAlgorithm::default(),
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            keep,
+            demoted_folder: None,
+        }
+    }
+
+    pub fn with_parallel_verify(mut self, enabled: bool) -> Self {
+        self.parallel_verify = enabled;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: ChecksumCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    // A custom keyer defines equality itself, so its groups skip content verification
+    pub fn with_keyer(mut self, keyer: Box<dyn DuplicateKeyer>) -> Self {
+        self.keyer = Some(keyer);
+        self
+    }
+
+    // Compares `.gz` files and single-entry `.zip` files by their decompressed content,
+    // so `report.pdf.gz` groups with `report.pdf`
+    pub fn with_compressed(mut self, enabled: bool) -> Self {
+        self.compressed = enabled;
+        self
+    }
+
+    // The checksum cache holds MD5/SHA256 pairs, so it is only used with `Md5Sha256`
+    pub fn with_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    // Files directly in this folder are only kept as the original when no copy outside
+    // it exists, so a file already set aside in Duplicates stays there
+    pub fn with_demoted_folder(mut self, folder: Option<PathBuf>) -> Self {
+        self.demoted_folder = folder;
+        self
+    }
+
+    // Files of the same size are hashed on this many threads; defaults to the number
+    // of logical CPUs
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    fn duplicate_key(&self, path: &Path) -> Result<String> {
+        match &self.keyer {
+            Some(keyer) => keyer.key(path),
+            None => self.content_key(path),
+        }
+    }
+
+    fn hash_key(&self, reader: &mut impl Read) -> Result<String> {
+        let throttle = self.throttle.as_deref();
+        match self.algorithm {
+            HashAlgorithm::Md5Sha256 => {
+                let (md5, sha256) = Self::checksums_of(reader, throttle)?;
+                Ok(format!("{}_{}", md5, sha256))
+            }
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                Throttle::read_chunks(throttle, reader, |chunk| {
+                    hasher.update(chunk);
+                    Ok(())
+                })?;
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+        }
+    }
+
+    // The key of a file as stored, through the cache where one applies
+    fn file_key(&self, path: &Path) -> Result<String> {
+        match self.algorithm {
+            HashAlgorithm::Md5Sha256 => {
+                let (md5, sha256) = self.cached_checksums(path)?;
+                Ok(format!("{}_{}", md5, sha256))
+            }
+            HashAlgorithm::Blake3 => self.hash_key(&mut File::open(path)?),
+        }
+    }
+
+    fn compression(&self, path: &Path) -> Option<Compression> {
+        if !self.compressed {
+            return None;
+        }
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "gz" => Some(Compression::Gzip),
+            "zip" => Some(Compression::Zip),
+            _ => None,
+        }
+    }
+
+    // The size used to bucket a file: the decompressed size for compressed files (from
+    // the gzip trailer or the zip directory), otherwise the size on disk
+    fn content_size(&self, path: &Path) -> Result<u64> {
+        let decompressed = match self.compression(path) {
+            Some(Compression::Gzip) => Self::gzip_size(path).ok(),
+            Some(Compression::Zip) => Self::with_zip_entry(path, |entry| Ok(entry.size())).ok(),
+            None => None,
+        };
+        match decompressed {
+            Some(size) => Ok(size),
+            None => Self::get_file_size(path),
+        }
+    }
+
+    // A gzip member ends with its uncompressed length modulo 2^32
+    fn gzip_size(path: &Path) -> Result<u64> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::End(-4))?;
+        let mut trailer = [0; 4];
+        file.read_exact(&mut trailer)?;
+        Ok(u32::from_le_bytes(trailer) as u64)
+    }
+
+    fn with_zip_entry<T>(path: &Path, f: impl FnOnce(&mut zip::read::ZipFile) -> Result<T>) -> Result<T> {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        if archive.len() != 1 {
+            anyhow::bail!("{} entries", archive.len());
+        }
+        f(&mut archive.by_index(0)?)
+    }
+
+    // The key of the decompressed content where that applies. A file that fails to
+    // decompress is compared as it is on disk instead of aborting the search.
+    fn content_key(&self, path: &Path) -> Result<String> {
+        let decompressed = match self.compression(path) {
+            Some(Compression::Gzip) => File::open(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| self.hash_key(&mut flate2::read::GzDecoder::new(file))),
+            Some(Compression::Zip) => Self::with_zip_entry(path, |entry| self.hash_key(entry)),
+            None => return self.file_key(path),
+        };
+        match decompressed {
+            Ok(key) => Ok(key),
+            Err(e) => {
+                if self.verbose {
+                    println!("[~] Cannot decompress {} ({}), comparing it as is", path.display(), e);
+                }
+                self.file_key(path)
+            }
+        }
+    }
+
+    // Groups with fewer files than this are left alone
+    pub fn with_min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count.max(2);
+        self
+    }
+
+    // Files smaller than this on disk are never hashed or grouped; 1 leaves out empty files
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    // Caps the files held by one group. Larger groups are split into several groups that
+    // each repeat the same original, so every copy is still handled.
+    pub fn with_group_limit(mut self, limit: Option<usize>) -> Self {
+        self.group_limit = limit.map(|n| n.max(2));
+        self
+    }
+
+    // Stops looking for duplicates once this many groups have been found
+    pub fn with_max_groups(mut self, max_groups: Option<usize>) -> Self {
+        self.max_groups = max_groups;
+        self
+    }
+
+    // Rate-limits the reads used for hashing
+    pub fn with_throttle(mut self, throttle: Option<Arc<Throttle>>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub fn with_symlink_aware(mut self, enabled: bool) -> Self {
+        self.symlink_aware = enabled;
+        self
+    }
+
+    // Drops symlinks whose target is another scanned entry, so a file and a link
+    // to it are not reported as content duplicates of each other
+    fn without_self_links<'a>(&self, entries: &'a [FileEntry]) -> Vec<&'a FileEntry> {
+        let is_symlink = |e: &FileEntry| {
+            std::fs::symlink_metadata(&e.path)
+                .map(|m| m.file_type().is_symlink())
+                .unwrap_or(false)
+        };
+
+        let real_targets: HashSet<_> = entries
+            .iter()
+            .filter(|e| !is_symlink(e))
+            .filter_map(|e| e.path.canonicalize().ok())
+            .collect();
+        let mut seen_targets = HashSet::new();
+
+        entries
+            .iter()
+            .filter(|e| {
+                if !is_symlink(e) {
+                    return true;
+                }
+                let Ok(target) = e.path.canonicalize() else {
+                    return true;
+                };
+                let keep = !real_targets.contains(&target) && seen_targets.insert(target);
+                if !keep && self.verbose {
+                    println!("[~] Ignoring symlink to scanned file: {}", e.path.display());
+                }
+                keep
+            })
+            .collect()
+    }
+
+    pub fn cache(&self) -> Option<&ChecksumCache> {
+        self.cache.as_ref()
+    }
+
+    fn cached_checksums(&self, file_path: &Path) -> Result<(String, String)> {
+        let throttle = self.throttle.as_deref();
+        let Some(cache) = &self.cache else {
+            return Self::read_checksums(file_path, throttle);
+        };
+        if let Some(checksums) = cache.get(file_path) {
+            return Ok(checksums);
+        }
+        let (md5, sha256) = Self::read_checksums(file_path, throttle)?;
+        cache.record(file_path, &md5, &sha256)?;
+        Ok((md5, sha256))
+    }
+
+    fn verify_candidates<'a>(
+        &self,
+        base: &FileEntry,
+        candidates: &[&'a FileEntry],
+    ) -> Result<Vec<&'a FileEntry>> {
+        // Re-reads the files rather than trusting the cache
+        let key = |path: &Path| match self.compression(path) {
+            Some(_) => self.content_key(path),
+            None => self.hash_key(&mut File::open(path)?),
+        };
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        // The base is read once and every candidate compared against it
+        let base_size = self.content_size(&base.path)?;
+        let base_key = key(&base.path)?;
+        let check = |entry: &&'a FileEntry| -> Result<Option<&'a FileEntry>> {
+            let identical =
+                base_size == self.content_size(&entry.path)? && base_key == key(&entry.path)?;
+            Ok(identical.then_some(*entry))
+        };
+
+        let confirmed: Vec<Option<&FileEntry>> = if self.parallel_verify {
+            candidates.par_iter().map(check).collect::<Result<_>>()?
+        } else {
+            candidates.iter().map(check).collect::<Result<_>>()?
+        };
+
+        Ok(confirmed.into_iter().flatten().collect())
     }
 
     fn calculate_checksums(file_path: &Path) -> Result<(String, String)> {
-        let mut file = File::open(file_path)?;
-        let mut buffer = [0; 8192];
+        Self::read_checksums(file_path, None)
+    }
+
+    pub(crate) fn read_checksums(file_path: &Path, throttle: Option<&Throttle>) -> Result<(String, String)> {
+        Self::checksums_of(&mut File::open(file_path)?, throttle)
+    }
+
+    pub(crate) fn checksums_of(reader: &mut impl Read, throttle: Option<&Throttle>) -> Result<(String, String)> {
         let mut md5_hash = md5::Context::new();
         let mut sha256_hash = Sha256::new();
 
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            md5_hash.consume(&buffer[..bytes_read]);
-            sha256_hash.update(&buffer[..bytes_read]);
-        }
+        Throttle::read_chunks(throttle, reader, |chunk| {
+            md5_hash.consume(chunk);
+            sha256_hash.update(chunk);
+            Ok(())
+        })?;
 
         let md5_digest = format!("{:x}", md5_hash.compute());
         let sha256_digest = format!("{:x}", sha256_hash.finalize());
@@ -38,7 +425,7 @@ impl DuplicateFinder {
     }
 
     fn get_file_size(file_path: &Path) -> Result<u64> {
-        Ok(std::fs::metadata(file_path)?.len())
+        Ok(FileMeta::load(file_path)?.size)
     }
 
     pub fn are_files_identical(file1: &Path, file2: &Path) -> Result<bool> {
@@ -57,22 +444,202 @@ impl DuplicateFinder {
         Ok(checksums1 == checksums2)
     }
 
+    // One SHA256 hex digest per line; blank lines and `#` comments are ignored
+    pub fn load_hash_list(path: &Path) -> Result<HashSet<String>> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.to_lowercase())
+            .collect())
+    }
+
+    pub fn find_denylisted(
+        &self,
+        entries: &[FileEntry],
+        denylist: &HashSet<String>,
+    ) -> Result<Vec<FileEntry>> {
+        let mut matches = Vec::new();
+        for entry in entries {
+            match self.cached_checksums(&entry.path) {
+                Ok((_, sha256)) if denylist.contains(&sha256) => {
+                    if self.verbose {
+                        println!("[!] Denylisted hash: {}", entry.path.display());
+                    }
+                    matches.push(entry.clone());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if self.verbose {
+                        eprintln!("[-] Error reading file {}: {}", entry.path.display(), e);
+                    }
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    // Groups come back sorted by checksum key, so the output does not depend on the
+    // order the threads finished hashing in
     pub fn find_duplicates(&self, entries: &[FileEntry]) -> Result<Vec<DuplicateGroup>> {
+        Ok(self.find_duplicates_capped(entries)?.0)
+    }
+
+    // Also tells whether the search stopped at the `with_max_groups` cap, leaving
+    // duplicates unreported
+    pub fn find_duplicates_capped(
+        &self,
+        entries: &[FileEntry],
+    ) -> Result<(Vec<DuplicateGroup>, bool)> {
+        let mut duplicates = Vec::new();
+        let capped =
+            self.find_duplicates_streaming(entries, |group| duplicates.push(group.clone()))?;
+        duplicates.sort_by(|a, b| a.checksum_key.cmp(&b.checksum_key));
+        Ok((duplicates, capped))
+    }
+
+    // Groups of files sharing their first `length` bytes that are not simply copies of
+    // each other: a group is kept only if it spans more than one of the given duplicate
+    // groups (or files outside them)
+    pub fn find_prefix_matches(
+        &self,
+        entries: &[FileEntry],
+        length: u64,
+        duplicates: &[DuplicateGroup],
+    ) -> Result<Vec<DuplicateGroup>> {
+        let copies_of: HashMap<&Path, usize> = duplicates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.files.iter().map(move |f| (f.path.as_path(), i)))
+            .collect();
+        let groups = DuplicateFinder::new_with_strategy(self.verbose, self.keep)
+            .with_threads(self.threads)
+            .with_keyer(Box::new(PrefixHashKeyer::new(length)))
+            .find_duplicates(entries)?;
+        Ok(groups
+            .into_iter()
+            .filter(|group| {
+                let contents: HashSet<Result<usize, &Path>> = group
+                    .files
+                    .iter()
+                    .map(|f| copies_of.get(f.path.as_path()).copied().ok_or(f.path.as_path()))
+                    .collect();
+                contents.len() > 1
+            })
+            .collect())
+    }
+
+    // Files are bucketed by size first and each bucket is hashed and verified on its own,
+    // so a group is final (and handed to `on_group`) as soon as its bucket is done rather
+    // than after the whole scan. Custom keyers may match across sizes and use one bucket.
+    // Returns true when the search stopped at the `with_max_groups` cap.
+    pub fn find_duplicates_streaming(
+        &self,
+        entries: &[FileEntry],
+        mut on_group: impl FnMut(&DuplicateGroup),
+    ) -> Result<bool> {
         if self.verbose {
             println!("\n[*] Checking for duplicates...");
         }
 
-        let mut file_checksums: HashMap<String, Vec<FileEntry>> = HashMap::new();
+        let entries: Vec<&FileEntry> = if self.symlink_aware {
+            self.without_self_links(entries)
+        } else {
+            entries.iter().collect()
+        };
 
-        // First pass: collect all files and their checksums
+        let mut size_buckets: HashMap<u64, Vec<&FileEntry>> = HashMap::new();
         for entry in entries {
-            match Self::calculate_checksums(&entry.path) {
-                Ok((md5, sha256)) => {
-                    let checksum_key = format!("{}_{}", md5, sha256);
-                    file_checksums
-                        .entry(checksum_key)
-                        .or_insert_with(Vec::new)
-                        .push(entry.clone());
+            if self.min_size > 0
+                && fs::metadata(&entry.path).map_or(0, |m| m.len()) < self.min_size
+            {
+                continue;
+            }
+            let size = if self.keyer.is_some() {
+                0
+            } else {
+                match self.content_size(&entry.path) {
+                    Ok(size) => size,
+                    Err(e) => {
+                        if self.verbose {
+                            eprintln!("[-] Error reading file {}: {}", entry.path.display(), e);
+                        }
+                        continue;
+                    }
+                }
+            };
+            size_buckets.entry(size).or_default().push(entry);
+        }
+        let mut sizes: Vec<u64> = size_buckets.keys().copied().collect();
+        sizes.sort_unstable();
+
+        // A file alone in its size bucket cannot have a duplicate, so it is never hashed
+        sizes.retain(|size| size_buckets[size].len() > 1);
+
+        // Buckets are hashed a few at a time on the pool, and their groups handed out in
+        // size order before the next batch starts, so streaming stays incremental
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build()?;
+        let mut found = 0;
+        for batch in sizes.chunks(self.threads) {
+            let confirmed: Vec<Result<Vec<ConfirmedSet>>> = pool.install(|| {
+                batch
+                    .par_iter()
+                    .map(|size| self.confirm_bucket(&size_buckets[size]))
+                    .collect()
+            });
+            for (checksum_key, files) in confirmed.into_iter().collect::<Result<Vec<_>>>()?.into_iter().flatten() {
+                for group in self.split_group(&checksum_key, &files) {
+                    if self.max_groups.is_some_and(|max| found >= max) {
+                        return Ok(true);
+                    }
+                    on_group(&group);
+                    found += 1;
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Puts the file to keep first. The sorts are stable, so equal files stay in scan order.
+    fn order_by_keep(&self, files: &mut [&FileEntry]) {
+        let created = |e: &&FileEntry| {
+            e.meta()
+                .ok()
+                .and_then(|m| m.created)
+                .unwrap_or_else(std::time::SystemTime::now)
+        };
+        let size = |e: &&FileEntry| e.meta().map(|m| m.size).unwrap_or(0);
+        match self.keep {
+            KeepStrategy::Oldest => files.sort_by_key(created),
+            KeepStrategy::Newest => files.sort_by_key(|e| std::cmp::Reverse(created(e))),
+            KeepStrategy::Largest => files.sort_by_key(|e| std::cmp::Reverse(size(e))),
+            KeepStrategy::Smallest => files.sort_by_key(size),
+            KeepStrategy::FirstFound => {}
+        }
+        if let Some(folder) = &self.demoted_folder {
+            files.sort_by_key(|e| e.path.parent() == Some(folder.as_path()));
+        }
+    }
+
+    // The confirmed sets of a bucket, each with the file to keep first.
+    // Only references into `bucket` are held; entries are cloned when `split_group`
+    // builds the groups, one group at a time.
+    fn confirm_bucket<'a>(&self, bucket: &[&'a FileEntry]) -> Result<Vec<ConfirmedSet<'a>>> {
+        let mut file_checksums: HashMap<String, Vec<&FileEntry>> = HashMap::new();
+
+        // Hashed in parallel, then collected in bucket order
+        let keys: Vec<Result<String>> = bucket
+            .par_iter()
+            .map(|entry| self.duplicate_key(&entry.path))
+            .collect();
+        for (entry, key) in bucket.iter().zip(keys) {
+            match key {
+                Ok(checksum_key) => {
+                    file_checksums.entry(checksum_key).or_default().push(*entry);
                 }
                 Err(e) => {
                     if self.verbose {
@@ -86,49 +653,60 @@ impl DuplicateFinder {
             }
         }
 
-        // Second pass: identify duplicates
-        let mut duplicates = Vec::new();
-
-        for (checksum_key, file_entries) in file_checksums {
-            if file_entries.len() > 1 {
-                // Verify files are actually identical
-                let base_file = &file_entries[0];
-                let mut identical_files = vec![base_file.clone()];
-
-                for file_entry in &file_entries[1..] {
-                    if Self::are_files_identical(&base_file.path, &file_entry.path)? {
-                        identical_files.push(file_entry.clone());
-                    }
-                }
+        let mut keys: Vec<String> = file_checksums
+            .iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+
+        let mut confirmed = Vec::new();
+        for checksum_key in keys {
+            let file_entries = &file_checksums[&checksum_key];
+            // Verify files are actually identical
+            let base_file = file_entries[0];
+            let mut identical_files = vec![base_file];
+            if self.keyer.is_some() {
+                identical_files.extend_from_slice(&file_entries[1..]);
+            } else {
+                identical_files.extend(self.verify_candidates(base_file, &file_entries[1..])?);
+            }
 
-                if identical_files.len() > 1 {
-                    // Sort by creation time (oldest first)
-                    identical_files.sort_by_key(|e| {
-                        std::fs::metadata(&e.path)
-                            .ok()
-                            .and_then(|m| m.created().ok())
-                            .unwrap_or_else(std::time::SystemTime::now)
-                    });
+            if identical_files.len() >= self.min_count {
+                self.order_by_keep(&mut identical_files);
 
-                    if self.verbose {
-                        println!(
-                            "[!] Found duplicates: Keeping {}",
-                            identical_files[0].path.display()
-                        );
-                        for entry in &identical_files[1..] {
-                            println!("[!]   - Will move: {}", entry.path.display());
-                        }
+                if self.verbose {
+                    println!(
+                        "[!] Found duplicates: Keeping {}",
+                        identical_files[0].path.display()
+                    );
+                    for entry in &identical_files[1..] {
+                        println!("[!]   - Will move: {}", entry.path.display());
                     }
-
-                    duplicates.push(DuplicateGroup {
-                        checksum_key,
-                        files: identical_files,
-                    });
                 }
+                confirmed.push((checksum_key, identical_files));
             }
         }
 
-        Ok(duplicates)
+        Ok(confirmed)
+    }
+
+    // The groups of one confirmed set, built as they are taken. With a group limit a
+    // large set becomes several groups that each repeat the same original.
+    fn split_group<'a>(
+        &self,
+        checksum_key: &'a str,
+        files: &'a [&'a FileEntry],
+    ) -> impl Iterator<Item = DuplicateGroup> + 'a {
+        let limit = self.group_limit.unwrap_or(files.len()).min(files.len());
+        let confirmed = self.keyer.is_none();
+        files[1..].chunks((limit - 1).max(1)).map(move |copies| {
+            DuplicateGroup::new(
+                checksum_key.to_string(),
+                std::iter::once(&files[0]).chain(copies).map(|e| (*e).clone()).collect(),
+                confirmed,
+            )
+        })
     }
 }
 
@@ -189,10 +767,14 @@ mod tests {
             FileEntry {
                 path: file1,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file2,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
         ];
 
@@ -205,6 +787,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_space_wasted_counts_copies_only() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries: Vec<FileEntry> = ["a.txt", "b.txt", "c.txt"]
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                fs::write(&path, b"duplicate content")?;
+                Ok(FileEntry {
+                    path,
+                    category: crate::types::FileCategory::Documents,
+                    content_category: None,
+                    size_bytes: 17,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let duplicates = DuplicateFinder::new(false).find_duplicates(&entries)?;
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].space_wasted_bytes, 34);
+        assert_eq!(DuplicateGroup::total_wasted_bytes(&duplicates), 34);
+
+        Ok(())
+    }
+
     #[test]
     fn test_no_duplicates() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -221,10 +828,14 @@ mod tests {
             FileEntry {
                 path: file1,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file2,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
         ];
 
@@ -265,22 +876,32 @@ mod tests {
             FileEntry {
                 path: file1,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file2,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file3,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file4,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file5,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
         ];
 
@@ -294,6 +915,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_min_size_skips_empty_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries: Vec<FileEntry> = ["empty1.txt", "empty2.txt"]
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                File::create(&path)?;
+                Ok(FileEntry {
+                    path,
+                    category: crate::types::FileCategory::Documents,
+                    content_category: None,
+                    size_bytes: 0,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let duplicates = DuplicateFinder::new(false).with_min_size(1).find_duplicates(&entries)?;
+        assert!(duplicates.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_file_duplicates() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -307,10 +951,14 @@ mod tests {
             FileEntry {
                 path: file1,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file2,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
         ];
 
@@ -339,10 +987,14 @@ mod tests {
             FileEntry {
                 path: file1,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
             FileEntry {
                 path: file2,
                 category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
             },
         ];
 
@@ -354,6 +1006,578 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parallel_verify_matches_serial() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let content = vec![7u8; 256 * 1024];
+        let mut entries = Vec::new();
+        for i in 0..6 {
+            let path = temp_dir.path().join(format!("large{}.mp4", i));
+            File::create(&path)?.write_all(&content)?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Videos,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let serial = DuplicateFinder::new(false).find_duplicates(&entries)?;
+        let parallel = DuplicateFinder::new(false)
+            .with_parallel_verify(true)
+            .find_duplicates(&entries)?;
+
+        assert_eq!(serial.len(), 1);
+        assert_eq!(parallel.len(), 1);
+        let mut serial_paths: Vec<_> = serial[0].files.iter().map(|e| e.path.clone()).collect();
+        let mut parallel_paths: Vec<_> = parallel[0].files.iter().map(|e| e.path.clone()).collect();
+        serial_paths.sort();
+        parallel_paths.sort();
+        assert_eq!(serial_paths, parallel_paths);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_resumes_after_interruption() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join(".desktidy-cache");
+        let mut entries = Vec::new();
+        for (name, content) in [("a.pdf", "same"), ("b.pdf", "same"), ("c.pdf", "other")] {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        // First run is "interrupted" after hashing only two files
+        let finder = DuplicateFinder::new(false).with_cache(ChecksumCache::open(&cache_path)?);
+        finder.find_duplicates(&entries[..2])?;
+        drop(finder);
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&cache_path)?
+            .write_all(b"/partial\t12")?;
+
+        let resumed = DuplicateFinder::new(false).with_cache(ChecksumCache::open(&cache_path)?);
+        let duplicates = resumed.find_duplicates(&entries)?;
+
+        assert_eq!(resumed.cache().unwrap().hits(), 2);
+        // c.pdf is the only file of its size, so it is never hashed
+        let reopened = ChecksumCache::open(&cache_path)?;
+        assert!(reopened.get(&entries[2].path).is_none());
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].files.len(), 2);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_aware_ignores_link_to_scanned_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let real = temp_dir.path().join("real.pdf");
+        let link = temp_dir.path().join("link.pdf");
+        File::create(&real)?.write_all(b"content")?;
+        std::os::unix::fs::symlink(&real, &link)?;
+
+        let entries = vec![
+            FileEntry {
+                path: real,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            },
+            FileEntry {
+                path: link,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ];
+
+        let naive = DuplicateFinder::new(false).find_duplicates(&entries)?;
+        assert_eq!(naive.len(), 1);
+
+        let aware = DuplicateFinder::new(false)
+            .with_symlink_aware(true)
+            .find_duplicates(&entries)?;
+        assert!(aware.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blake3_finds_the_same_duplicates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        // A few MB across several files: two pairs of copies and two near-misses
+        let block = |seed: u8| -> Vec<u8> {
+            (0..1024 * 1024).map(|i: u32| (i % 251) as u8 ^ seed).collect()
+        };
+        let mut entries = Vec::new();
+        for (name, seed, tweak) in [
+            ("a.mov", 1, false),
+            ("a copy.mov", 1, false),
+            ("a edited.mov", 1, true),
+            ("b.mov", 2, false),
+            ("b copy.mov", 2, false),
+            ("c.mov", 3, false),
+        ] {
+            let mut content = block(seed);
+            if tweak {
+                *content.last_mut().unwrap() ^= 0xFF;
+            }
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, content)?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Videos,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let file_sets = |algorithm: HashAlgorithm| -> Result<Vec<Vec<String>>> {
+            let groups = DuplicateFinder::new(false)
+                .with_algorithm(algorithm)
+                .find_duplicates(&entries)?;
+            let mut sets: Vec<Vec<String>> = groups
+                .iter()
+                .map(|g| {
+                    let mut names: Vec<String> = g
+                        .files
+                        .iter()
+                        .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+                        .collect();
+                    names.sort();
+                    names
+                })
+                .collect();
+            sets.sort();
+            Ok(sets)
+        };
+
+        let default_sets = file_sets(HashAlgorithm::Md5Sha256)?;
+        let blake3_sets = file_sets(HashAlgorithm::Blake3)?;
+        assert_eq!(default_sets, blake3_sets);
+        assert_eq!(
+            blake3_sets,
+            vec![vec!["a copy.mov", "a.mov"], vec!["b copy.mov", "b.mov"]]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_copy_grouped_with_original() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let content = b"%PDF-1.4 quarterly report ".repeat(200);
+        let original = temp_dir.path().join("report.pdf");
+        std::fs::write(&original, &content)?;
+        let gzipped = temp_dir.path().join("report.pdf.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&gzipped)?, flate2::Compression::default());
+        encoder.write_all(&content)?;
+        encoder.finish()?;
+        // Named like a gzip file but not one: compared as is rather than failing the search
+        let broken = temp_dir.path().join("broken.gz");
+        std::fs::write(&broken, b"not gzip at all")?;
+
+        let entries: Vec<FileEntry> = [&original, &gzipped, &broken]
+            .into_iter()
+            .map(|path| FileEntry {
+                path: path.clone(),
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            })
+            .collect();
+
+        assert!(DuplicateFinder::new(false).find_duplicates(&entries)?.is_empty());
+
+        let groups = DuplicateFinder::new(false)
+            .with_compressed(true)
+            .find_duplicates(&entries)?;
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].confirmed);
+        let mut paths: Vec<_> = groups[0].files.iter().map(|f| f.path.clone()).collect();
+        paths.sort();
+        assert_eq!(paths, vec![original, gzipped]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_groups_match_batch() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for (name, content) in [
+            ("a.pdf", "short"),
+            ("b.pdf", "short"),
+            ("c.pdf", "a much longer body"),
+            ("d.pdf", "a much longer body"),
+            ("e.pdf", "unique"),
+        ] {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        // Record each group together with how many had arrived before it
+        let mut arrivals = Vec::new();
+        DuplicateFinder::new(false).find_duplicates_streaming(&entries, |group| {
+            arrivals.push((arrivals.len(), group.clone()));
+        })?;
+        let batch = DuplicateFinder::new(false).find_duplicates(&entries)?;
+
+        assert_eq!(arrivals.len(), 2);
+        assert_eq!(arrivals[0].0, 0);
+        assert_eq!(arrivals[1].0, 1);
+        // Smaller files are confirmed first
+        assert!(arrivals[0].1.files.iter().any(|e| e.path.ends_with("a.pdf")));
+        // The batch is sorted by checksum key instead
+        let mut streamed: Vec<_> = arrivals.iter().map(|(_, g)| g.checksum_key.clone()).collect();
+        streamed.sort();
+        let batched: Vec<_> = batch.iter().map(|g| g.checksum_key.clone()).collect();
+        assert_eq!(streamed, batched);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_threaded_hashing_is_deterministic() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        // Same-size files, so they all land in one bucket and are hashed together
+        for i in 0..24 {
+            let path = temp_dir.path().join(format!("{:02}.pdf", i));
+            File::create(&path)?.write_all(format!("content {:02}", i % 6).as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let serial = DuplicateFinder::new(false).with_threads(1).find_duplicates(&entries)?;
+        let threaded = DuplicateFinder::new(false).with_threads(8).find_duplicates(&entries)?;
+        assert_eq!(serial.len(), 6);
+        let keys = |groups: &[DuplicateGroup]| -> Vec<String> {
+            groups.iter().map(|g| g.checksum_key.clone()).collect()
+        };
+        assert_eq!(keys(&serial), keys(&threaded));
+        assert!(keys(&threaded).is_sorted());
+        for (a, b) in serial.iter().zip(&threaded) {
+            assert_eq!(a.files.len(), b.files.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_strategy_picks_the_original() -> Result<()> {
+        // Groups every file together, so the copies can differ in size
+        struct SameKeyer;
+        impl DuplicateKeyer for SameKeyer {
+            fn key(&self, _path: &Path) -> Result<String> {
+                Ok("same".to_string())
+            }
+        }
+
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for (name, content) in [("first.pdf", "abc"), ("second.pdf", "abcdefghij"), ("third.pdf", "a")] {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        let original = |keep: KeepStrategy| -> Result<String> {
+            let groups = DuplicateFinder::new_with_strategy(false, keep)
+                .with_keyer(Box::new(SameKeyer))
+                .find_duplicates(&entries)?;
+            assert_eq!(groups.len(), 1);
+            assert_eq!(groups[0].files.len(), 3);
+            Ok(groups[0].files[0].path.file_name().unwrap().to_string_lossy().to_string())
+        };
+        assert_eq!(original(KeepStrategy::FirstFound)?, "first.pdf");
+        assert_eq!(original(KeepStrategy::Largest)?, "second.pdf");
+        assert_eq!(original(KeepStrategy::Smallest)?, "third.pdf");
+        // Creation times are not available on every filesystem
+        if entries[0].meta()?.created.is_some() {
+            assert_eq!(original(KeepStrategy::Oldest)?, "first.pdf");
+            assert_eq!(original(KeepStrategy::Newest)?, "third.pdf");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_search_matches_sequential_on_many_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        // 100 files of assorted sizes: every fourth one repeats an earlier file's content
+        for i in 0..100 {
+            let content = if i % 4 == 3 {
+                format!("file {} {}", i - 3, "x".repeat(i - 3))
+            } else {
+                format!("file {} {}", i, "x".repeat(i))
+            };
+            let path = temp_dir.path().join(format!("{:03}.txt", i));
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let sequential = DuplicateFinder::new(false).with_threads(1).find_duplicates(&entries)?;
+        let parallel = DuplicateFinder::new(false).with_threads(8).find_duplicates(&entries)?;
+        assert_eq!(sequential.len(), 25);
+        assert_eq!(parallel.len(), sequential.len());
+        let paths = |groups: &[DuplicateGroup]| -> Vec<Vec<std::path::PathBuf>> {
+            groups
+                .iter()
+                .map(|g| g.files.iter().map(|f| f.path.clone()).collect())
+                .collect()
+        };
+        assert_eq!(paths(&parallel), paths(&sequential));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_matches_reported_apart_from_duplicates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let header = "HEADER-v1:".repeat(10);
+        let mut entries = Vec::new();
+        for (name, content) in [
+            ("full.bin", format!("{}complete body", header)),
+            ("partial.bin", format!("{}compl", header)),
+            ("copy-a.txt", "same".to_string()),
+            ("copy-b.txt", "same".to_string()),
+        ] {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let finder = DuplicateFinder::new(false);
+        let duplicates = finder.find_duplicates(&entries)?;
+        assert_eq!(duplicates.len(), 1);
+        assert!(duplicates[0].files.iter().all(|f| f.path.extension().unwrap() == "txt"));
+
+        // The two copies share a prefix too, but they are already a duplicate group
+        let prefix = finder.find_prefix_matches(&entries, 64, &duplicates)?;
+        assert_eq!(prefix.len(), 1);
+        let mut names: Vec<_> = prefix[0]
+            .files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["full.bin", "partial.bin"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_sizes_are_never_hashed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join(".desktidy-cache");
+        let mut entries = Vec::new();
+        for i in 1..=10 {
+            let path = temp_dir.path().join(format!("{:02}.pdf", i));
+            File::create(&path)?.write_all("x".repeat(i).as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let finder = DuplicateFinder::new(false).with_cache(ChecksumCache::open(&cache_path)?);
+        let duplicates = finder.find_duplicates(&entries)?;
+        assert!(duplicates.is_empty());
+        // The cache records every checksum computed, and holds none
+        let cache = ChecksumCache::open(&cache_path)?;
+        assert!(entries.iter().all(|e| cache.get(&e.path).is_none()));
+
+        // Same answer as comparing every pair in full
+        for (i, a) in entries.iter().enumerate() {
+            for b in &entries[i + 1..] {
+                assert!(!DuplicateFinder::are_files_identical(&a.path, &b.path)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_count_filters_small_groups() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        let files = (0..2)
+            .map(|i| (format!("pair{}.jpg", i), "pair"))
+            .chain((0..5).map(|i| (format!("meme{}.jpg", i), "the same meme")));
+        for (name, content) in files {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        assert_eq!(DuplicateFinder::new(false).find_duplicates(&entries)?.len(), 2);
+
+        let duplicates = DuplicateFinder::new(false)
+            .with_min_count(3)
+            .find_duplicates(&entries)?;
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].files.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_limit_holds_references_until_each_group_is_built() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for i in 0..25 {
+            let path = temp_dir.path().join(format!("tiny{:02}.txt", i));
+            File::create(&path)?.write_all(b"same")?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+        let finder = DuplicateFinder::new(false).with_group_limit(Some(4));
+
+        // Confirming copies no entries: every file in a set is one of the scanned ones
+        let bucket: Vec<&FileEntry> = entries.iter().collect();
+        let confirmed = finder.confirm_bucket(&bucket)?;
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].1.len(), 25);
+        assert!(confirmed[0].1.iter().all(|e| entries.iter().any(|s| std::ptr::eq(*e, s))));
+
+        // Groups are built one at a time, so no more than the limit is held at once
+        let mut held = Vec::new();
+        finder.find_duplicates_streaming(&entries, |group| held.push(group.files.len()))?;
+        assert_eq!(held, vec![4, 4, 4, 4, 4, 4, 4, 4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast_dedup_groups_large_files_as_probable() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let content: Vec<u8> = (0..5 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut different = content.clone();
+        different[10] ^= 0xff;
+        let mut entries = Vec::new();
+        for (name, bytes) in [("a.mov", &content), ("b.mov", &content), ("c.mov", &different)] {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, bytes)?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Videos,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let duplicates = DuplicateFinder::new(false)
+            .with_keyer(Box::new(SampledHashKeyer))
+            .find_duplicates(&entries)?;
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].files.len(), 2);
+        assert!(!duplicates[0].confirmed);
+        assert!(duplicates[0].checksum_key.starts_with("sampled_"));
+
+        let exact = DuplicateFinder::new(false).find_duplicates(&entries)?;
+        assert!(exact[0].confirmed);
+
+        Ok(())
+    }
+
+    struct SizeKeyer;
+
+    impl DuplicateKeyer for SizeKeyer {
+        fn key(&self, path: &Path) -> Result<String> {
+            Ok(std::fs::metadata(path)?.len().to_string())
+        }
+    }
+
+    #[test]
+    fn test_custom_keyer_groups_by_size() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for (name, content) in [("a.txt", "aaaa"), ("b.txt", "bbbb"), ("c.txt", "cc")] {
+            let path = temp_dir.path().join(name);
+            File::create(&path)?.write_all(content.as_bytes())?;
+            entries.push(FileEntry {
+                path,
+                category: crate::types::FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let by_content = DuplicateFinder::new(false).find_duplicates(&entries)?;
+        assert!(by_content.is_empty());
+
+        let by_size = DuplicateFinder::new(false)
+            .with_keyer(Box::new(SizeKeyer))
+            .find_duplicates(&entries)?;
+        assert_eq!(by_size.len(), 1);
+        assert_eq!(by_size[0].checksum_key, "4");
+        assert_eq!(by_size[0].files.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_hash_keyer_matches_default() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file = temp_dir.path().join("file.txt");
+        File::create(&file)?.write_all(b"content")?;
+
+        let (md5, sha256) = DuplicateFinder::calculate_checksums(&file)?;
+        assert_eq!(ContentHashKeyer.key(&file)?, format!("{}_{}", md5, sha256));
+
+        Ok(())
+    }
+
     #[test]
     fn test_single_file_not_duplicate() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -365,6 +1589,8 @@ mod tests {
         let entries = vec![FileEntry {
             path: file1,
             category: crate::types::FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
         }];
 
         let finder = DuplicateFinder::new(false);
@@ -1,45 +1,565 @@
-use crate::types::{FileCategory, FileEntry, AnalysisResult};
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::categories::CategorySet;
+use crate::config::{Config, FOLDER_CONFIG_FILE_NAME};
+use crate::filesystem::is_symlink_cycle;
+use crate::metadata::FileMeta;
+use crate::rules::RuleSet;
+use crate::snapshot::{SNAPSHOT_AFTER_FILE_NAME, SNAPSHOT_BEFORE_FILE_NAME};
+use crate::state::STATE_FILE_NAME;
+use crate::types::{AnalysisResult, ClassifyBy, FileCategory, FileEntry, Warning};
+use crate::undo::UNDO_LOG_FILE_NAME;
+use anyhow::{Result, bail};
+use glob::Pattern;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Extensions browsers and download managers use while a file is still arriving
+pub const PARTIAL_DOWNLOAD_EXTENSIONS: &[&str] = &["crdownload", "part", "download", "tmp"];
+
+// How many files of an unknown extension are sniffed, and what share of them must
+// agree on a category before every file with that extension is routed there
+const LEARN_SAMPLE_SIZE: usize = 10;
+const LEARN_AGREEMENT: f64 = 0.9;
 
 pub struct FileAnalyzer {
     folder_path: PathBuf,
+    provided_path: PathBuf,
     verbose: bool,
+    classify_by: ClassifyBy,
+    mime_map: Vec<(String, FileCategory)>,
+    sniff: bool,
+    include_hidden: bool,
+    rules: RuleSet,
+    skip_partial_downloads: bool,
+    categories: CategorySet,
+    modified_after: Option<SystemTime>,
+    learn_extensions: bool,
+    recursive: Option<usize>,
+    process_duplicates_folder: bool,
+    content_check: bool,
+    by_content: bool,
+    exclude: Vec<Pattern>,
+    exclude_patterns: Vec<Regex>,
+    include_extensions: Option<HashSet<String>>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    older_than: Option<Duration>,
+    newer_than: Option<Duration>,
+    archive: bool,
 }
 
 impl FileAnalyzer {
     pub fn new(folder_path: PathBuf, verbose: bool) -> Self {
         Self {
-            folder_path: folder_path.canonicalize().unwrap_or(folder_path),
+            folder_path: folder_path.canonicalize().unwrap_or_else(|_| folder_path.clone()),
+            provided_path: folder_path,
             verbose,
+            classify_by: ClassifyBy::Extension,
+            mime_map: Self::default_mime_map(),
+            sniff: false,
+            include_hidden: false,
+            rules: RuleSet::default(),
+            skip_partial_downloads: true,
+            categories: CategorySet::default(),
+            modified_after: None,
+            learn_extensions: false,
+            recursive: None,
+            process_duplicates_folder: false,
+            content_check: false,
+            by_content: false,
+            exclude: Vec::new(),
+            exclude_patterns: Vec::new(),
+            include_extensions: None,
+            min_size: None,
+            max_size: None,
+            older_than: None,
+            newer_than: None,
+            archive: false,
+        }
+    }
+
+    // Validates the folder when built; see FileAnalyzerBuilder
+    pub fn builder(folder_path: impl Into<PathBuf>) -> FileAnalyzerBuilder {
+        FileAnalyzerBuilder::new(folder_path.into())
+    }
+
+    pub fn default_mime_map() -> Vec<(String, FileCategory)> {
+        vec![
+            ("image/".to_string(), FileCategory::Images),
+            ("video/".to_string(), FileCategory::Videos),
+            ("audio/".to_string(), FileCategory::Audio),
+            ("application/pdf".to_string(), FileCategory::PDFs),
+            ("application/zip".to_string(), FileCategory::Archives),
+            ("application/gzip".to_string(), FileCategory::Archives),
+            ("application/x-7z-compressed".to_string(), FileCategory::Archives),
+            ("application/vnd.rar".to_string(), FileCategory::Archives),
+            ("application/x-tar".to_string(), FileCategory::Archives),
+            ("application/x-bzip2".to_string(), FileCategory::Archives),
+            ("application/x-xz".to_string(), FileCategory::Archives),
+        ]
+    }
+
+    pub fn with_classify_by(mut self, classify_by: ClassifyBy) -> Self {
+        self.classify_by = classify_by;
+        self
+    }
+
+    // Prefixes are matched longest-first, so "image/png" can override "image/"
+    pub fn with_mime_map(mut self, mime_map: Vec<(String, FileCategory)>) -> Self {
+        self.mime_map = mime_map;
+        self
+    }
+
+    // Inspect the contents of files without an extension
+    pub fn with_sniff(mut self, enabled: bool) -> Self {
+        self.sniff = enabled;
+        self
+    }
+
+    // Compare each file's leading bytes with its extension and record disagreements
+    pub fn with_content_check(mut self, enabled: bool) -> Self {
+        self.content_check = enabled;
+        self
+    }
+
+    // File by what the contents say when they disagree with the extension
+    pub fn with_by_content(mut self, enabled: bool) -> Self {
+        self.by_content = enabled;
+        self.content_check |= enabled;
+        self
+    }
+
+    // Files whose names match any of these are left where they are and not counted
+    pub fn with_exclude(mut self, patterns: Vec<Pattern>) -> Self {
+        self.exclude = patterns;
+        self
+    }
+
+    // Like `with_exclude`, for regular expressions matched against the file name
+    pub fn with_exclude_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.exclude_patterns = patterns;
+        self
+    }
+
+    // Only files with one of these extensions are analyzed; the rest are not counted
+    pub fn with_include_extensions(mut self, extensions: &[&str]) -> Self {
+        self.include_extensions = Some(
+            extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    // The size and age filters below leave files alone; unlike exclusions, the files
+    // they drop still count towards the total scanned
+    pub fn with_min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    // Only files last modified at least this long ago
+    pub fn with_older_than(mut self, age: Duration) -> Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    // Only files modified within this long
+    pub fn with_newer_than(mut self, age: Duration) -> Self {
+        self.newer_than = Some(age);
+        self
+    }
+
+    // Dotfiles are skipped by default; they are still reported in `hidden` for dedup
+    pub fn with_include_hidden(mut self, enabled: bool) -> Self {
+        self.include_hidden = enabled;
+        self
+    }
+
+    // Rules take precedence over every other classification
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Replaces the built-in categories and extension mapping
+    pub fn with_categories(mut self, categories: CategorySet) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    // Learns a mapping for unknown extensions from the content of a sample of files
+    pub fn with_learn_extensions(mut self, enabled: bool) -> Self {
+        self.learn_extensions = enabled;
+        self
+    }
+
+    // The mapping only holds for this run; it is never written back
+    fn learn_extension_mapping(&self, unknown: &[PathBuf]) -> HashMap<String, FileCategory> {
+        let mut by_extension: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+        for path in unknown {
+            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+                by_extension.entry(ext.to_lowercase()).or_default().push(path);
+            }
+        }
+
+        let mut learned = HashMap::new();
+        for (ext, paths) in by_extension {
+            let sample = &paths[..paths.len().min(LEARN_SAMPLE_SIZE)];
+            let mut votes: HashMap<FileCategory, usize> = HashMap::new();
+            for path in sample {
+                if let Some(category) = self
+                    .sniff_category(path)
+                    .filter(|c| self.categories.contains(c))
+                {
+                    *votes.entry(category).or_default() += 1;
+                }
+            }
+            if let Some((category, count)) = votes.into_iter().max_by_key(|(_, count)| *count)
+                && count as f64 >= sample.len() as f64 * LEARN_AGREEMENT
+            {
+                if self.verbose {
+                    println!(
+                        "[*] Learned: .{} files here are {} ({}/{} sampled)",
+                        ext,
+                        category.as_str(),
+                        count,
+                        sample.len()
+                    );
+                }
+                learned.insert(ext, category);
+            }
+        }
+        learned
+    }
+
+    // Only files modified after this time are scanned
+    pub fn with_modified_after(mut self, time: Option<SystemTime>) -> Self {
+        self.modified_after = time;
+        self
+    }
+
+    fn is_unchanged(&self, path: &Path) -> bool {
+        let Some(threshold) = self.modified_after else {
+            return false;
+        };
+        FileMeta::load(path)
+            .map(|meta| meta.modified <= threshold)
+            .unwrap_or(false)
+    }
+
+    // Merges a user config's category mappings over the current categories
+    pub fn with_config(mut self, config: &Config) -> Result<Self> {
+        self.categories = config.apply(&self.categories)?;
+        Ok(self)
+    }
+
+    // Also scans the files in the root `Duplicates` folder, so files dropped there by hand
+    // are deduplicated against the rest and the unique ones organized like any other
+    pub fn with_process_duplicates_folder(mut self, enabled: bool) -> Self {
+        self.process_duplicates_folder = enabled;
+        self
+    }
+
+    // Walks subfolders up to `max_depth` levels below the root; 0 means no limit.
+    // Output folders at the root, hidden folders and symlinked folders are not entered.
+    pub fn with_recursive(mut self, max_depth: usize) -> Self {
+        self.recursive = Some(max_depth);
+        self
+    }
+
+    fn should_descend(&self, dir: &Path, depth: usize) -> bool {
+        let Some(max_depth) = self.recursive else {
+            return false;
+        };
+        let is_symlink = fs::symlink_metadata(dir).is_ok_and(|m| m.file_type().is_symlink());
+        (max_depth == 0 || depth < max_depth)
+            && !is_symlink
+            && !(depth == 0 && self.is_output_folder(dir))
+            && (self.include_hidden || !Self::is_hidden(dir))
+    }
+
+    // Every non-folder entry to consider, from the root and (when recursive) its subfolders.
+    // Subfolders that cannot be listed are reported rather than failing the scan.
+    fn scan_paths(
+        &self,
+        dir: &Path,
+        depth: usize,
+        paths: &mut Vec<PathBuf>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            // Skip directories, including Duplicates and category output folders
+            if path.is_dir() {
+                let reexamine = depth == 0
+                    && self.process_duplicates_folder
+                    && path.file_name().is_some_and(|name| name == "Duplicates");
+                if reexamine || self.should_descend(&path, depth) {
+                    if let Err(e) = self.scan_paths(&path, depth + 1, paths, warnings) {
+                        if self.verbose {
+                            println!("[!] Cannot read folder {}: {}", path.display(), e);
+                        }
+                        warnings.push(Warning::Unreadable(path));
+                    }
+                } else if self.verbose {
+                    if depth == 0 && self.is_output_folder(&path) {
+                        println!("[~] Skipping output folder: {}", path.display());
+                    } else {
+                        println!("[~] Skipping folder: {}", path.display());
+                    }
+                }
+                continue;
+            }
+
+            paths.push(path);
+        }
+        Ok(())
+    }
+
+    // Partial downloads are counted but never categorized unless this is disabled
+    pub fn with_skip_partial_downloads(mut self, enabled: bool) -> Self {
+        self.skip_partial_downloads = enabled;
+        self
+    }
+
+    fn is_partial_download(path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| PARTIAL_DOWNLOAD_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+
+    // Folders desktidy writes into: Duplicates, every effective category folder and its
+    // rollover folders (Images_2, ...). Their contents are already organized and must
+    // never be fed back into a scan.
+    pub fn is_output_folder(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return false;
+        };
+        if name == "Duplicates" {
+            return true;
+        }
+        self.categories.order().iter().any(|category| {
+            let category = category.as_str();
+            name == category
+                || name
+                    .strip_prefix(category)
+                    .and_then(|rest| rest.strip_prefix('_'))
+                    .is_some_and(|n| n.parse::<usize>().is_ok_and(|n| n > 1))
+        })
+    }
+
+    // With --archive, the `<Category>.zip` files it writes at the root are its output and
+    // are skipped; otherwise a file with such a name is the user's and is organized
+    pub fn with_archive(mut self, enabled: bool) -> Self {
+        self.archive = enabled;
+        self
+    }
+
+    fn is_output_archive(&self, path: &Path) -> bool {
+        self.archive
+            && path.parent() == Some(self.folder_path.as_path())
+            && path.file_name().is_some_and(|name| {
+                self.categories
+                    .order()
+                    .iter()
+                    .any(|category| name.to_string_lossy() == format!("{}.zip", category.as_str()))
+            })
+    }
+
+    // The state file and undo manifest desktidy keeps at the root
+    fn is_bookkeeping_file(&self, path: &Path) -> bool {
+        path.parent() == Some(self.folder_path.as_path())
+            && path
+                .file_name()
+                .is_some_and(|name| {
+                    name == STATE_FILE_NAME
+                        || name == UNDO_LOG_FILE_NAME
+                        || name == FOLDER_CONFIG_FILE_NAME
+                        || name == SNAPSHOT_BEFORE_FILE_NAME
+                        || name == SNAPSHOT_AFTER_FILE_NAME
+                })
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if self.exclude.iter().any(|pattern| pattern.matches(&name))
+            || self.exclude_patterns.iter().any(|pattern| pattern.is_match(&name))
+        {
+            return true;
+        }
+        if let Some(extensions) = &self.include_extensions {
+            let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+            if !extensions.contains(&ext) {
+                return true;
+            }
         }
+        false
     }
 
-    fn get_extension_category(ext: &str) -> Option<FileCategory> {
-        match ext.to_lowercase().as_str() {
-            // Office Documents
-            "ppt" | "pptx" => Some(FileCategory::Presentations),
-            "doc" | "docx" => Some(FileCategory::Documents),
-            "xls" | "xlsx" => Some(FileCategory::Spreadsheets),
-            // PDFs
-            "pdf" => Some(FileCategory::PDFs),
-            // Images
-            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "webp" | "heic" | "raw"
-            | "cr2" | "nef" | "arw" => Some(FileCategory::Images),
-            // Videos
-            "mp4" | "mov" | "avi" | "mkv" | "wmv" | "flv" | "webm" | "m4v" | "3gp" => {
-                Some(FileCategory::Videos)
+    // Applied once a file has a category, so unsupported files are never stat'ed for it
+    fn filtered_out(&self, path: &Path) -> bool {
+        let filtered = !self.passes_filters(path);
+        if filtered && self.verbose {
+            println!("[~] Filtered out by size or age: {}", path.display());
+        }
+        filtered
+    }
+
+    fn passes_filters(&self, path: &Path) -> bool {
+        if self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.older_than.is_none()
+            && self.newer_than.is_none()
+        {
+            return true;
+        }
+        let Ok(meta) = FileMeta::load(path) else {
+            return false;
+        };
+        let age = SystemTime::now().duration_since(meta.modified).unwrap_or_default();
+        self.min_size.is_none_or(|min| meta.size >= min)
+            && self.max_size.is_none_or(|max| meta.size <= max)
+            && self.older_than.is_none_or(|older| age >= older)
+            && self.newer_than.is_none_or(|newer| age <= newer)
+    }
+
+    fn is_hidden(path: &Path) -> bool {
+        path.file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(false)
+    }
+
+    fn sniff_category(&self, path: &Path) -> Option<FileCategory> {
+        self.sniff_detected(path).map(|(category, _)| category)
+    }
+
+    // The category and detected MIME type of a file judged by its contents alone
+    fn sniff_detected(&self, path: &Path) -> Option<(FileCategory, String)> {
+        let mut header = [0u8; 2];
+        let mut file = fs::File::open(path).ok()?;
+        if std::io::Read::read_exact(&mut file, &mut header).is_ok() && &header == b"#!" {
+            return Some((FileCategory::Code, "text/x-shellscript".to_string()));
+        }
+        let kind = infer::get_from_path(path).ok()??;
+        let category = self.mime_category(kind.mime_type())?;
+        self.categories
+            .contains(&category)
+            .then(|| (category, kind.mime_type().to_string()))
+    }
+
+    fn get_mime_category(&self, path: &Path) -> Option<FileCategory> {
+        let kind = infer::get_from_path(path).ok()??;
+        self.mime_category(kind.mime_type())
+    }
+
+    // The category the first bytes point to, when it is a different known category
+    fn content_mismatch(&self, path: &Path, category: &FileCategory) -> Option<FileCategory> {
+        let mut header = [0u8; 16];
+        let mut file = fs::File::open(path).ok()?;
+        let read = std::io::Read::read(&mut file, &mut header).ok()?;
+        let kind = infer::get(&header[..read])?;
+        let detected = self.mime_category(kind.mime_type())?;
+        (self.categories.contains(&detected) && detected != *category).then_some(detected)
+    }
+
+    fn mime_category(&self, mime: &str) -> Option<FileCategory> {
+        self.mime_map
+            .iter()
+            .filter(|(prefix, _)| mime.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, category)| category.clone())
+    }
+
+    fn categorize(&self, path: &Path) -> Option<FileCategory> {
+        if let Some(rule) = self.rules.first_match(path) {
+            return Some(rule.category.clone());
+        }
+        let by_extension = || {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .and_then(|e| self.categories.category_for_extension(e))
+        };
+        // Content detection yields built-in categories, which a custom set may not have
+        let known = |category: FileCategory| self.categories.contains(&category).then_some(category);
+        let category = match self.classify_by {
+            ClassifyBy::Extension => by_extension(),
+            ClassifyBy::Mime => self.get_mime_category(path).and_then(known).or_else(by_extension),
+        };
+        if category.is_none() && self.sniff && path.extension().is_none() {
+            return self.sniff_category(path).and_then(known);
+        }
+        category
+    }
+
+    // Files whose size cannot be read count as empty
+    fn file_size(&self, path: &Path) -> u64 {
+        match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                if self.verbose {
+                    println!("[!] Cannot read the size of {}: {}", path.display(), e);
+                }
+                0
             }
-            // Audio
-            "mp3" | "wav" | "aac" | "ogg" | "flac" | "m4a" | "wma" | "aiff" => {
-                Some(FileCategory::Audio)
+        }
+    }
+
+    // Every categorizable file under the root's category folders, at any depth
+    fn collect_organized(&self, dir: &Path, depth: usize, organized: &mut Vec<FileEntry>) {
+        let Ok(children) = fs::read_dir(dir) else {
+            return;
+        };
+        for path in children.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let is_symlink = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+            if path.is_dir() && !is_symlink {
+                let inside = depth > 0
+                    || (self.is_output_folder(&path)
+                        && path.file_name().is_some_and(|name| name != "Duplicates"));
+                if inside {
+                    self.collect_organized(&path, depth + 1, organized);
+                }
+            } else if depth > 0
+                && path.is_file()
+                && let Some(category) = self.categorize(&path)
+            {
+                let size_bytes = self.file_size(&path);
+                organized.push(FileEntry { path, category, content_category: None, size_bytes });
             }
-            _ => None,
         }
     }
 
+    // The root entries are listed under, after `with_resolve_root`
+    pub fn folder_path(&self) -> &Path {
+        &self.folder_path
+    }
+
+    // By default the root is canonicalized, which resolves a symlinked scan folder
+    // to its target; disabling this keeps paths relative to the link itself
+    pub fn with_resolve_root(mut self, resolve: bool) -> Self {
+        if !resolve {
+            self.folder_path = self.provided_path.clone();
+        }
+        self
+    }
+
+    // The built-in mapping, regardless of any configured categories
+    // A compound extension such as `tar.gz` is looked up by its last part
+    pub fn get_extension_category(ext: &str) -> Option<FileCategory> {
+        let ext = ext.rsplit('.').next().unwrap_or(ext);
+        CategorySet::builtin().category_for_extension(ext)
+    }
+
     pub fn analyze(&self) -> Result<AnalysisResult> {
         if self.verbose {
             println!("\n[*] Starting file analysis...");
@@ -48,38 +568,161 @@ impl FileAnalyzer {
         let mut categories: HashMap<FileCategory, Vec<FileEntry>> = HashMap::new();
         let mut total_files = 0;
         let mut supported_files = 0;
+        let mut unsupported_files = 0;
+        let mut hidden = Vec::new();
+        let mut warnings = Vec::new();
+        let mut unknown = Vec::new();
 
-        let duplicates_dir = self.folder_path.join("Duplicates");
+        let mut paths = Vec::new();
+        self.scan_paths(&self.folder_path, 0, &mut paths, &mut warnings)?;
 
-        for entry in fs::read_dir(&self.folder_path)? {
-            let entry = entry?;
-            let path = entry.path();
+        for path in paths {
+            // Dangling symlinks and files we cannot stat are reported, not organized
+            if fs::metadata(&path).is_err() {
+                if is_symlink_cycle(&path) {
+                    if self.verbose {
+                        println!("[!] Symlink cycle: {}", path.display());
+                    }
+                    warnings.push(Warning::Skipped(path, "symlink cycle".to_string()));
+                    continue;
+                }
+                if self.verbose {
+                    println!("[!] Cannot read: {}", path.display());
+                }
+                warnings.push(Warning::Unreadable(path));
+                continue;
+            }
 
-            // Skip directories and Duplicates folder
-            if path.is_dir() || path == duplicates_dir {
+            if self.is_bookkeeping_file(&path) {
+                continue;
+            }
+
+            if self.is_excluded(&path) {
                 if self.verbose {
-                    println!("[~] Skipping folder: {}", path.display());
+                    println!("[~] Excluded: {}", path.display());
+                }
+                continue;
+            }
+
+            if !self.include_hidden && Self::is_hidden(&path) {
+                if self.verbose {
+                    println!("[~] Skipping hidden file: {}", path.display());
+                }
+                if let Some(category) = self.categorize(&path) {
+                    let size_bytes = self.file_size(&path);
+                    hidden.push(FileEntry { path, category, content_category: None, size_bytes });
+                }
+                continue;
+            }
+
+            if self.is_output_archive(&path) {
+                if self.verbose {
+                    println!("[~] Skipping category archive: {}", path.display());
+                }
+                continue;
+            }
+
+            if self.is_unchanged(&path) {
+                if self.verbose {
+                    println!("[~] Unchanged since last run: {}", path.display());
                 }
                 continue;
             }
 
             total_files += 1;
 
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                if let Some(category) = Self::get_extension_category(ext) {
-                    supported_files += 1;
+            if self.skip_partial_downloads && Self::is_partial_download(&path) {
+                if self.verbose {
+                    println!("[~] Skipping download in progress: {}", path.display());
+                }
+                warnings.push(Warning::Skipped(path, "download in progress".to_string()));
+                continue;
+            }
+
+            if let Some(mut category) = self.categorize(&path) {
+                if self.filtered_out(&path) {
+                    continue;
+                }
+                supported_files += 1;
+                let content_category = self
+                    .content_check
+                    .then(|| self.content_mismatch(&path, &category))
+                    .flatten();
+                if let Some(detected) = &content_category {
                     if self.verbose {
                         println!(
-                            "[+] Found {} file: {}",
-                            category.as_str(),
-                            path.file_name().unwrap_or_default().to_string_lossy()
+                            "[!] {} looks like {} content",
+                            path.file_name().unwrap_or_default().to_string_lossy(),
+                            detected.as_str()
                         );
                     }
-                    categories.entry(category.clone()).or_insert_with(Vec::new).push(FileEntry {
-                        path,
-                        category,
-                    });
+                    if self.by_content {
+                        category = detected.clone();
+                    }
                 }
+                if self.verbose {
+                    println!(
+                        "[+] Found {} file: {}",
+                        category.as_str(),
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                }
+                let size_bytes = self.file_size(&path);
+                categories.entry(category.clone()).or_default().push(FileEntry {
+                    path,
+                    category,
+                    content_category,
+                    size_bytes,
+                });
+            } else if let Some((category, mime)) = self.sniff.then(|| self.sniff_detected(&path)).flatten() {
+                // The extension is unsupported but the contents are recognized, so the file
+                // is organized by what it is and the mismatch is reported
+                if self.filtered_out(&path) {
+                    continue;
+                }
+                supported_files += 1;
+                if self.verbose {
+                    println!("[!] Reclassified {} as {} ({})", path.display(), category.as_str(), mime);
+                }
+                warnings.push(Warning::Reclassified(path.clone(), category.clone(), mime));
+                let size_bytes = self.file_size(&path);
+                categories.entry(category.clone()).or_default().push(FileEntry {
+                    path,
+                    category,
+                    content_category: None,
+                    size_bytes,
+                });
+            } else if self.learn_extensions {
+                unknown.push(path);
+            } else {
+                unsupported_files += 1;
+            }
+        }
+
+        let learned_extensions = if self.learn_extensions {
+            self.learn_extension_mapping(&unknown)
+        } else {
+            HashMap::new()
+        };
+        for path in unknown {
+            let learned = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(|e| learned_extensions.get(&e.to_lowercase()));
+            if let Some(category) = learned {
+                if self.filtered_out(&path) {
+                    continue;
+                }
+                supported_files += 1;
+                let size_bytes = self.file_size(&path);
+                categories.entry(category.clone()).or_default().push(FileEntry {
+                    path,
+                    category: category.clone(),
+                    content_category: None,
+                    size_bytes,
+                });
+            } else {
+                unsupported_files += 1;
             }
         }
 
@@ -87,21 +730,129 @@ impl FileAnalyzer {
             println!("\n[*] Analysis Summary:");
             println!("[*] Total files scanned: {}", total_files);
             println!("[*] Supported files found: {}", supported_files);
-            for category in FileCategory::order() {
+            for category in self.categories.order() {
                 if let Some(files) = categories.get(&category) {
                     println!("[*] {}: {} files", category.as_str(), files.len());
                 }
             }
         }
 
+        let mut organized = Vec::new();
+        if self.process_duplicates_folder {
+            self.collect_organized(&self.folder_path, 0, &mut organized);
+        }
+
+        let bytes_by_category: HashMap<FileCategory, u64> = categories
+            .iter()
+            .map(|(category, files)| (category.clone(), files.iter().map(|f| f.size_bytes).sum()))
+            .collect();
+
         Ok(AnalysisResult {
             total_files,
             supported_files,
+            unsupported_files,
+            total_bytes: bytes_by_category.values().sum(),
+            bytes_by_category,
             categories,
+            hidden,
+            organized,
+            warnings,
+            learned_extensions,
         })
     }
 }
 
+// Builds a FileAnalyzer for a folder that is checked to exist. Anything not set here
+// can still be set with the `with_` methods on the analyzer it returns.
+pub struct FileAnalyzerBuilder {
+    folder_path: PathBuf,
+    verbose: bool,
+    recursive: Option<usize>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    include_extensions: Option<Vec<String>>,
+    exclude_patterns: Vec<Regex>,
+}
+
+impl FileAnalyzerBuilder {
+    pub fn new(folder_path: PathBuf) -> Self {
+        Self {
+            folder_path,
+            verbose: false,
+            recursive: None,
+            min_size: None,
+            max_size: None,
+            include_extensions: None,
+            exclude_patterns: Vec::new(),
+        }
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    // Scan subfolders up to this depth; 0 means no limit
+    pub fn recursive(mut self, max_depth: usize) -> Self {
+        self.recursive = Some(max_depth);
+        self
+    }
+
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn include_extensions(mut self, extensions: Vec<&str>) -> Self {
+        self.include_extensions = Some(extensions.into_iter().map(str::to_string).collect());
+        self
+    }
+
+    pub fn exclude_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.exclude_patterns = patterns;
+        self
+    }
+
+    pub fn build(self) -> Result<FileAnalyzer> {
+        check_folder(&self.folder_path)?;
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size)
+            && min > max
+        {
+            bail!("minimum size {} is larger than maximum size {}", min, max);
+        }
+        let mut analyzer = FileAnalyzer::new(self.folder_path, self.verbose)
+            .with_exclude_patterns(self.exclude_patterns);
+        if let Some(bytes) = self.min_size {
+            analyzer = analyzer.with_min_size(bytes);
+        }
+        if let Some(bytes) = self.max_size {
+            analyzer = analyzer.with_max_size(bytes);
+        }
+        if let Some(extensions) = &self.include_extensions {
+            let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+            analyzer = analyzer.with_include_extensions(&extensions);
+        }
+        if let Some(max_depth) = self.recursive {
+            analyzer = analyzer.with_recursive(max_depth);
+        }
+        Ok(analyzer)
+    }
+}
+
+// Builders refuse a folder that is missing or is a file before anything is scanned
+pub(crate) fn check_folder(folder_path: &Path) -> Result<()> {
+    match fs::metadata(folder_path) {
+        Ok(meta) if meta.is_dir() => Ok(()),
+        Ok(_) => bail!("{} is not a folder", folder_path.display()),
+        Err(_) => bail!("{} does not exist", folder_path.display()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,6 +876,67 @@ mod tests {
         assert_eq!(FileAnalyzer::get_extension_category("unknown"), None);
     }
 
+    #[test]
+    fn test_source_files_are_code() {
+        assert_eq!(FileAnalyzer::get_extension_category("rs"), Some(FileCategory::Code));
+        assert_eq!(FileAnalyzer::get_extension_category("py"), Some(FileCategory::Code));
+        assert_eq!(FileAnalyzer::get_extension_category("json"), Some(FileCategory::Code));
+        for ext in ["cs", "php", "swift", "kt", "ps1", "lua", "r", "scala"] {
+            assert_eq!(FileAnalyzer::get_extension_category(ext), Some(FileCategory::Code));
+        }
+    }
+
+    #[test]
+    fn test_archives_category() -> Result<()> {
+        assert_eq!(FileAnalyzer::get_extension_category("zip"), Some(FileCategory::Archives));
+        assert_eq!(FileAnalyzer::get_extension_category("7z"), Some(FileCategory::Archives));
+        for ext in ["cab", "iso", "dmg", "tar.gz"] {
+            assert_eq!(FileAnalyzer::get_extension_category(ext), Some(FileCategory::Archives));
+        }
+
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join("file.zip"))?;
+        File::create(temp_dir.path().join("archive.tar.gz"))?;
+        File::create(temp_dir.path().join("Images.zip"))?;
+
+        let names = |result: &AnalysisResult| {
+            let mut names: Vec<String> = result.categories[&FileCategory::Archives]
+                .iter()
+                .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        // Only an --archive run treats Images.zip as its own output
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(names(&result), vec!["Images.zip", "archive.tar.gz", "file.zip"]);
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_archive(true)
+            .analyze()?;
+        assert_eq!(names(&result), vec!["archive.tar.gz", "file.zip"]);
+        assert_eq!(result.total_files, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_mappings_take_precedence() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join("logo.sketch"))?;
+        File::create(temp_dir.path().join("notes.txt"))?;
+
+        let config = Config::parse("[categories]\nDesign = [\"sketch\"]\nNotes = [\"txt\"]\n")?;
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_config(&config)?
+            .analyze()?;
+        assert_eq!(result.supported_files, 2);
+        assert!(result.categories.contains_key(&FileCategory::Custom("Design".to_string())));
+        assert!(result.categories.contains_key(&FileCategory::Custom("Notes".to_string())));
+        assert!(!result.categories.contains_key(&FileCategory::Documents));
+
+        Ok(())
+    }
+
     #[test]
     fn test_analyze_empty_folder() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -245,6 +1057,400 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_classify_by_mime_ignores_wrong_extension() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let png_header = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D];
+        std::fs::write(temp_dir.path().join("photo.txt"), png_header)?;
+
+        let by_extension = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert!(by_extension.categories.contains_key(&FileCategory::Documents));
+
+        let by_mime = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_classify_by(ClassifyBy::Mime)
+            .analyze()?;
+        assert_eq!(by_mime.categories.len(), 1);
+        assert!(by_mime.categories.contains_key(&FileCategory::Images));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sniff_shebang_without_extension() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("build"), b"#!/bin/sh\necho hello\n")?;
+        std::fs::write(temp_dir.path().join("Makefile"), b"all:\n\techo hi\n")?;
+
+        let plain = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(plain.supported_files, 0);
+
+        let sniffed = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_sniff(true)
+            .analyze()?;
+        assert_eq!(sniffed.total_files, 2);
+        assert_eq!(sniffed.supported_files, 1);
+        let code = &sniffed.categories[&FileCategory::Code];
+        assert_eq!(code[0].path.file_name().unwrap(), "build");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sniff_detects_magic_numbers_after_extension_lookup() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let headers: [(&str, &[u8], FileCategory); 5] = [
+            ("photo", b"\xFF\xD8\xFF\xE0\0\x10JFIF\0", FileCategory::Images),
+            ("picture", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", FileCategory::Images),
+            ("invoice", b"%PDF-1.7\n", FileCategory::PDFs),
+            ("bundle", b"PK\x03\x04\x14\0\0\0\x08\0", FileCategory::Archives),
+            ("song", b"ID3\x04\0\0\0\0\0\x0f", FileCategory::Audio),
+        ];
+        for (name, header, _) in &headers {
+            std::fs::write(temp_dir.path().join(name), header)?;
+        }
+        // A known extension wins over the contents
+        std::fs::write(temp_dir.path().join("report.pdf"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+
+        let sniffed = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_sniff(true)
+            .analyze()?;
+        for (name, _, category) in &headers {
+            assert!(
+                sniffed.categories[category].iter().any(|e| e.path.ends_with(name)),
+                "{name} should be in {category:?}"
+            );
+        }
+        assert_eq!(sniffed.categories[&FileCategory::Images].len(), 2);
+        assert_eq!(sniffed.categories[&FileCategory::PDFs].len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sniff_reclassifies_misnamed_file_with_warning() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let misnamed = temp_dir.path().join("file.dat");
+        std::fs::write(&misnamed, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+
+        let plain = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(plain.supported_files, 0);
+        assert!(plain.warnings.is_empty());
+
+        let sniffed = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_sniff(true)
+            .analyze()?;
+        assert_eq!(sniffed.categories[&FileCategory::Images][0].path, misnamed);
+        assert_eq!(
+            sniffed.warnings,
+            vec![Warning::Reclassified(misnamed, FileCategory::Images, "image/png".to_string())]
+        );
+        assert_eq!(
+            sniffed.warnings[0].to_string(),
+            "Moved file.dat to Images — detected as image/png"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_scan_respects_depth() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let nested = temp_dir.path().join("trip").join("day1");
+        fs::create_dir_all(&nested)?;
+        fs::create_dir(temp_dir.path().join("Images"))?;
+        File::create(temp_dir.path().join("top.pdf"))?;
+        File::create(temp_dir.path().join("trip").join("ticket.pdf"))?;
+        File::create(nested.join("beach.jpg"))?;
+        File::create(temp_dir.path().join("Images").join("sorted.jpg"))?;
+
+        let scanned = |analyzer: FileAnalyzer| -> Result<Vec<PathBuf>> {
+            let mut paths: Vec<PathBuf> = analyzer
+                .analyze()?
+                .categories
+                .into_values()
+                .flatten()
+                .map(|e| e.path)
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+        let root = temp_dir.path().canonicalize()?;
+        let analyzer = || FileAnalyzer::new(temp_dir.path().to_path_buf(), false);
+
+        assert_eq!(scanned(analyzer())?, vec![root.join("top.pdf")]);
+        assert_eq!(
+            scanned(analyzer().with_recursive(1))?,
+            vec![root.join("top.pdf"), root.join("trip/ticket.pdf")]
+        );
+        // Unlimited depth still leaves the root's output folders alone
+        assert_eq!(
+            scanned(analyzer().with_recursive(0))?,
+            vec![root.join("top.pdf"), root.join("trip/day1/beach.jpg"), root.join("trip/ticket.pdf")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hidden_files_skipped_by_default() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join(".cover.jpg"))?;
+        File::create(temp_dir.path().join("photo.jpg"))?;
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.categories[&FileCategory::Images].len(), 1);
+        assert_eq!(result.hidden.len(), 1);
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_include_hidden(true)
+            .analyze()?;
+        assert_eq!(result.categories[&FileCategory::Images].len(), 2);
+        assert!(result.hidden.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_file_reported_as_warning() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let broken = temp_dir.path().join("broken.pdf");
+        std::os::unix::fs::symlink(temp_dir.path().join("missing.pdf"), &broken)?;
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(result.supported_files, 0);
+        assert_eq!(result.warnings, vec![Warning::Unreadable(broken)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_download_never_categorized() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let partial = temp_dir.path().join("report.pdf.crdownload");
+        std::fs::write(&partial, b"%PDF-1.4\n%partial")?;
+
+        // Even content-based classification would recognise the PDF header
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_classify_by(ClassifyBy::Mime)
+            .analyze()?;
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.supported_files, 0);
+        assert!(matches!(&result.warnings[..], [Warning::Skipped(path, _)] if path == &partial));
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_classify_by(ClassifyBy::Mime)
+            .with_skip_partial_downloads(false)
+            .analyze()?;
+        assert!(result.categories.contains_key(&FileCategory::PDFs));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_folders_not_reingested() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        for folder in ["Images", "Images_2", "Duplicates"] {
+            std::fs::create_dir(temp_dir.path().join(folder))?;
+            File::create(temp_dir.path().join(folder).join("photo.jpg"))?;
+        }
+        File::create(temp_dir.path().join("new.jpg"))?;
+
+        let analyzer = FileAnalyzer::new(temp_dir.path().to_path_buf(), false);
+        let result = analyzer.analyze()?;
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.categories[&FileCategory::Images].len(), 1);
+
+        let root = temp_dir.path();
+        assert!(analyzer.is_output_folder(&root.join("Images")));
+        assert!(analyzer.is_output_folder(&root.join("Images_2")));
+        assert!(analyzer.is_output_folder(&root.join("Duplicates")));
+        assert!(!analyzer.is_output_folder(&root.join("Images_old")));
+        assert!(!analyzer.is_output_folder(&root.join("Projects")));
+
+        // Custom categories change which folders count as output
+        let custom = FileAnalyzer::new(root.to_path_buf(), false).with_categories(
+            CategorySet::parse("[[category]]\nname = \"Work\"\nextensions = [\"jpg\"]\n")?,
+        );
+        assert!(custom.is_output_folder(&root.join("Work")));
+        assert!(!custom.is_output_folder(&root.join("Images")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_learned_extension_routes_to_images() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let png_header = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D];
+        for i in 0..4 {
+            std::fs::write(temp_dir.path().join(format!("frame{}.dat", i)), png_header)?;
+        }
+        // Only half of these agree, which is not enough to learn from
+        std::fs::write(temp_dir.path().join("a.bin"), png_header)?;
+        std::fs::write(temp_dir.path().join("b.bin"), b"opaque")?;
+
+        let plain = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        assert_eq!(plain.supported_files, 0);
+
+        let learned = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_learn_extensions(true)
+            .analyze()?;
+        assert_eq!(learned.learned_extensions.get("dat"), Some(&FileCategory::Images));
+        assert!(!learned.learned_extensions.contains_key("bin"));
+        assert_eq!(learned.categories[&FileCategory::Images].len(), 4);
+        assert_eq!(learned.supported_files, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_mismatch_recorded_and_used_by_content() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("scan.jpg"), b"%PDF-1.4\n%fake scan")?;
+        std::fs::write(temp_dir.path().join("notes.pdf"), b"%PDF-1.4\n%real notes")?;
+
+        let checked = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_content_check(true)
+            .analyze()?;
+        let images = &checked.categories[&FileCategory::Images];
+        assert_eq!(images[0].content_category, Some(FileCategory::PDFs));
+        assert_eq!(checked.categories[&FileCategory::PDFs][0].content_category, None);
+
+        let by_content = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_by_content(true)
+            .analyze()?;
+        assert!(!by_content.categories.contains_key(&FileCategory::Images));
+        assert_eq!(by_content.categories[&FileCategory::PDFs].len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_records_sizes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("a.jpg"), vec![1u8; 300])?;
+        std::fs::write(temp_dir.path().join("b.jpg"), vec![2u8; 200])?;
+        std::fs::write(temp_dir.path().join("c.pdf"), vec![3u8; 50])?;
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        let mut sizes: Vec<u64> = result.categories[&FileCategory::Images]
+            .iter()
+            .map(|e| e.size_bytes)
+            .collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![200, 300]);
+        assert_eq!(result.bytes_by_category[&FileCategory::Images], 500);
+        assert_eq!(result.bytes_by_category[&FileCategory::PDFs], 50);
+        assert_eq!(result.total_bytes, 550);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_excluded_files_are_not_analyzed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("cache.tmp"), "cache")?;
+        fs::write(temp_dir.path().join("session.tmp"), "session")?;
+        fs::write(temp_dir.path().join("README.md"), "readme")?;
+        fs::write(temp_dir.path().join("report.pdf"), "report")?;
+
+        let result = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_exclude(vec![Pattern::new("*.tmp")?, Pattern::new("README*")?])
+            .analyze()?;
+        let names: Vec<_> = result
+            .categories
+            .values()
+            .flatten()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["report.pdf"]);
+        assert_eq!(result.total_files, 1);
+        assert_eq!(result.supported_files, 1);
+        assert!(result.warnings.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_and_age_filters_count_but_skip_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let old = temp_dir.path().join("old.pdf");
+        fs::write(&old, vec![b'o'; 2048])?;
+        File::options()
+            .write(true)
+            .open(&old)?
+            .set_modified(SystemTime::now() - Duration::from_secs(40 * 86_400))?;
+        fs::write(temp_dir.path().join("new.pdf"), vec![b'n'; 2048])?;
+        fs::write(temp_dir.path().join("small.pdf"), "small")?;
+        fs::write(temp_dir.path().join("notes.xyz"), "tiny")?;
+
+        let names = |result: &AnalysisResult| {
+            let mut names: Vec<_> = result
+                .categories
+                .values()
+                .flatten()
+                .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        let analyzer = || FileAnalyzer::new(temp_dir.path().to_path_buf(), false).with_min_size(1024);
+
+        let stale = analyzer().with_older_than(Duration::from_secs(30 * 86_400)).analyze()?;
+        assert_eq!(names(&stale), vec!["old.pdf"]);
+        assert_eq!(stale.total_files, 4);
+        assert_eq!(stale.supported_files, 1);
+        // Filters apply after extension matching, so an unknown file is still unsupported
+        assert_eq!(stale.unsupported_files, 1);
+
+        let recent = analyzer().with_newer_than(Duration::from_secs(30 * 86_400)).analyze()?;
+        assert_eq!(names(&recent), vec!["new.pdf"]);
+
+        let small = FileAnalyzer::new(temp_dir.path().to_path_buf(), false)
+            .with_max_size(100)
+            .analyze()?;
+        assert_eq!(names(&small), vec!["small.pdf"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_validates_folder_and_filters() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("tiny.pdf"), "x")?;
+        fs::write(temp_dir.path().join("report.pdf"), "quarterly report")?;
+        fs::write(temp_dir.path().join("huge.pdf"), vec![b'x'; 4096])?;
+        fs::write(temp_dir.path().join("draft_report.pdf"), "draft of the report")?;
+        fs::write(temp_dir.path().join("photo.jpg"), "a photo of the beach")?;
+
+        let result = FileAnalyzer::builder(temp_dir.path())
+            .min_size(5)
+            .max_size(1024)
+            .include_extensions(vec!["PDF"])
+            .exclude_patterns(vec![Regex::new("^draft_")?])
+            .build()?
+            .analyze()?;
+        let names: Vec<_> = result
+            .categories
+            .values()
+            .flatten()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["report.pdf"]);
+        // Files outside the size range are still scanned, unlike excluded ones
+        assert_eq!(result.total_files, 3);
+        assert_eq!(result.supported_files, 1);
+
+        let missing = FileAnalyzer::builder(temp_dir.path().join("missing")).build();
+        assert!(missing.is_err_and(|e| e.to_string().ends_with("does not exist")));
+        let file = FileAnalyzer::builder(temp_dir.path().join("report.pdf")).build();
+        assert!(file.is_err_and(|e| e.to_string().ends_with("is not a folder")));
+        assert!(FileAnalyzer::builder(temp_dir.path()).min_size(10).max_size(5).build().is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_analyze_skips_directories() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -0,0 +1,692 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// The most links followed before a chain is treated as a cycle, as the kernel does
+pub const MAX_SYMLINK_DEPTH: usize = 40;
+
+// Whether following the symlink at `path` comes back to a link already passed or
+// runs past MAX_SYMLINK_DEPTH. Only the chain of file links is followed, so this
+// always returns; a path that is not a link is not a cycle.
+pub fn is_symlink_cycle(path: &Path) -> bool {
+    let mut current = path.to_path_buf();
+    let mut seen = HashSet::new();
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        let Ok(target) = fs::read_link(&current) else {
+            return false;
+        };
+        let next = match current.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+        if !seen.insert(current) {
+            return true;
+        }
+        current = next;
+    }
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsMetadata {
+    pub len: u64,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+}
+
+// An open file that can be read, written and seeked, as a zip archive needs
+pub trait FileHandle: Read + Write + Seek {}
+
+impl<T: Read + Write + Seek> FileHandle for T {}
+
+// The file operations the organizer goes through, so it can be exercised against an
+// in-memory tree. File dates, EXIF details and whatever a custom verifier opens are
+// still read from the real disk.
+pub trait FileSystem: Send + Sync {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    // Follows symlinks, like `Path::is_dir`
+    fn is_dir(&self, path: &Path) -> bool;
+    // Does not follow symlinks, so a link reports `is_symlink`
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    // The paths of a folder's children, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()>;
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>>;
+    // Creates or truncates the file at `path`
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + '_>>;
+    // Opens the file at `path` for reading and writing, creating it empty if missing
+    fn open_rw(&self, path: &Path) -> io::Result<Box<dyn FileHandle + '_>>;
+    fn permissions(&self, path: &Path) -> io::Result<fs::Permissions>;
+    fn set_permissions(&self, path: &Path, permissions: fs::Permissions) -> io::Result<()>;
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.create(path)?.write_all(contents)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let meta = fs::symlink_metadata(path)?;
+        Ok(FsMetadata {
+            len: meta.len(),
+            is_dir: meta.is_dir(),
+            is_symlink: meta.file_type().is_symlink(),
+        })
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect()
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    #[cfg(not(unix))]
+    fn symlink(&self, _target: &Path, link: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            format!("cannot link {}: symlinks are only supported on Unix", link.display()),
+        ))
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + '_>> {
+        Ok(Box::new(fs::File::create(path)?))
+    }
+
+    fn open_rw(&self, path: &Path) -> io::Result<Box<dyn FileHandle + '_>> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn permissions(&self, path: &Path) -> io::Result<fs::Permissions> {
+        Ok(fs::metadata(path)?.permissions())
+    }
+
+    fn set_permissions(&self, path: &Path, permissions: fs::Permissions) -> io::Result<()> {
+        fs::set_permissions(path, permissions)
+    }
+}
+
+impl<T: FileSystem + ?Sized> FileSystem for Arc<T> {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        (**self).rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        (**self).copy(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        (**self).remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        (**self).create_dir_all(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        (**self).exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        (**self).is_dir(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        (**self).metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        (**self).canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        (**self).read_dir(path)
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        (**self).symlink(target, link)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        (**self).read_link(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        (**self).open(path)
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + '_>> {
+        (**self).create(path)
+    }
+
+    fn open_rw(&self, path: &Path) -> io::Result<Box<dyn FileHandle + '_>> {
+        (**self).open_rw(path)
+    }
+
+    fn permissions(&self, path: &Path) -> io::Result<fs::Permissions> {
+        (**self).permissions(path)
+    }
+
+    fn set_permissions(&self, path: &Path, permissions: fs::Permissions) -> io::Result<()> {
+        (**self).set_permissions(path, permissions)
+    }
+}
+
+#[derive(Default)]
+struct MemoryTree {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+    // Symlinks and the target each one holds, relative to its folder unless absolute
+    links: BTreeMap<PathBuf, PathBuf>,
+    // Files without an entry have the default mode
+    permissions: BTreeMap<PathBuf, fs::Permissions>,
+    // Roots of separately mounted trees; renames between them fail as across devices
+    devices: Vec<PathBuf>,
+    read_only: Vec<PathBuf>,
+}
+
+// A tree of files, folders and symlinks held in memory. Paths are used as given,
+// without resolving `..` or symlinks in their folders, and a file's parent folder
+// must exist before it is written, as on disk. Only a link's own target is followed.
+#[derive(Default)]
+pub struct MemoryFileSystem {
+    tree: Mutex<MemoryTree>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a file, creating its parent folders
+    pub fn with_file(self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            let _ = self.create_dir_all(parent);
+        }
+        self.lock().files.insert(path, contents.into());
+        self
+    }
+
+    // Mounts `root` as another device, so renames into or out of it fail
+    pub fn with_device(self, root: impl Into<PathBuf>) -> Self {
+        self.lock().devices.push(root.into());
+        self
+    }
+
+    // Files under `root` cannot be created, moved or removed
+    pub fn with_read_only(self, root: impl Into<PathBuf>) -> Self {
+        self.lock().read_only.push(root.into());
+        self
+    }
+
+    pub fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        self.lock().files.get(path).cloned()
+    }
+
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.lock().files.keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryTree> {
+        self.tree.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(ErrorKind::NotFound, format!("{} not found", path.display()))
+    }
+
+    fn device_of<'a>(tree: &'a MemoryTree, path: &Path) -> Option<&'a PathBuf> {
+        tree.devices
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+    }
+
+    fn check_writable(tree: &MemoryTree, path: &Path) -> io::Result<()> {
+        match tree.read_only.iter().any(|root| path.starts_with(root)) {
+            true => Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} is read-only", path.display()),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    // Follows `path` while it names a link; a relative target is joined to the link's
+    // folder and its `..` parts applied
+    fn resolve(tree: &MemoryTree, path: &Path) -> PathBuf {
+        let mut current = path.to_path_buf();
+        for _ in 0..MAX_SYMLINK_DEPTH {
+            let Some(target) = tree.links.get(&current) else {
+                break;
+            };
+            let joined = current.parent().map(|p| p.join(target)).unwrap_or_else(|| target.clone());
+            current = PathBuf::new();
+            for component in joined.components() {
+                match component {
+                    std::path::Component::ParentDir => {
+                        current.pop();
+                    }
+                    std::path::Component::CurDir => {}
+                    other => current.push(other),
+                }
+            }
+        }
+        current
+    }
+
+    #[cfg(unix)]
+    fn default_permissions() -> io::Result<fs::Permissions> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(fs::Permissions::from_mode(0o644))
+    }
+
+    #[cfg(not(unix))]
+    fn default_permissions() -> io::Result<fs::Permissions> {
+        Err(io::Error::new(ErrorKind::Unsupported, "no default permissions on this platform"))
+    }
+
+    fn check_parent(tree: &MemoryTree, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !tree.dirs.contains(parent) => {
+                Err(Self::not_found(parent))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, from)?;
+        Self::check_writable(&tree, to)?;
+        Self::check_parent(&tree, to)?;
+        if Self::device_of(&tree, from) != Self::device_of(&tree, to) {
+            return Err(io::Error::new(
+                ErrorKind::CrossesDevices,
+                format!("{} and {} are on different devices", from.display(), to.display()),
+            ));
+        }
+        if let Some(target) = tree.links.remove(from) {
+            tree.links.insert(to.to_path_buf(), target);
+            return Ok(());
+        }
+        let contents = tree.files.remove(from).ok_or_else(|| Self::not_found(from))?;
+        tree.files.insert(to.to_path_buf(), contents);
+        if let Some(permissions) = tree.permissions.remove(from) {
+            tree.permissions.insert(to.to_path_buf(), permissions);
+        }
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, to)?;
+        Self::check_parent(&tree, to)?;
+        let from = Self::resolve(&tree, from);
+        let contents = tree.files.get(&from).cloned().ok_or_else(|| Self::not_found(&from))?;
+        let len = contents.len() as u64;
+        tree.files.insert(to.to_path_buf(), contents);
+        match tree.permissions.get(&from).cloned() {
+            Some(permissions) => tree.permissions.insert(to.to_path_buf(), permissions),
+            None => tree.permissions.remove(to),
+        };
+        Ok(len)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, path)?;
+        if tree.links.remove(path).is_some() {
+            return Ok(());
+        }
+        tree.permissions.remove(path);
+        tree.files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut tree = self.lock();
+        for ancestor in path.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+            if tree.files.contains_key(ancestor) {
+                return Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("{} is a file", ancestor.display()),
+                ));
+            }
+            tree.dirs.insert(ancestor.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let tree = self.lock();
+        let path = Self::resolve(&tree, path);
+        tree.files.contains_key(&path) || tree.dirs.contains(&path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let tree = self.lock();
+        tree.dirs.contains(&Self::resolve(&tree, path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let tree = self.lock();
+        if let Some(target) = tree.links.get(path) {
+            return Ok(FsMetadata {
+                len: target.as_os_str().len() as u64,
+                is_dir: false,
+                is_symlink: true,
+            });
+        }
+        match tree.files.get(path) {
+            Some(contents) => Ok(FsMetadata {
+                len: contents.len() as u64,
+                is_dir: false,
+                is_symlink: false,
+            }),
+            None if tree.dirs.contains(path) => Ok(FsMetadata {
+                len: 0,
+                is_dir: true,
+                is_symlink: false,
+            }),
+            None => Err(Self::not_found(path)),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let tree = self.lock();
+        let resolved = Self::resolve(&tree, path);
+        match tree.files.contains_key(&resolved) || tree.dirs.contains(&resolved) {
+            true => Ok(resolved),
+            false => Err(Self::not_found(path)),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let tree = self.lock();
+        let dir = Self::resolve(&tree, path);
+        if !tree.dirs.contains(&dir) {
+            return Err(Self::not_found(path));
+        }
+        let children = tree.files.keys().chain(&tree.dirs).chain(tree.links.keys());
+        Ok(children
+            .filter(|child| child.parent() == Some(dir.as_path()))
+            .map(|child| path.join(child.file_name().unwrap_or_default()))
+            .collect())
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, link)?;
+        Self::check_parent(&tree, link)?;
+        if tree.files.contains_key(link) || tree.dirs.contains(link) || tree.links.contains_key(link) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", link.display()),
+            ));
+        }
+        tree.links.insert(link.to_path_buf(), target.to_path_buf());
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.lock().links.get(path).cloned().ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, format!("{} is not a symlink", path.display()))
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let tree = self.lock();
+        let resolved = Self::resolve(&tree, path);
+        let contents = tree.files.get(&resolved).cloned().ok_or_else(|| Self::not_found(path))?;
+        Ok(Box::new(Cursor::new(contents)))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + '_>> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, path)?;
+        Self::check_parent(&tree, path)?;
+        let path = Self::resolve(&tree, path);
+        tree.files.insert(path.clone(), Vec::new());
+        Ok(Box::new(MemoryWriter { file_system: self, path }))
+    }
+
+    fn open_rw(&self, path: &Path) -> io::Result<Box<dyn FileHandle + '_>> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, path)?;
+        Self::check_parent(&tree, path)?;
+        let path = Self::resolve(&tree, path);
+        tree.files.entry(path.clone()).or_default();
+        Ok(Box::new(MemoryFile { file_system: self, path, position: 0 }))
+    }
+
+    fn permissions(&self, path: &Path) -> io::Result<fs::Permissions> {
+        let tree = self.lock();
+        let resolved = Self::resolve(&tree, path);
+        if !tree.files.contains_key(&resolved) {
+            return Err(Self::not_found(path));
+        }
+        match tree.permissions.get(&resolved) {
+            Some(permissions) => Ok(permissions.clone()),
+            None => Self::default_permissions(),
+        }
+    }
+
+    fn set_permissions(&self, path: &Path, permissions: fs::Permissions) -> io::Result<()> {
+        let mut tree = self.lock();
+        Self::check_writable(&tree, path)?;
+        let resolved = Self::resolve(&tree, path);
+        if !tree.files.contains_key(&resolved) {
+            return Err(Self::not_found(path));
+        }
+        tree.permissions.insert(resolved, permissions);
+        Ok(())
+    }
+}
+
+// Appends each write to the file in the tree, so the contents are there as soon as
+// the write returns
+struct MemoryWriter<'a> {
+    file_system: &'a MemoryFileSystem,
+    path: PathBuf,
+}
+
+impl Write for MemoryWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut tree = self.file_system.lock();
+        let file = tree
+            .files
+            .get_mut(&self.path)
+            .ok_or_else(|| MemoryFileSystem::not_found(&self.path))?;
+        file.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// A read and write handle on a file in the tree; writes past the end extend it
+struct MemoryFile<'a> {
+    file_system: &'a MemoryFileSystem,
+    path: PathBuf,
+    position: u64,
+}
+
+impl MemoryFile<'_> {
+    fn with_contents<T>(&self, f: impl FnOnce(&mut Vec<u8>) -> T) -> io::Result<T> {
+        let mut tree = self.file_system.lock();
+        let contents = tree
+            .files
+            .get_mut(&self.path)
+            .ok_or_else(|| MemoryFileSystem::not_found(&self.path))?;
+        Ok(f(contents))
+    }
+}
+
+impl Read for MemoryFile<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = self.position as usize;
+        let read = self.with_contents(|contents| {
+            let available = contents.get(start..).unwrap_or_default();
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        })?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Write for MemoryFile<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = self.position as usize;
+        self.with_contents(|contents| {
+            if contents.len() < start + buf.len() {
+                contents.resize(start + buf.len(), 0);
+            }
+            contents[start..start + buf.len()].copy_from_slice(buf);
+        })?;
+        self.position += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let len = self.with_contents(|contents| contents.len() as u64)?;
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "seek before the start of the file")
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_file_system_needs_parent_folders() {
+        let fs = MemoryFileSystem::new().with_file("/desk/a.pdf", "report");
+
+        assert!(fs.rename(Path::new("/desk/a.pdf"), Path::new("/desk/PDFs/a.pdf")).is_err());
+        fs.create_dir_all(Path::new("/desk/PDFs")).unwrap();
+        fs.rename(Path::new("/desk/a.pdf"), Path::new("/desk/PDFs/a.pdf")).unwrap();
+
+        assert!(!fs.exists(Path::new("/desk/a.pdf")));
+        assert_eq!(fs.read(Path::new("/desk/PDFs/a.pdf")), Some(b"report".to_vec()));
+        assert!(fs.metadata(Path::new("/desk/PDFs")).unwrap().is_dir);
+        assert_eq!(fs.metadata(Path::new("/desk/PDFs/a.pdf")).unwrap().len, 6);
+    }
+
+    #[test]
+    fn test_memory_file_system_follows_links() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/desk/notes/a.txt", "notes")
+            .with_file("/desk/PDFs/b.pdf", "report");
+        fs.symlink(Path::new("../notes/a.txt"), Path::new("/desk/PDFs/a.txt")).unwrap();
+
+        assert!(fs.metadata(Path::new("/desk/PDFs/a.txt")).unwrap().is_symlink);
+        assert!(fs.exists(Path::new("/desk/PDFs/a.txt")));
+        let mut contents = String::new();
+        fs.open(Path::new("/desk/PDFs/a.txt")).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "notes");
+
+        let mut children = fs.read_dir(Path::new("/desk/PDFs")).unwrap();
+        children.sort();
+        assert_eq!(children, vec![PathBuf::from("/desk/PDFs/a.txt"), PathBuf::from("/desk/PDFs/b.pdf")]);
+
+        fs.remove_file(Path::new("/desk/notes/a.txt")).unwrap();
+        assert!(!fs.exists(Path::new("/desk/PDFs/a.txt")));
+        assert!(fs.metadata(Path::new("/desk/PDFs/a.txt")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_cycle_detected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("real.pdf"), "real").unwrap();
+        std::os::unix::fs::symlink("real.pdf", dir.join("link.pdf")).unwrap();
+        std::os::unix::fs::symlink("b.pdf", dir.join("a.pdf")).unwrap();
+        std::os::unix::fs::symlink("a.pdf", dir.join("b.pdf")).unwrap();
+        std::os::unix::fs::symlink("self.pdf", dir.join("self.pdf")).unwrap();
+
+        assert!(is_symlink_cycle(&dir.join("a.pdf")));
+        assert!(is_symlink_cycle(&dir.join("self.pdf")));
+        assert!(!is_symlink_cycle(&dir.join("link.pdf")));
+        assert!(!is_symlink_cycle(&dir.join("real.pdf")));
+        assert!(!is_symlink_cycle(&dir.join("missing.pdf")));
+    }
+}
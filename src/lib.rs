@@ -3,8 +3,25 @@ pub mod duplicate_finder;
 pub mod organizer;
 pub mod types;
 pub mod display;
+pub mod checksum_cache;
+pub mod categories;
+pub mod collation;
+pub mod config;
+pub mod dates;
+pub mod doctor;
+pub mod filesystem;
+pub mod location;
+pub mod metadata;
+pub mod plan;
+pub mod review;
+pub mod rules;
+pub mod snapshot;
+pub mod state;
+pub mod throttle;
+pub mod undo;
+pub mod watcher;
 
 pub use file_analyzer::FileAnalyzer;
-pub use duplicate_finder::DuplicateFinder;
+pub use duplicate_finder::{DuplicateFinder, DuplicateKeyer};
 pub use organizer::Organizer;
 pub use types::{FileCategory, FileEntry, DuplicateGroup};
@@ -0,0 +1,234 @@
+use exif::{In, Reader, Tag, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// Photos without GPS data, or whose coordinates cannot be read, go here
+pub const UNKNOWN_LOCATION: &str = "unknown";
+
+// Beyond this distance from every known city a photo is bucketed by a 1° grid cell
+const MAX_CITY_DISTANCE_KM: f64 = 150.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// A coarse offline gazetteer: (country, city, latitude, longitude)
+const PLACES: &[(&str, &str, f64, f64)] = &[
+    ("Argentina", "Buenos Aires", -34.60, -58.38),
+    ("Australia", "Melbourne", -37.81, 144.96),
+    ("Australia", "Sydney", -33.87, 151.21),
+    ("Austria", "Vienna", 48.21, 16.37),
+    ("Belgium", "Brussels", 50.85, 4.35),
+    ("Brazil", "Rio de Janeiro", -22.91, -43.17),
+    ("Brazil", "Sao Paulo", -23.55, -46.63),
+    ("Canada", "Montreal", 45.50, -73.57),
+    ("Canada", "Toronto", 43.65, -79.38),
+    ("Canada", "Vancouver", 49.28, -123.12),
+    ("China", "Beijing", 39.90, 116.41),
+    ("China", "Hong Kong", 22.32, 114.17),
+    ("China", "Shanghai", 31.23, 121.47),
+    ("Czechia", "Prague", 50.08, 14.44),
+    ("Denmark", "Copenhagen", 55.68, 12.57),
+    ("Egypt", "Cairo", 30.04, 31.24),
+    ("France", "Nice", 43.70, 7.27),
+    ("France", "Paris", 48.86, 2.35),
+    ("Germany", "Berlin", 52.52, 13.40),
+    ("Germany", "Munich", 48.14, 11.58),
+    ("Greece", "Athens", 37.98, 23.73),
+    ("Hungary", "Budapest", 47.50, 19.04),
+    ("Iceland", "Reykjavik", 64.15, -21.94),
+    ("India", "Delhi", 28.61, 77.21),
+    ("India", "Mumbai", 19.08, 72.88),
+    ("Indonesia", "Bali", -8.65, 115.22),
+    ("Ireland", "Dublin", 53.35, -6.26),
+    ("Italy", "Florence", 43.77, 11.26),
+    ("Italy", "Milan", 45.46, 9.19),
+    ("Italy", "Rome", 41.90, 12.50),
+    ("Italy", "Venice", 45.44, 12.32),
+    ("Japan", "Kyoto", 35.01, 135.77),
+    ("Japan", "Osaka", 34.69, 135.50),
+    ("Japan", "Tokyo", 35.68, 139.69),
+    ("Kenya", "Nairobi", -1.29, 36.82),
+    ("Malaysia", "Kuala Lumpur", 3.139, 101.687),
+    ("Mexico", "Mexico City", 19.43, -99.13),
+    ("Morocco", "Marrakesh", 31.63, -8.01),
+    ("Netherlands", "Amsterdam", 52.37, 4.90),
+    ("New Zealand", "Auckland", -36.85, 174.76),
+    ("Norway", "Oslo", 59.91, 10.75),
+    ("Peru", "Lima", -12.05, -77.04),
+    ("Portugal", "Lisbon", 38.72, -9.14),
+    ("Singapore", "Singapore", 1.35, 103.82),
+    ("South Africa", "Cape Town", -33.92, 18.42),
+    ("South Korea", "Seoul", 37.57, 126.98),
+    ("Spain", "Barcelona", 41.39, 2.17),
+    ("Spain", "Madrid", 40.42, -3.70),
+    ("Sweden", "Stockholm", 59.33, 18.07),
+    ("Switzerland", "Zurich", 47.38, 8.54),
+    ("Taiwan", "Taipei", 25.03, 121.57),
+    ("Thailand", "Bangkok", 13.76, 100.50),
+    ("Turkey", "Istanbul", 41.01, 28.98),
+    ("United Arab Emirates", "Dubai", 25.20, 55.27),
+    ("United Kingdom", "Edinburgh", 55.95, -3.19),
+    ("United Kingdom", "London", 51.51, -0.13),
+    ("United States", "Chicago", 41.88, -87.63),
+    ("United States", "Honolulu", 21.31, -157.86),
+    ("United States", "Las Vegas", 36.17, -115.14),
+    ("United States", "Los Angeles", 34.05, -118.24),
+    ("United States", "Miami", 25.76, -80.19),
+    ("United States", "New York", 40.71, -74.01),
+    ("United States", "San Francisco", 37.77, -122.42),
+    ("United States", "Seattle", 47.61, -122.33),
+    ("Vietnam", "Hanoi", 21.03, 105.85),
+];
+
+// Reads the GPS position from a photo's EXIF data as signed decimal degrees
+pub fn read_gps(path: &Path) -> Option<(f64, f64)> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+    let coordinate = |value_tag: Tag, ref_tag: Tag, negative: u8| {
+        let Value::Rational(parts) = &exif.get_field(value_tag, In::PRIMARY)?.value else {
+            return None;
+        };
+        let [degrees, minutes, seconds] = parts.get(..3)? else {
+            return None;
+        };
+        let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+        let is_negative = match &exif.get_field(ref_tag, In::PRIMARY)?.value {
+            Value::Ascii(refs) => refs.first().and_then(|r| r.first()) == Some(&negative),
+            _ => false,
+        };
+        value
+            .is_finite()
+            .then_some(if is_negative { -value } else { value })
+    };
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')?;
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')?;
+    Some((latitude, longitude))
+}
+
+fn distance_km((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// `<Country>/<City>` for the nearest known city, or a coarse `Other/<cell>` folder
+// such as `Other/N46_W122` when the photo was taken far from all of them
+pub fn place_folder(latitude: f64, longitude: f64) -> PathBuf {
+    let nearest = PLACES
+        .iter()
+        .map(|&(country, city, lat, lon)| (country, city, distance_km((latitude, longitude), (lat, lon))))
+        .min_by(|a, b| a.2.total_cmp(&b.2));
+    match nearest {
+        Some((country, city, distance)) if distance <= MAX_CITY_DISTANCE_KM => {
+            Path::new(country).join(city)
+        }
+        _ => Path::new("Other").join(format!(
+            "{}{:02}_{}{:03}",
+            if latitude < 0.0 { 'S' } else { 'N' },
+            latitude.abs().floor() as u32,
+            if longitude < 0.0 { 'W' } else { 'E' },
+            longitude.abs().floor() as u32
+        )),
+    }
+}
+
+pub fn location_folder(path: &Path) -> PathBuf {
+    match read_gps(path) {
+        Some((latitude, longitude)) => place_folder(latitude, longitude),
+        None => PathBuf::from(UNKNOWN_LOCATION),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use exif::{Field, Rational};
+    use exif::experimental::Writer;
+
+    fn dms(value: f64) -> Vec<Rational> {
+        let degrees = value.floor();
+        let minutes = ((value - degrees) * 60.0).floor();
+        let seconds = ((value - degrees) * 60.0 - minutes) * 60.0;
+        vec![
+            Rational::from((degrees as u32, 1)),
+            Rational::from((minutes as u32, 1)),
+            Rational::from(((seconds * 100.0).round() as u32, 100)),
+        ]
+    }
+
+    // A minimal JPEG whose only content is an EXIF block with the given position
+    pub(crate) fn jpeg_with_gps(latitude: f64, longitude: f64) -> Vec<u8> {
+        jpeg_with_fields(&[
+            Field {
+                tag: Tag::GPSLatitudeRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![if latitude < 0.0 { b"S".to_vec() } else { b"N".to_vec() }]),
+            },
+            Field {
+                tag: Tag::GPSLatitude,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(dms(latitude.abs())),
+            },
+            Field {
+                tag: Tag::GPSLongitudeRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![if longitude < 0.0 { b"W".to_vec() } else { b"E".to_vec() }]),
+            },
+            Field {
+                tag: Tag::GPSLongitude,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(dms(longitude.abs())),
+            },
+        ])
+    }
+
+    // A minimal JPEG whose only content is an EXIF block holding `fields`
+    pub(crate) fn jpeg_with_fields(fields: &[Field]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).expect("EXIF fields serialize");
+        let tiff = tiff.into_inner();
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_read_gps_and_nearest_city() -> anyhow::Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let photo = temp_dir.path().join("eiffel.jpg");
+        // The Eiffel Tower
+        std::fs::write(&photo, jpeg_with_gps(48.8584, 2.2945))?;
+
+        let (latitude, longitude) = read_gps(&photo).expect("GPS data is readable");
+        assert!((latitude - 48.8584).abs() < 0.001);
+        assert!((longitude - 2.2945).abs() < 0.001);
+        assert_eq!(location_folder(&photo), Path::new("France").join("Paris"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remote_and_missing_locations() -> anyhow::Result<()> {
+        // Southern hemisphere and western longitudes keep their signs
+        assert_eq!(place_folder(-33.9, 151.2), Path::new("Australia").join("Sydney"));
+        assert_eq!(place_folder(-45.5, -120.3), Path::new("Other").join("S45_W120"));
+
+        let temp_dir = tempfile::TempDir::new()?;
+        let plain = temp_dir.path().join("plain.jpg");
+        std::fs::write(&plain, b"not a real jpeg")?;
+        assert_eq!(location_folder(&plain), PathBuf::from(UNKNOWN_LOCATION));
+
+        Ok(())
+    }
+}
@@ -1,20 +1,46 @@
-use clap::Parser;
+use anyhow::{Context, bail};
+use clap::{Parser, Subcommand};
 use desktidy::{
-    display::DisplayFormatter, duplicate_finder::DuplicateFinder, file_analyzer::FileAnalyzer,
-    organizer::Organizer,
+    categories::CategorySet,
+    checksum_cache::ChecksumCache,
+    collation::SortLocale,
+    config::Config,
+    dates::{DateFormat, DateGranularity},
+    display::{ActionsMode, DisplayFormatter, OutputFormat, TableStyle},
+    doctor::Doctor,
+    duplicate_finder::{DuplicateFinder, HashAlgorithm, SampledHashKeyer},
+    file_analyzer::FileAnalyzer,
+    organizer::{DuplicateAction, OrganizeBy, Organizer, PlanFilter, UnicodeForm, DEFAULT_COLLISION_SUFFIX},
+    plan::Plan,
+    review::ReviewState,
+    rules::RuleSet,
+    snapshot::{SNAPSHOT_AFTER_FILE_NAME, SNAPSHOT_BEFORE_FILE_NAME, Snapshot},
+    state::{RunState, STATE_FILE_NAME},
+    throttle::Throttle,
+    types::{ClassifyBy, ConflictStrategy, KeepStrategy, MoveMode},
+    undo::UNDO_LOG_FILE_NAME,
+    watcher::FolderWatcher,
 };
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(
     name = "desktidy",
     about = "Organize files (Office Documents, PDFs, Images, Videos, Audio) in a folder",
-    long_about = "A command-line tool to organize files into categorized folders.\nOnly processes files in the root folder, ignoring subfolders."
+    long_about = "A command-line tool to organize files into categorized folders.\nProcesses files in the root folder only, unless --recursive (limited by --depth) also scans its subfolders."
 )]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the folder to organize
-    #[arg(value_name = "FOLDER_PATH")]
-    folder_path: PathBuf,
+    #[arg(value_name = "FOLDER_PATH", required_unless_present = "undo")]
+    folder_path: Option<PathBuf>,
 
     /// Only analyze files without moving them (dry run)
     #[arg(long)]
@@ -23,29 +49,679 @@ struct Args {
     /// Show detailed progress during analysis
     #[arg(short, long)]
     verbose: bool,
+
+    /// Split Documents into Word, OpenDocument and Plain subfolders
+    #[arg(long)]
+    group_documents_by_type: bool,
+
+    /// Verify duplicate candidates in parallel
+    #[arg(long)]
+    dedup_parallel_verify: bool,
+
+    /// Maximum files per destination folder before rolling over into Name_2, Name_3, ...
+    #[arg(long, value_name = "N")]
+    max_per_folder: Option<usize>,
+
+    /// Replace duplicates with symlinks in Duplicates pointing at the organized original
+    #[arg(long)]
+    link_duplicates: bool,
+
+    /// Leave duplicates in place and list them in duplicates.log instead of moving them
+    #[arg(long, conflicts_with = "link_duplicates")]
+    quiet_duplicates: bool,
+
+    /// Send duplicates to the system trash instead of the Duplicates folder
+    #[arg(long, conflicts_with_all = ["link_duplicates", "quiet_duplicates", "copy"])]
+    trash_duplicates: bool,
+
+    /// Organize symlinked files as real copies of their targets, leaving the targets untouched
+    #[arg(long)]
+    organize_symlinks_as_copies: bool,
+
+    /// Classify files by extension or by detected MIME type
+    #[arg(long, value_enum, default_value = "extension")]
+    classify_by: ClassifyBy,
+
+    /// What to do when a file with the same name is already at the destination
+    #[arg(long, value_enum, default_value = "rename")]
+    on_conflict: ConflictStrategy,
+
+    /// Suffix format for renamed collisions, using counter and timestamp placeholders
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = DEFAULT_COLLISION_SUFFIX,
+        value_parser = parse_collision_suffix
+    )]
+    collision_suffix: String,
+
+    /// Bundle each category into a zip archive instead of a folder
+    #[arg(long)]
+    archive: bool,
+
+    /// Detect scripts (shebang) and known file signatures in files without a supported extension
+    #[arg(long, visible_alias = "detect-content")]
+    sniff: bool,
+
+    /// File by content when a file's leading bytes disagree with its extension
+    #[arg(long)]
+    by_content: bool,
+
+    /// Sniff samples of unknown extensions and route them by their dominant content type
+    #[arg(long)]
+    learn_extensions: bool,
+
+    /// Also scan subfolders of the target folder
+    #[arg(long)]
+    recursive: bool,
+
+    /// How many subfolder levels --recursive descends; 0 means no limit
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "recursive")]
+    depth: usize,
+
+    /// Normalize destination file names to this Unicode form
+    #[arg(long, value_enum, value_name = "FORM")]
+    normalize_unicode: Option<UnicodeForm>,
+
+    /// Re-apply each file's original permissions after moving it
+    #[arg(long)]
+    preserve_permissions: bool,
+
+    /// Comma-separated extensions that are analyzed but never moved
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    ignore_extension: Vec<String>,
+
+    /// Leave files whose names match this glob untouched (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    exclude: Vec<glob::Pattern>,
+
+    /// Only organize files of at least this size, e.g. 500KB or 1MB
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+
+    /// Only organize files of at most this size
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_size: Option<u64>,
+
+    /// Only organize files last modified at least this long ago, e.g. 30d or 2w
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    older_than: Option<Duration>,
+
+    /// Only organize files modified within this long, e.g. 12h or 7d
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    newer_than: Option<Duration>,
+
+    /// Nest files in date folders by modification time
+    #[arg(long, value_enum, conflicts_with = "date_format")]
+    date_granularity: Option<DateGranularity>,
+
+    /// Custom date folder pattern: %Y year, %m month, %d day, %D decade, '/' nests folders
+    #[arg(long, value_name = "FORMAT", value_parser = parse_date_format)]
+    date_format: Option<DateFormat>,
+
+    /// Nest images in year/month folders by their EXIF capture date, or modification time
+    #[arg(long, conflicts_with_all = ["date_granularity", "date_format"])]
+    date_folders: bool,
+
+    /// Nest every category in year/month folders by modification time
+    #[arg(long, conflicts_with_all = ["date_folders", "date_granularity", "date_format"])]
+    by_date: bool,
+
+    /// Organize hidden files (names starting with '.') instead of skipping them
+    #[arg(long)]
+    include_hidden: bool,
+
+    /// Look for duplicates among hidden files even when they are not organized
+    #[arg(long)]
+    dedup_include_hidden: bool,
+
+    /// TOML file of routing rules checked before the built-in categories (first match wins)
+    #[arg(long, value_name = "FILE")]
+    organize_by_rules_file: Option<PathBuf>,
+
+    /// Organize partial downloads (.crdownload, .part, .download, .tmp) instead of skipping them
+    #[arg(long)]
+    include_partial_downloads: bool,
+
+    /// TOML file of categories and extensions that replaces the built-in categories
+    #[arg(long, value_name = "FILE")]
+    categories_config: Option<PathBuf>,
+
+    /// Config file with extra [categories] mappings (default: the folder's desktidy.toml, then ~/.config/desktidy/config.toml)
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Only process files modified since the last run with this flag
+    #[arg(long)]
+    since_last_run: bool,
+
+    /// Keep a symlinked folder path as given instead of resolving it
+    #[arg(long)]
+    no_resolve_root: bool,
+
+    /// Border style for the summary table
+    #[arg(long, value_enum, default_value = "full")]
+    table_style: TableStyle,
+
+    /// Order of file names in the summary: root (Unicode), bytes or arabic (default: from the system locale)
+    #[arg(long, value_name = "ORDER", value_parser = parse_locale)]
+    locale: Option<SortLocale>,
+
+    /// Only report files and duplicates this run actually moved
+    #[arg(long)]
+    changes_only: bool,
+
+    /// Show duplicates as a tree grouped by the folder of each kept original
+    #[arg(long)]
+    report_duplicates_tree: bool,
+
+    /// Also write the duplicate groups, with the kept original and reclaimable bytes, to FILE as JSON
+    #[arg(long, value_name = "FILE")]
+    dedup_report_json: Option<PathBuf>,
+
+    /// Show duplicate paths relative to this directory instead of the scan folder
+    #[arg(long, value_name = "DIR")]
+    dedup_show_paths_relative_to: Option<PathBuf>,
+
+    /// Browse duplicate groups in the terminal and choose which files to keep before organizing
+    #[arg(long)]
+    review_duplicates: bool,
+
+    /// Print the summary as text, as a JSON document, or as a diff of the planned changes (with --analyze)
+    #[arg(long, visible_alias = "format", value_enum, default_value = "text")]
+    output: OutputFormat,
+
+    /// How to list actions: every action, counts per destination, or not at all
+    #[arg(long, value_enum, default_value = "all")]
+    actions: ActionsMode,
+
+    /// Add a bar chart of files per category to the summary
+    #[arg(long)]
+    chart: bool,
+
+    /// Print a single summary line instead of the table and action list
+    #[arg(short = 'q', long)]
+    summary_only: bool,
+
+    /// Delete files whose SHA256 hash appears in this file (one hash per line)
+    #[arg(long, value_name = "FILE")]
+    delete_hashes: Option<PathBuf>,
+
+    /// Group duplicates by a hash of sampled chunks (probable, not confirmed matches)
+    #[arg(long)]
+    fast_dedup: bool,
+
+    /// Hash used to compare file contents; blake3 is much faster on large libraries
+    #[arg(long, value_enum, default_value = "md5-sha256")]
+    hash_algorithm: HashAlgorithm,
+
+    /// Which copy of a duplicate group to keep as the original
+    #[arg(long, value_enum, default_value = "oldest")]
+    keep: KeepStrategy,
+
+    /// Hash files for duplicate detection on N threads (default: number of CPUs)
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+
+    /// Compare .gz and single-file .zip files by their decompressed content
+    #[arg(long)]
+    dedup_compressed: bool,
+
+    /// Only act on duplicate groups with at least N files
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    dedup_min_count: usize,
+
+    /// Leave files smaller than this many bytes out of duplicate detection (1 skips empty files)
+    #[arg(long, visible_alias = "dup-min-size", value_name = "BYTES", default_value_t = 0)]
+    dedup_min_size: u64,
+
+    /// Hold at most N files per duplicate group, splitting larger groups
+    #[arg(long, value_name = "N")]
+    dedup_group_limit: Option<usize>,
+
+    /// Stop looking for duplicates after N groups
+    #[arg(long, value_name = "N")]
+    dedup_max_groups: Option<usize>,
+
+    /// Also report files whose first N bytes match but which are not duplicates
+    #[arg(long, value_name = "N")]
+    report_prefix_matches: Option<u64>,
+
+    /// Re-examine files in the Duplicates folder, organizing those that are not duplicates
+    #[arg(long)]
+    process_duplicates_folder: bool,
+
+    /// Do not treat a symlink and the scanned file it points to as duplicates
+    #[arg(long)]
+    dedup_symlink_aware: bool,
+
+    /// Persist checksums to this file as they are computed so interrupted runs can resume
+    #[arg(long, value_name = "FILE")]
+    checksum_cache: Option<PathBuf>,
+
+    /// Write an _index.txt listing the contents of each category folder
+    #[arg(long)]
+    write_index: bool,
+
+    /// Keep running and organize new files as they appear, until Ctrl-C
+    #[arg(long, conflicts_with = "undo")]
+    watch: bool,
+
+    /// Record every file's path, size and SHA-256 in before.json and after.json at the root
+    #[arg(long)]
+    snapshot: bool,
+
+    /// Limit copy and hashing throughput to this many MB/s (useful on network shares)
+    #[arg(long, value_name = "MB/s", value_parser = parse_throttle)]
+    throttle: Option<f64>,
+
+    /// Also write a one-line JSON summary to stderr for scripts
+    #[arg(long)]
+    porcelain: bool,
+
+    /// Carry out only the part of the plan for this category or file name glob
+    #[arg(long, value_name = "CATEGORY|GLOB")]
+    apply_only: Option<String>,
+
+    /// Commit a checkpoint every N moves; a failed move undoes those since the last one and stops
+    #[arg(long, value_name = "N")]
+    checkpoint_every: Option<usize>,
+
+    /// Rename files to YYYY-MM-DD_NNN.ext by modification date, numbered in modification order
+    #[arg(long)]
+    organize_preserving_mtime_order_names: bool,
+
+    /// Sort photos into Images/<Country>/<City> using their EXIF GPS position
+    #[arg(long)]
+    by_location: bool,
+
+    /// Build an organized copy, leaving every original where it is
+    #[arg(long)]
+    copy: bool,
+
+    /// Move by copying, verifying the copy's checksum and only then deleting the source
+    #[arg(long)]
+    safe_mode: bool,
+
+    /// Put back the files moved by earlier runs, from the folder's undo manifest or the one given
+    #[arg(long, value_name = "MANIFEST", num_args = 0..=1)]
+    undo: Option<Option<PathBuf>>,
+
+    /// With --analyze, write the moves the run would make to the undo manifest as a preview
+    #[arg(long, requires = "analyze")]
+    undo_preview: bool,
+
+    /// With --analyze, also show the folder tree the run would leave behind
+    #[arg(long, requires = "analyze")]
+    tree: bool,
+
+    /// Save the moves a run would make to FILE and exit without moving anything; see `apply`
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "watch",
+            "archive",
+            "link_duplicates",
+            "quiet_duplicates",
+            "trash_duplicates",
+            "delete_hashes",
+        ]
+    )]
+    export_plan: Option<PathBuf>,
+
+    /// Answer yes to confirmation prompts
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check writability, free space and the size of the plan without moving anything
+    Doctor {
+        /// Path to the folder to check
+        #[arg(value_name = "FOLDER_PATH")]
+        folder_path: PathBuf,
+
+        /// Show detailed progress during analysis
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Carry out a plan saved with --export-plan, exactly as it was written
+    Apply {
+        /// Path to the plan file
+        #[arg(value_name = "PLAN")]
+        plan: PathBuf,
+
+        /// Show each file as it is skipped
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+fn parse_date_format(pattern: &str) -> Result<DateFormat, String> {
+    DateFormat::parse(pattern).map_err(|e| e.to_string())
+}
+
+fn parse_size(size: &str) -> Result<u64, String> {
+    desktidy::metadata::parse_size(size).map_err(|e| e.to_string())
+}
+
+fn parse_age(age: &str) -> Result<Duration, String> {
+    desktidy::dates::parse_age(age).map_err(|e| e.to_string())
+}
+
+fn parse_glob(pattern: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(pattern).map_err(|e| e.to_string())
+}
+
+fn parse_throttle(rate: &str) -> Result<f64, String> {
+    match rate.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err("throttle must be a positive number of MB/s".to_string()),
+    }
+}
+
+fn parse_locale(name: &str) -> Result<SortLocale, String> {
+    SortLocale::parse(name).map_err(|e| e.to_string())
+}
+
+fn parse_collision_suffix(format: &str) -> Result<String, String> {
+    if format.contains('/') || format.contains('\\') {
+        return Err("suffix must not contain path separators".to_string());
+    }
+    if !format.contains("{n}") && !format.contains("{timestamp}") {
+        return Err("suffix must contain {n} or {timestamp}".to_string());
+    }
+    Ok(format.to_string())
+}
+
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
+    if let Some(Command::Doctor {
+        folder_path,
+        verbose,
+    }) = &args.command
+    {
+        println!("{}", Doctor::run(folder_path, *verbose)?.render());
+        return Ok(());
+    }
+    if let Some(Command::Apply { plan, verbose }) = &args.command {
+        let plan = Plan::load(plan)?;
+        let summary = Organizer::new(plan.folder.clone(), *verbose)
+            .with_mode(plan.mode)
+            .with_undo_log(true)
+            .apply_plan(&plan)?;
+        for action in &summary.actions_taken {
+            println!("[+] {}", action);
+        }
+        if summary.actions_taken.is_empty() {
+            println!("[~] Nothing to apply.");
+        }
+        if !summary.warnings.is_empty() {
+            println!("\n{}", DisplayFormatter::new().warnings_report(&summary.warnings));
+        }
+        return Ok(());
+    }
+    // Undo needs no analysis: `--undo` alone uses FOLDER_PATH, and a folder given to it
+    // stands for the manifest at its root
+    if let Some(manifest) = &args.undo {
+        let manifest = match (manifest, &args.folder_path) {
+            (Some(path), _) if path.is_dir() => path.join(UNDO_LOG_FILE_NAME),
+            (Some(path), _) => path.clone(),
+            (None, Some(folder)) => folder.join(UNDO_LOG_FILE_NAME),
+            (None, None) => bail!("--undo needs FOLDER_PATH or a manifest"),
+        };
+        let folder_path = match &args.folder_path {
+            Some(path) => path.clone(),
+            None => manifest.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        };
+        let summary =
+            Organizer::new(folder_path, args.verbose).undo_from(&manifest, args.analyze)?;
+        for action in &summary.actions_taken {
+            println!("[+] {}", action);
+        }
+        if summary.actions_taken.is_empty() {
+            println!("[~] Nothing to undo.");
+        }
+        if !summary.warnings.is_empty() {
+            println!("\n{}", DisplayFormatter::new().warnings_report(&summary.warnings));
+        }
+        return Ok(());
+    }
+    let folder_path = args.folder_path.clone().context("FOLDER_PATH is required")?;
+
+    organize(&args, &folder_path)?;
+    if args.watch {
+        FolderWatcher::new(folder_path.clone(), args.verbose)
+            .with_recursive(args.recursive)
+            .watch(|_| organize(&args, &folder_path))?;
+    }
+    Ok(())
+}
+
+// One analyze, deduplicate and organize pass over the folder, as set up by the flags
+fn organize(args: &Args, folder_path: &Path) -> anyhow::Result<()> {
+    let folder_path = folder_path.to_path_buf();
+    // Progress lines would break the document, so JSON output is never verbose
+    let verbose = args.verbose && args.output != OutputFormat::Json;
+    // Exporting a plan only ever looks
+    let dry_run = args.analyze || args.export_plan.is_some();
+
+    let rules = match &args.organize_by_rules_file {
+        Some(path) => RuleSet::load(path)?,
+        None => RuleSet::default(),
+    };
+
+    let categories = match &args.categories_config {
+        Some(path) => CategorySet::load(path)?,
+        None => CategorySet::default(),
+    };
+    let config = match &args.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::load_for_folder(&folder_path)?,
+    };
+    let categories = config.apply(&categories)?;
+    let category_roots = config.category_roots(&categories, &folder_path)?;
+
+    let before_snapshot = match args.snapshot && !dry_run {
+        true => Some(Snapshot::capture(&folder_path)?),
+        false => None,
+    };
+
+    // Taken before scanning so files changed during this run are picked up next time
+    let run_started = std::time::SystemTime::now();
+    let mut run_state = if args.since_last_run {
+        RunState::load(&folder_path)?
+    } else {
+        RunState::default()
+    };
+
     // Analyze files
-    let analyzer = FileAnalyzer::new(args.folder_path.clone(), args.verbose);
+    let mut analyzer = FileAnalyzer::builder(folder_path.clone()).verbose(verbose);
+    if args.recursive {
+        analyzer = analyzer.recursive(args.depth);
+    }
+    if let Some(bytes) = args.min_size {
+        analyzer = analyzer.min_size(bytes);
+    }
+    if let Some(bytes) = args.max_size {
+        analyzer = analyzer.max_size(bytes);
+    }
+    let mut analyzer = analyzer
+        .build()?
+        .with_resolve_root(!args.no_resolve_root)
+        .with_classify_by(args.classify_by)
+        .with_sniff(args.sniff)
+        .with_by_content(args.by_content)
+        .with_exclude(args.exclude.clone())
+        .with_include_hidden(args.include_hidden)
+        .with_rules(rules.clone())
+        .with_skip_partial_downloads(!args.include_partial_downloads)
+        .with_categories(categories.clone())
+        .with_modified_after(run_state.last_run())
+        .with_learn_extensions(args.learn_extensions)
+        .with_process_duplicates_folder(args.process_duplicates_folder)
+        .with_archive(args.archive);
+    if let Some(age) = args.older_than {
+        analyzer = analyzer.with_older_than(age);
+    }
+    if let Some(age) = args.newer_than {
+        analyzer = analyzer.with_newer_than(age);
+    }
     let analysis = analyzer.analyze()?;
 
-    // Collect all entries
+    // Collect all entries, in path order so the same folder always gives the same plan
     let mut all_entries = Vec::new();
     for entries in analysis.categories.values() {
         all_entries.extend(entries.clone());
     }
+    all_entries.sort_by(|a, b| a.path.cmp(&b.path));
 
-    // Find duplicates
-    let finder = DuplicateFinder::new(args.verbose);
-    let duplicates = finder.find_duplicates(&all_entries)?;
+    let throttle = args.throttle.map(|rate| Arc::new(Throttle::new(rate)));
+    let mut finder = DuplicateFinder::new_with_strategy(verbose, args.keep)
+        .with_throttle(throttle.clone())
+        .with_min_count(args.dedup_min_count)
+        .with_min_size(args.dedup_min_size)
+        .with_group_limit(args.dedup_group_limit)
+        .with_max_groups(args.dedup_max_groups)
+        .with_parallel_verify(args.dedup_parallel_verify)
+        .with_symlink_aware(args.dedup_symlink_aware)
+        .with_demoted_folder(
+            args.process_duplicates_folder
+                .then(|| analyzer.folder_path().join("Duplicates")),
+        )
+        .with_compressed(args.dedup_compressed)
+        .with_algorithm(args.hash_algorithm);
+    if let Some(threads) = args.threads {
+        finder = finder.with_threads(threads);
+    }
+    if args.fast_dedup {
+        finder = finder.with_keyer(Box::new(SampledHashKeyer));
+    }
+    if let Some(cache_path) = &args.checksum_cache {
+        finder = finder.with_cache(ChecksumCache::open(cache_path)?);
+    }
+    let apply_only = args
+        .apply_only
+        .as_deref()
+        .map(|value| PlanFilter::parse(value, &categories))
+        .transpose()?;
+    let move_mode = if args.copy { MoveMode::Copy } else { MoveMode::Move };
+    let organizer = Organizer::builder(folder_path.clone())
+        .verbose(verbose)
+        .conflict_strategy(args.on_conflict)
+        .mode(move_mode)
+        .build()?
+        .with_resolve_root(!args.no_resolve_root)
+        .with_category_roots(category_roots.clone())
+        .with_group_documents_by_type(args.group_documents_by_type)
+        .with_max_per_folder(args.max_per_folder)
+        .with_link_duplicates(args.link_duplicates)
+        .with_symlinks_as_copies(args.organize_symlinks_as_copies)
+        .with_quiet_duplicates(args.quiet_duplicates)
+        .with_duplicate_action(if args.trash_duplicates {
+            DuplicateAction::Trash
+        } else {
+            DuplicateAction::MoveToFolder
+        })
+        .with_collision_suffix(args.collision_suffix.clone())
+        .with_archive(args.archive)
+        .with_preserve_permissions(args.preserve_permissions)
+        .with_ignored_extensions(&args.ignore_extension)
+        .with_rules(rules)
+        .with_throttle(throttle)
+        .with_categories(categories.clone())
+        .with_apply_only(apply_only)
+        .with_safe_mode(args.safe_mode)
+        .with_by_location(args.by_location)
+        .with_date_sequence_names(args.organize_preserving_mtime_order_names)
+        .with_checkpoint_every(args.checkpoint_every)
+        .with_undo_log(!dry_run || args.undo_preview)
+        .with_normalize_unicode(args.normalize_unicode)
+        .with_date_subfolders(args.date_folders)
+        .with_organize_by(if args.by_date {
+            OrganizeBy::CategoryThenDate
+        } else {
+            OrganizeBy::Category
+        })
+        .with_date_folders(
+            args.date_format
+                .clone()
+                .or_else(|| args.date_granularity.map(|g| g.format())),
+        );
 
-    // Organize files
-    let organizer = Organizer::new(args.folder_path.clone(), args.verbose);
+    // Delete files matching the hash denylist
+    let mut deletion_actions = Vec::new();
+    if let Some(hash_file) = &args.delete_hashes {
+        let denylist = DuplicateFinder::load_hash_list(hash_file)?;
+        let denylisted = finder.find_denylisted(&all_entries, &denylist)?;
+        if !denylisted.is_empty() {
+            let approved = dry_run
+                || args.yes
+                || confirm(&format!(
+                    "Delete {} file(s) matching the hash denylist?",
+                    denylisted.len()
+                ))?;
+            if approved {
+                deletion_actions = organizer.delete_files(&denylisted, dry_run)?;
+                let removed: HashSet<_> = denylisted.iter().map(|e| e.path.clone()).collect();
+                all_entries.retain(|e| !removed.contains(&e.path));
+            }
+        }
+    }
 
-    if !args.analyze {
+    // Find duplicates, optionally among hidden files that are not being organized
+    let mut dedup_entries = all_entries.clone();
+    if args.dedup_include_hidden {
+        dedup_entries.extend(analysis.hidden.iter().cloned());
+    }
+    if args.process_duplicates_folder {
+        dedup_entries.extend(analysis.organized.iter().cloned());
+    }
+    let (mut duplicates, capped) = finder.find_duplicates_capped(&dedup_entries)?;
+    if capped {
+        let notice = format!(
+            "[!] Stopped after {} duplicate groups; remaining duplicates are left in place",
+            duplicates.len()
+        );
+        // Kept off stdout so the JSON output stays valid
+        match args.output {
+            OutputFormat::Json => eprintln!("{}", notice),
+            _ => println!("{}", notice),
+        }
+    }
+    // Looked for before anything moves; reported after the summary
+    let prefix_matches = match args.report_prefix_matches {
+        Some(length) => Some((
+            length,
+            finder.find_prefix_matches(&dedup_entries, length, &duplicates)?,
+        )),
+        None => None,
+    };
+    if args.review_duplicates && !duplicates.is_empty() {
+        if !io::stdout().is_terminal() {
+            anyhow::bail!("--review-duplicates needs an interactive terminal");
+        }
+        match ReviewState::new(duplicates).run()? {
+            Some(decided) => duplicates = decided,
+            None => {
+                println!("[~] Review cancelled; nothing was changed.");
+                return Ok(());
+            }
+        }
+    }
+
+    if !dry_run && !args.archive {
         // Create category folders
         let categories: Vec<&str> = analysis
             .categories
@@ -55,17 +731,116 @@ fn main() -> anyhow::Result<()> {
         organizer.create_category_folders(&categories)?;
     }
 
+    // Measured up front, since the JSON summary is written after the files have moved
+    let file_sizes = if args.output == OutputFormat::Json {
+        all_entries
+            .iter()
+            .filter_map(|e| Some((e.path.clone(), e.meta().ok()?.size)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
     // Organize files
-    let summary = organizer.organize_files(&all_entries, &duplicates, args.analyze)?;
+    let mut summary = organizer.organize_files(&all_entries, &duplicates, dry_run)?;
+    if let Some(plan_path) = &args.export_plan {
+        let plan = Plan::new(
+            analyzer.folder_path().to_path_buf(),
+            move_mode,
+            summary.planned_actions,
+        )
+        .with_category_roots(category_roots.into_values());
+        plan.save(plan_path)?;
+        println!(
+            "[+] Saved a plan of {} action(s) to {}",
+            plan.actions.len(),
+            plan_path.display()
+        );
+        return Ok(());
+    }
+    summary.actions_taken.splice(0..0, deletion_actions);
+    if args.write_index && !dry_run {
+        summary.actions_taken.extend(organizer.write_category_indexes()?);
+    }
+    if let Some(before) = before_snapshot {
+        before.save(&folder_path.join(SNAPSHOT_BEFORE_FILE_NAME))?;
+        Snapshot::capture(&folder_path)?.save(&folder_path.join(SNAPSHOT_AFTER_FILE_NAME))?;
+        if verbose {
+            println!(
+                "[*] Wrote {} and {}",
+                SNAPSHOT_BEFORE_FILE_NAME, SNAPSHOT_AFTER_FILE_NAME
+            );
+        }
+    }
+
+    if args.since_last_run && !dry_run {
+        run_state.set_last_run(run_started);
+        run_state.save(&folder_path)?;
+        if verbose {
+            println!("[*] Recorded this run in {}", STATE_FILE_NAME);
+        }
+    }
 
     // Display summary
-    DisplayFormatter::display_summary(
-        &all_entries,
-        &summary.duplicates_found,
-        &summary.actions_taken,
-        args.analyze,
-        &args.folder_path,
-    );
+    let formatter = DisplayFormatter::new()
+        .with_table_style(args.table_style)
+        .with_summary_only(args.summary_only)
+        .with_chart(args.chart)
+        .with_actions_mode(args.actions)
+        .with_duplicates_tree(args.report_duplicates_tree)
+        .with_changes_only(args.changes_only)
+        .with_file_sizes(file_sizes)
+        .with_locale(args.locale.unwrap_or_else(SortLocale::from_env))
+        .with_duplicates_relative_to(args.dedup_show_paths_relative_to.clone())
+        .with_categories(categories);
+    if let Some(report_path) = &args.dedup_report_json {
+        let report = formatter.dedup_report_json(&summary.duplicates_found);
+        std::fs::write(report_path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write {}", report_path.display()))?;
+        if verbose {
+            println!("[*] Wrote the duplicate report to {}", report_path.display());
+        }
+    }
+    let mut warnings = analysis.warnings.clone();
+    warnings.extend(summary.warnings.iter().cloned());
+    let (shown_entries, shown_duplicates) = if args.changes_only {
+        DisplayFormatter::changed_view(&all_entries, &summary.duplicates_found, &summary.changed)
+    } else {
+        (all_entries.clone(), summary.duplicates_found.clone())
+    };
+    match args.output {
+        OutputFormat::Text => {
+            formatter.display_summary(
+                &shown_entries,
+                &shown_duplicates,
+                &summary.actions_taken,
+                &warnings,
+                dry_run,
+                &folder_path,
+            );
+            if args.tree {
+                formatter.display_tree(&summary.planned_actions, analyzer.folder_path());
+            }
+            if let Some((length, groups)) = &prefix_matches {
+                println!("\n{}", formatter.prefix_matches_report(groups, *length, &folder_path));
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            formatter.to_json(&shown_entries, &shown_duplicates, &summary.actions_taken, dry_run)
+        ),
+        OutputFormat::Diff => println!(
+            "{}",
+            // Planned paths are under the resolved root, not the path as typed
+            formatter.diff_report(&summary.planned_actions, analyzer.folder_path())
+        ),
+    }
+    if args.porcelain {
+        eprintln!(
+            "{}",
+            formatter.porcelain_summary(&summary, analysis.unsupported_files, &warnings)
+        );
+    }
 
     Ok(())
 }
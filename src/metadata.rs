@@ -0,0 +1,194 @@
+use crate::dates::CivilDateTime;
+use anyhow::{Result, bail};
+use std::cell::OnceCell;
+use exif::{In, Reader, Tag, Value};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Everything desktidy needs to know about a file from one `stat` call. Content-based
+// details are only read from disk the first time they are asked for.
+#[derive(Debug)]
+pub struct FileMeta {
+    path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    // Birth time; not every filesystem records it
+    pub created: Option<SystemTime>,
+    // Last status change (ctime) on Unix
+    pub changed: Option<SystemTime>,
+    mime: OnceCell<Option<&'static str>>,
+    capture: OnceCell<Option<CivilDateTime>>,
+}
+
+impl FileMeta {
+    pub fn load(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified: metadata.modified()?,
+            created: metadata.created().ok(),
+            changed: Self::changed_time(&metadata),
+            mime: OnceCell::new(),
+            capture: OnceCell::new(),
+        })
+    }
+
+    #[cfg(unix)]
+    fn changed_time(metadata: &fs::Metadata) -> Option<SystemTime> {
+        use std::os::unix::fs::MetadataExt;
+        let secs = u64::try_from(metadata.ctime()).ok()?;
+        let nanos = u32::try_from(metadata.ctime_nsec()).ok()?;
+        Some(SystemTime::UNIX_EPOCH + std::time::Duration::new(secs, nanos))
+    }
+
+    #[cfg(not(unix))]
+    fn changed_time(_metadata: &fs::Metadata) -> Option<SystemTime> {
+        None
+    }
+
+    pub fn modified_date(&self) -> CivilDateTime {
+        CivilDateTime::from_system_time(self.modified)
+    }
+
+    // Detected from the file signature on first use
+    pub fn mime_type(&self) -> Option<&'static str> {
+        *self.mime.get_or_init(|| {
+            infer::get_from_path(&self.path)
+                .ok()
+                .flatten()
+                .map(|kind| kind.mime_type())
+        })
+    }
+
+    // When a photo was taken, from the EXIF DateTimeOriginal tag of JPEG, HEIC and
+    // TIFF files. The camera's local time is taken as is.
+    pub fn capture_date(&self) -> Option<CivilDateTime> {
+        *self.capture.get_or_init(|| {
+            let ext = self.path.extension()?.to_str()?.to_lowercase();
+            if !matches!(ext.as_str(), "jpg" | "jpeg" | "heic" | "tif" | "tiff") {
+                return None;
+            }
+            let file = fs::File::open(&self.path).ok()?;
+            let exif = Reader::new()
+                .read_from_container(&mut BufReader::new(file))
+                .ok()?;
+            let Value::Ascii(parts) = &exif.get_field(Tag::DateTimeOriginal, In::PRIMARY)?.value else {
+                return None;
+            };
+            let taken = exif::DateTime::from_ascii(parts.first()?).ok()?;
+            Some(CivilDateTime {
+                year: i64::from(taken.year),
+                month: u32::from(taken.month),
+                day: u32::from(taken.day),
+                hour: u32::from(taken.hour),
+                minute: u32::from(taken.minute),
+                second: u32::from(taken.second),
+            })
+        })
+    }
+
+    // The capture date of a photo, or its modification time
+    pub fn photo_date(&self) -> CivilDateTime {
+        self.capture_date().unwrap_or_else(|| self.modified_date())
+    }
+}
+
+// A size such as "500", "100KB" or "1.5 GB", in the binary units human_size prints
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = match number.parse() {
+        Ok(number) if number >= 0.0 => number,
+        _ => bail!("size '{}' must start with a number", size),
+    };
+    let power = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" => 1,
+        "M" | "MB" => 2,
+        "G" | "GB" => 3,
+        "T" | "TB" => 4,
+        _ => bail!("size '{}' must be in B, KB, MB, GB or TB", size),
+    };
+    Ok((number * 1024f64.powi(power)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_size_and_timestamps() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("photo.png");
+        let png_header = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D];
+        fs::write(&path, png_header)?;
+        let modified = UNIX_EPOCH + Duration::from_secs(1_686_832_496);
+        fs::File::options().write(true).open(&path)?.set_modified(modified)?;
+
+        let meta = FileMeta::load(&path)?;
+        assert_eq!(meta.size, 12);
+        assert_eq!(meta.modified, modified);
+        assert_eq!(meta.modified_date().to_utc_string(), "2023-06-15 12:34:56 UTC");
+        if cfg!(unix) {
+            assert!(meta.changed.is_some_and(|changed| changed > modified));
+        }
+        assert_eq!(meta.mime_type(), Some("image/png"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_date_from_exif_with_mtime_fallback() -> Result<()> {
+        use exif::Field;
+
+        let temp_dir = TempDir::new()?;
+        let photo = temp_dir.path().join("beach.jpg");
+        fs::write(
+            &photo,
+            crate::location::tests::jpeg_with_fields(&[Field {
+                tag: Tag::DateTimeOriginal,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"2023:07:14 09:30:05".to_vec()]),
+            }]),
+        )?;
+        let meta = FileMeta::load(&photo)?;
+        assert_eq!(
+            meta.capture_date().map(|d| d.to_utc_string()),
+            Some("2023-07-14 09:30:05 UTC".to_string())
+        );
+
+        let plain = temp_dir.path().join("plain.jpg");
+        fs::write(&plain, b"no exif here")?;
+        let modified = UNIX_EPOCH + Duration::from_secs(1_686_832_496);
+        fs::File::options().write(true).open(&plain)?.set_modified(modified)?;
+        let meta = FileMeta::load(&plain)?;
+        assert_eq!(meta.capture_date(), None);
+        assert_eq!(meta.photo_date().to_utc_string(), "2023-06-15 12:34:56 UTC");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_size() -> Result<()> {
+        assert_eq!(parse_size("500")?, 500);
+        assert_eq!(parse_size("100KB")?, 100 * 1024);
+        assert_eq!(parse_size("1mb")?, 1024 * 1024);
+        assert_eq!(parse_size("1.5 GB")?, 3 * 512 * 1024 * 1024);
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10 parsecs").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_missing_file_fails() {
+        assert!(FileMeta::load(Path::new("/nonexistent/desktidy/file.pdf")).is_err());
+    }
+}
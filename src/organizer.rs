@@ -1,44 +1,923 @@
-use crate::types::{DuplicateGroup, FileEntry, OrganizationSummary};
-use anyhow::Result;
+use crate::categories::CategorySet;
+use crate::dates::{CivilDateTime, DateFormat, DateGranularity};
+use crate::duplicate_finder::{DuplicateFinder, DuplicateKeyer};
+use crate::file_analyzer::check_folder;
+use crate::filesystem::{FileSystem, OsFileSystem};
+use crate::location;
+use crate::metadata::FileMeta;
+use crate::plan::Plan;
+use crate::rules::RuleSet;
+use crate::throttle::Throttle;
+use crate::types::{
+    ConflictStrategy, DuplicateGroup, FileCategory, FileEntry, MoveMode, OrganizationSummary,
+    PlannedAction, Warning,
+};
+use crate::undo::{UNDO_LOG_FILE_NAME, UndoLog, UndoManifest};
+use anyhow::{Result, bail};
+use clap::ValueEnum;
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use unicode_normalization::UnicodeNormalization;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub const DEFAULT_COLLISION_SUFFIX: &str = " ({n})";
+pub const INDEX_FILE_NAME: &str = "_index.txt";
+pub const DUPLICATES_LOG_FILE_NAME: &str = "duplicates.log";
+
+// Moves made since the last checkpoint, oldest first, so a failure can undo them
+struct Journal {
+    every: usize,
+    since_checkpoint: Vec<(PathBuf, PathBuf, u64)>,
+}
+
+impl Journal {
+    fn new(every: usize) -> Self {
+        Self {
+            every,
+            since_checkpoint: Vec::new(),
+        }
+    }
+
+    // True when this move completes a checkpoint
+    fn record(&mut self, source: &Path, dest: &Path, size: u64) -> bool {
+        self.since_checkpoint.push((source.to_path_buf(), dest.to_path_buf(), size));
+        if self.every > 0 && self.since_checkpoint.len() >= self.every {
+            self.since_checkpoint.clear();
+            return true;
+        }
+        false
+    }
+}
 
 pub struct Organizer {
     folder_path: PathBuf,
+    provided_path: PathBuf,
     verbose: bool,
+    group_documents_by_type: bool,
+    max_per_folder: Option<usize>,
+    link_duplicates: bool,
+    quiet_duplicates: bool,
+    symlinks_as_copies: bool,
+    collision_suffix: String,
+    archive: bool,
+    preserve_permissions: bool,
+    permission_reports: Mutex<Vec<String>>,
+    ignored_extensions: HashSet<String>,
+    date_folders: Option<DateFormat>,
+    date_subfolders: bool,
+    organize_by: OrganizeBy,
+    rules: RuleSet,
+    throttle: Option<Arc<Throttle>>,
+    categories: CategorySet,
+    apply_only: Option<PlanFilter>,
+    normalize_unicode: Option<UnicodeForm>,
+    safe_mode: bool,
+    verifier: Option<Box<dyn DuplicateKeyer>>,
+    by_location: bool,
+    checkpoint_every: Option<usize>,
+    undo_log: bool,
+    date_sequence_names: bool,
+    conflict_strategy: ConflictStrategy,
+    move_mode: MoveMode,
+    file_system: Arc<dyn FileSystem>,
+    // Categories whose files go to a folder of their own instead of `<root>/<Category>`
+    category_roots: HashMap<FileCategory, PathBuf>,
+    duplicate_action: DuplicateAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnicodeForm {
+    Nfc,
+    Nfd,
+}
+
+impl UnicodeForm {
+    pub fn normalize(&self, name: &str) -> String {
+        match self {
+            UnicodeForm::Nfc => name.nfc().collect(),
+            UnicodeForm::Nfd => name.nfd().collect(),
+        }
+    }
+}
+
+// How destination folders are laid out: flat category folders, or each category split
+// into `<YYYY>/<MM>` folders by modification time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrganizeBy {
+    #[default]
+    Category,
+    CategoryThenDate,
+}
+
+// Where the extra copies in a duplicate group go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateAction {
+    #[default]
+    MoveToFolder,
+    // The system trash or recycle bin, from where they can be restored
+    Trash,
+}
+
+// Selects the part of a plan to carry out: a whole category, or files whose name matches a glob
+#[derive(Debug, Clone)]
+pub enum PlanFilter {
+    Category(FileCategory),
+    Glob(glob::Pattern),
+}
+
+impl PlanFilter {
+    // Category names take priority; anything else is read as a glob
+    pub fn parse(value: &str, categories: &CategorySet) -> Result<Self> {
+        if let Some(category) = categories
+            .order()
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(value))
+        {
+            return Ok(PlanFilter::Category(category));
+        }
+        Ok(PlanFilter::Glob(glob::Pattern::new(value)?))
+    }
+
+    pub fn matches(&self, entry: &FileEntry) -> bool {
+        match self {
+            PlanFilter::Category(category) => &entry.category == category,
+            PlanFilter::Glob(pattern) => entry
+                .path
+                .file_name()
+                .is_some_and(|name| pattern.matches(&name.to_string_lossy())),
+        }
+    }
 }
 
 impl Organizer {
+    // Validates the folder when built; see OrganizerBuilder
+    pub fn builder(folder_path: impl Into<PathBuf>) -> OrganizerBuilder {
+        OrganizerBuilder::new(folder_path.into())
+    }
+
     pub fn new(folder_path: PathBuf, verbose: bool) -> Self {
+        Self::new_with_strategy(folder_path, verbose, ConflictStrategy::default())
+    }
+
+    pub fn new_with_strategy(
+        folder_path: PathBuf,
+        verbose: bool,
+        conflict_strategy: ConflictStrategy,
+    ) -> Self {
         Self {
-            folder_path: folder_path.canonicalize().unwrap_or(folder_path),
+            folder_path: folder_path.canonicalize().unwrap_or_else(|_| folder_path.clone()),
+            provided_path: folder_path,
             verbose,
+            group_documents_by_type: false,
+            max_per_folder: None,
+            link_duplicates: false,
+            quiet_duplicates: false,
+            symlinks_as_copies: false,
+            collision_suffix: DEFAULT_COLLISION_SUFFIX.to_string(),
+            archive: false,
+            preserve_permissions: false,
+            permission_reports: Mutex::new(Vec::new()),
+            ignored_extensions: HashSet::new(),
+            date_folders: None,
+            date_subfolders: false,
+            organize_by: OrganizeBy::default(),
+            rules: RuleSet::default(),
+            throttle: None,
+            categories: CategorySet::default(),
+            apply_only: None,
+            normalize_unicode: None,
+            safe_mode: false,
+            verifier: None,
+            by_location: false,
+            checkpoint_every: None,
+            undo_log: false,
+            date_sequence_names: false,
+            conflict_strategy,
+            move_mode: MoveMode::default(),
+            file_system: Arc::new(OsFileSystem),
+            category_roots: HashMap::new(),
+            duplicate_action: DuplicateAction::default(),
         }
     }
 
-    fn clean_filename(&self, filepath: &Path) -> PathBuf {
-        if let Some(file_name) = filepath.file_name().and_then(|n| n.to_str()) {
-            if let Some(stem_start) = file_name.rfind('.') {
-                let stem = &file_name[..stem_start];
-                let ext = &file_name[stem_start..];
-
-                // Match patterns like "_1", "_2", etc. at the end of the filename
-                if let Ok(re) = Regex::new(r"_\d+$") {
-                    if re.is_match(stem) {
-                        let clean_stem = re.replace(stem, "").to_string();
-                        return filepath.parent().unwrap().join(format!("{}{}", clean_stem, ext));
+    // Runs moves against another file system, such as an in-memory one in tests
+    pub fn with_file_system(mut self, file_system: impl FileSystem + 'static) -> Self {
+        self.file_system = Arc::new(file_system);
+        self
+    }
+
+    pub fn with_resolve_root(mut self, resolve: bool) -> Self {
+        if !resolve {
+            self.folder_path = self.provided_path.clone();
+        }
+        self
+    }
+
+    // Each listed category is organized straight into its folder, which may be on
+    // another drive; subfolders such as dates are still made inside it
+    pub fn with_category_roots(mut self, roots: HashMap<FileCategory, PathBuf>) -> Self {
+        self.category_roots = roots;
+        self
+    }
+
+    // Where a category's files go, by category name
+    fn category_folder(&self, category: &str) -> PathBuf {
+        self.category_roots
+            .iter()
+            .find(|(c, _)| c.as_str() == category)
+            .map(|(_, root)| root.clone())
+            .unwrap_or_else(|| self.folder_path.join(category))
+    }
+
+    pub fn with_group_documents_by_type(mut self, enabled: bool) -> Self {
+        self.group_documents_by_type = enabled;
+        self
+    }
+
+    pub fn with_max_per_folder(mut self, max: Option<usize>) -> Self {
+        self.max_per_folder = max.filter(|&n| n > 0);
+        self
+    }
+
+    // A symlinked file is organized as a real copy of its target, which stays where it is;
+    // the link itself is moved as usual, so it is removed unless copying
+    pub fn with_symlinks_as_copies(mut self, enabled: bool) -> Self {
+        self.symlinks_as_copies = enabled;
+        self
+    }
+
+    // Keeps one real copy in its category and leaves a symlink to it in Duplicates
+    pub fn with_link_duplicates(mut self, enabled: bool) -> Self {
+        self.link_duplicates = enabled;
+        self
+    }
+
+    // Leaves extra copies where they are and lists them in `duplicates.log` instead
+    // of moving them to Duplicates
+    pub fn with_quiet_duplicates(mut self, enabled: bool) -> Self {
+        self.quiet_duplicates = enabled;
+        self
+    }
+
+    // One `<duplicate>\t<original>` line per extra copy, relative to the root, with
+    // the original at the place this run moved it to
+    fn log_duplicates(
+        &self,
+        duplicates: &[DuplicateGroup],
+        organized_paths: &HashMap<PathBuf, PathBuf>,
+        dry_run: bool,
+    ) -> Result<String> {
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.folder_path)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let mut lines = Vec::new();
+        for group in duplicates {
+            let original = &group.files[0].path;
+            let original = organized_paths.get(original).unwrap_or(original);
+            for entry in group.files[1..].iter().filter(|e| !self.is_ignored(&e.path)) {
+                lines.push(format!("{}\t{}\n", relative(&entry.path), relative(original)));
+            }
+        }
+        if !dry_run {
+            self.file_system
+                .write(&self.folder_path.join(DUPLICATES_LOG_FILE_NAME), lines.concat().as_bytes())?;
+        }
+        Ok(format!(
+            "{} {} duplicate{} in {}",
+            if dry_run { "Would log" } else { "Logged" },
+            lines.len(),
+            if lines.len() == 1 { "" } else { "s" },
+            DUPLICATES_LOG_FILE_NAME
+        ))
+    }
+
+    // `{n}` is the collision counter and `{timestamp}` the current Unix time
+    pub fn with_collision_suffix(mut self, format: impl Into<String>) -> Self {
+        self.collision_suffix = format.into();
+        self
+    }
+
+    fn render_collision_suffix(&self, counter: usize) -> String {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let suffix = self
+            .collision_suffix
+            .replace("{n}", &counter.to_string())
+            .replace("{timestamp}", &timestamp.to_string());
+
+        // Without a counter placeholder, repeated attempts need a counter to stay unique
+        if !self.collision_suffix.contains("{n}") && counter > 1 {
+            format!("{}_{}", suffix, counter)
+        } else {
+            suffix
+        }
+    }
+
+    // Bundles each category into `<Category>.zip` instead of a folder
+    pub fn with_archive(mut self, enabled: bool) -> Self {
+        self.archive = enabled;
+        self
+    }
+
+    // Files with these extensions are still analyzed but never moved
+    pub fn with_ignored_extensions(mut self, extensions: &[String]) -> Self {
+        self.ignored_extensions = extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+        self
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.ignored_extensions.contains(&ext.to_lowercase()))
+    }
+
+    // Nests files under `<category>/<date folder>` using each file's modification time
+    pub fn with_date_folders(mut self, format: Option<DateFormat>) -> Self {
+        self.date_folders = format;
+        self
+    }
+
+    // Nests images under `Images/<year>/<month>` by when they were taken, falling back
+    // to the modification time; other categories stay flat
+    pub fn with_date_subfolders(mut self, enabled: bool) -> Self {
+        self.date_subfolders = enabled;
+        self
+    }
+
+    pub fn with_duplicate_action(mut self, action: DuplicateAction) -> Self {
+        self.duplicate_action = action;
+        self
+    }
+
+    // Sends the extra copies of each group to the system trash. Groups from a custom
+    // keyer are compared in full first, and only identical files are trashed.
+    fn trash_duplicates(
+        &self,
+        duplicates: &[DuplicateGroup],
+        organized_paths: &HashMap<PathBuf, PathBuf>,
+        dry_run: bool,
+        warnings: &mut Vec<Warning>,
+        changed: &mut Vec<PathBuf>,
+    ) -> Vec<String> {
+        let mut actions = Vec::new();
+        for group in duplicates {
+            let original = &group.files[0];
+            let current = organized_paths.get(&original.path).unwrap_or(&original.path);
+            for entry in &group.files[1..] {
+                if self.is_ignored(&entry.path) || !self.is_selected(entry) {
+                    continue;
+                }
+                let verified = group.confirmed
+                    || self.same_contents(current, &entry.path).unwrap_or(false);
+                if !verified {
+                    warnings.push(Warning::Skipped(
+                        entry.path.clone(),
+                        "contents differ from the original".to_string(),
+                    ));
+                    continue;
+                }
+                let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+                if dry_run {
+                    actions.push(format!("Would send duplicate {} to trash", name));
+                    changed.push(entry.path.clone());
+                    continue;
+                }
+                match trash::delete(&entry.path) {
+                    Ok(()) => {
+                        actions.push(format!("Sent duplicate {} to trash", name));
+                        changed.push(entry.path.clone());
+                    }
+                    Err(e) => warnings.push(Warning::MoveFailed(entry.path.clone(), e.to_string())),
+                }
+            }
+        }
+        actions
+    }
+
+    pub fn with_organize_by(mut self, organize_by: OrganizeBy) -> Self {
+        self.organize_by = organize_by;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: MoveMode) -> Self {
+        self.move_mode = mode;
+        self
+    }
+
+    fn copying(&self) -> bool {
+        self.move_mode == MoveMode::Copy
+    }
+
+    // Renames files to `YYYY-MM-DD_NNN.ext` from their modification date, numbering the
+    // files of each date in the order they were modified
+    pub fn with_date_sequence_names(mut self, enabled: bool) -> Self {
+        self.date_sequence_names = enabled;
+        self
+    }
+
+    // Assigns the sequence names for the files about to be moved. Numbers count per date
+    // and destination folder and continue after any already in that folder, so a later
+    // batch of the same day does not collide with an earlier one.
+    fn date_sequence_names(&self, entries: &[&FileEntry]) -> HashMap<PathBuf, String> {
+        let mut by_day: HashMap<(PathBuf, String), Vec<(SystemTime, &FileEntry)>> = HashMap::new();
+        for entry in entries {
+            let Ok(meta) = entry.meta() else {
+                continue;
+            };
+            let date = meta.modified_date();
+            let day = format!("{:04}-{:02}-{:02}", date.year, date.month, date.day);
+            by_day
+                .entry((self.destination_folder(entry), day))
+                .or_default()
+                .push((meta.modified, entry));
+        }
+
+        let mut names = HashMap::new();
+        for ((folder, day), mut files) in by_day {
+            files.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.path.cmp(&b.1.path)));
+            let taken = self
+                .file_system
+                .read_dir(&folder)
+                .into_iter()
+                .flatten()
+                .filter_map(|path| {
+                    let name = path.file_name()?.to_string_lossy().to_string();
+                    let stem = name.split('.').next()?.to_string();
+                    stem.strip_prefix(&format!("{}_", day))?.parse::<usize>().ok()
+                })
+                .max()
+                .unwrap_or(0);
+            for (index, (_, entry)) in files.into_iter().enumerate() {
+                let ext = entry
+                    .path
+                    .extension()
+                    .map(|e| format!(".{}", e.to_string_lossy()))
+                    .unwrap_or_default();
+                names.insert(
+                    entry.path.clone(),
+                    format!("{}_{:03}{}", day, taken + index + 1, ext),
+                );
+            }
+        }
+        names
+    }
+
+    // A matching rule's subfolder replaces the document type subfolder
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // The full plan is still computed so names match it, but only matching files are touched
+    pub fn with_apply_only(mut self, filter: Option<PlanFilter>) -> Self {
+        self.apply_only = filter;
+        self
+    }
+
+    fn is_selected(&self, entry: &FileEntry) -> bool {
+        self.apply_only.as_ref().is_none_or(|filter| filter.matches(entry))
+    }
+
+    // Destination file names are rewritten in this normalization form
+    pub fn with_normalize_unicode(mut self, form: Option<UnicodeForm>) -> Self {
+        self.normalize_unicode = form;
+        self
+    }
+
+    // Every move copies, verifies the copy by checksum and only then deletes the source
+    pub fn with_safe_mode(mut self, enabled: bool) -> Self {
+        self.safe_mode = enabled;
+        self
+    }
+
+    // Replaces the checksum used to verify safe-mode copies
+    pub fn with_verifier(mut self, verifier: Box<dyn DuplicateKeyer>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    // Sort photos into Images/<Country>/<City> from their EXIF GPS position
+    pub fn with_by_location(mut self, enabled: bool) -> Self {
+        self.by_location = enabled;
+        self
+    }
+
+    // Runs the moves as a transaction that commits every N moves: a failed move puts
+    // back everything moved since the last checkpoint and stops. 0 commits only at the end.
+    pub fn with_checkpoint_every(mut self, every: Option<usize>) -> Self {
+        self.checkpoint_every = every;
+        self
+    }
+
+    // Records every move in the undo manifest at the root before it happens
+    pub fn with_undo_log(mut self, enabled: bool) -> Self {
+        self.undo_log = enabled;
+        self
+    }
+
+    pub fn undo_log_path(&self) -> PathBuf {
+        self.folder_path.join(UNDO_LOG_FILE_NAME)
+    }
+
+    // Puts back everything recorded in the undo manifest at the root
+    pub fn undo(&self) -> Result<OrganizationSummary> {
+        self.undo_from(&self.undo_log_path(), false)
+    }
+
+    // Reverses the moves in a manifest, newest first. A file the user has since deleted is
+    // reported and dropped from the manifest; one whose original location is now occupied
+    // is left where it is and kept. The manifest is removed once nothing is left in it.
+    pub fn undo_from(&self, manifest_path: &Path, dry_run: bool) -> Result<OrganizationSummary> {
+        let mut manifest = UndoManifest::load_from(self.file_system.as_ref(), manifest_path)?;
+        if manifest.dry_run {
+            bail!("{} was written by a dry run; there is nothing to undo", manifest_path.display());
+        }
+        let mut actions_taken = Vec::new();
+        let mut warnings = Vec::new();
+        let mut changed = Vec::new();
+        let (mut files_moved, mut bytes_moved) = (0, 0);
+        let mut remaining = Vec::new();
+
+        for entry in manifest.moves.drain(..).rev() {
+            let name = entry.source.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let folder = entry.source.parent().unwrap_or(&self.folder_path);
+            let label = match Self::folder_label(&self.folder_path, folder) {
+                label if label.is_empty() => "the top folder".to_string(),
+                label => format!("{} folder", label),
+            };
+            if self.file_system.metadata(&entry.destination).is_err() {
+                if self.verbose {
+                    println!("[!] {} no longer exists, skipping", entry.destination.display());
+                }
+                warnings.push(Warning::Skipped(
+                    entry.destination.clone(),
+                    "no longer exists".to_string(),
+                ));
+                continue;
+            }
+            // The original of a copy is normally still in place, so only the copy goes
+            if entry.copied && self.file_system.metadata(&entry.source).is_ok() {
+                let copy_folder = entry.destination.parent().unwrap_or(&self.folder_path);
+                let copy_label = Self::folder_label(&self.folder_path, copy_folder);
+                if dry_run {
+                    actions_taken.push(format!("Would remove the copy of {} from {} folder", name, copy_label));
+                    remaining.push(entry);
+                    continue;
+                }
+                match self.file_system.remove_file(&entry.destination) {
+                    Ok(()) => {
+                        actions_taken.push(format!("Removed the copy of {} from {} folder", name, copy_label));
+                        changed.push(entry.destination);
+                    }
+                    Err(e) => {
+                        warnings.push(Warning::MoveFailed(entry.destination.clone(), e.to_string()));
+                        remaining.push(entry);
+                    }
+                }
+                continue;
+            }
+            if self.file_system.metadata(&entry.source).is_ok() {
+                warnings.push(Warning::Skipped(
+                    entry.source.clone(),
+                    "a newer file exists at the original location".to_string(),
+                ));
+                remaining.push(entry);
+                continue;
+            }
+            if dry_run {
+                actions_taken.push(format!("Would restore {} to {}", name, label));
+                remaining.push(entry);
+                continue;
+            }
+            // A link's size is that of the original it points at
+            let size = self
+                .file_system
+                .canonicalize(&entry.destination)
+                .and_then(|path| self.file_system.metadata(&path))
+                .map(|m| m.len)
+                .unwrap_or(0);
+            let restored = match entry.linked {
+                true => self.restore_linked(&entry.destination, &entry.source),
+                false => self.safe_move(&entry.destination, &entry.source),
+            };
+            match restored {
+                Ok(_) => {
+                    files_moved += 1;
+                    bytes_moved += size;
+                    actions_taken.push(format!("Restored {} to {}", name, label));
+                    changed.push(entry.destination);
+                }
+                Err(e) => {
+                    warnings.push(Warning::MoveFailed(entry.destination.clone(), e.to_string()));
+                    remaining.push(entry);
+                }
+            }
+        }
+
+        if !dry_run {
+            if remaining.is_empty() {
+                self.file_system.remove_file(manifest_path)?;
+            } else {
+                remaining.reverse();
+                let mut log = UndoLog::open_in(self.file_system.clone(), manifest_path, false)?;
+                log.replace(remaining);
+                log.save()?;
+            }
+        }
+
+        Ok(OrganizationSummary {
+            actions_taken,
+            duplicates_found: Vec::new(),
+            warnings,
+            files_moved,
+            files_copied: 0,
+            bytes_moved,
+            changed,
+            skipped_files: Vec::new(),
+            planned_actions: Vec::new(),
+        })
+    }
+
+    // Carries out a saved plan step by step, exactly as the dry run that made it
+    // described. Nothing is recomputed: a move whose source has gone or whose
+    // destination has since been taken is reported and skipped.
+    pub fn apply_plan(&self, plan: &Plan) -> Result<OrganizationSummary> {
+        let mut actions_taken = Vec::new();
+        let mut warnings = Vec::new();
+        let mut changed = Vec::new();
+        let mut skipped_files = Vec::new();
+        let (mut files_moved, mut bytes_moved) = (0, 0);
+        let mut undo_log = match self.undo_log {
+            true => Some(UndoLog::open_in(self.file_system.clone(), &self.undo_log_path(), false)?),
+            false => None,
+        };
+
+        for action in &plan.actions {
+            let (from, to) = match action {
+                PlannedAction::CreateDirectory { path } => {
+                    self.create_folder(path)?;
+                    continue;
+                }
+                PlannedAction::MoveFile { from, to } | PlannedAction::MoveDuplicate { from, to, .. } => {
+                    (from, to)
+                }
+            };
+            let skip_reason = match (self.file_system.metadata(from), self.file_system.exists(to)) {
+                (Err(_), _) => Some("no longer exists".to_string()),
+                (_, true) => Some(format!("{} already exists", to.display())),
+                _ => None,
+            };
+            if let Some(reason) = skip_reason {
+                if self.verbose {
+                    println!("[~] Skipping {}: {}", from.display(), reason);
+                }
+                warnings.push(Warning::Skipped(from.clone(), reason));
+                skipped_files.push(from.clone());
+                continue;
+            }
+
+            let size = self.file_system.metadata(from).map(|m| m.len).unwrap_or(0);
+            if let Some(log) = &mut undo_log {
+                log.record(from, to, self.copying());
+            }
+            match self.safe_move(from, to) {
+                Ok(true) => {
+                    files_moved += 1;
+                    bytes_moved += size;
+                    changed.push(from.clone());
+                    actions_taken.push(format!(
+                        "{} {} to {} folder",
+                        if self.copying() { "Copied" } else { "Moved" },
+                        from.file_name().unwrap_or_default().to_string_lossy(),
+                        Self::folder_label(&self.folder_path, to.parent().unwrap_or(&self.folder_path))
+                    ));
+                }
+                Ok(false) => {
+                    if let Some(log) = &mut undo_log {
+                        log.forget(from);
+                    }
+                }
+                Err(e) => {
+                    warnings.push(Warning::MoveFailed(from.clone(), e.to_string()));
+                    if let Some(log) = &mut undo_log {
+                        log.forget(from);
                     }
                 }
             }
         }
+        if let Some(log) = &mut undo_log {
+            log.save()?;
+        }
+
+        let (files_moved, files_copied) = match self.copying() {
+            true => (0, files_moved),
+            false => (files_moved, 0),
+        };
+        Ok(OrganizationSummary {
+            actions_taken,
+            duplicates_found: Vec::new(),
+            warnings,
+            files_moved,
+            files_copied,
+            bytes_moved,
+            changed,
+            skipped_files,
+            planned_actions: Vec::new(),
+        })
+    }
+
+    fn normalize_filename(&self, path: &Path) -> PathBuf {
+        let (Some(form), Some(name)) = (self.normalize_unicode, path.file_name()) else {
+            return path.to_path_buf();
+        };
+        match name.to_str() {
+            Some(name) => path.with_file_name(form.normalize(name)),
+            None => path.to_path_buf(),
+        }
+    }
+
+    // The categories whose folders get an index
+    pub fn with_categories(mut self, categories: CategorySet) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    // Limits the copy fallback used when a rename crosses devices
+    pub fn with_throttle(mut self, throttle: Option<Arc<Throttle>>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    // A destination folder that resolves back to the source's own folder (e.g. a category
+    // folder symlinked to the scan root), or into the source itself, must never be used
+    fn is_self_targeting(&self, source: &Path, dest_folder: &Path) -> bool {
+        let Ok(source) = self.file_system.canonicalize(source) else {
+            return false;
+        };
+        let dest_folder = self
+            .file_system
+            .canonicalize(dest_folder)
+            .unwrap_or_else(|_| dest_folder.to_path_buf());
+        source.parent() == Some(dest_folder.as_path()) || dest_folder.starts_with(&source)
+    }
+
+    fn file_date(path: &Path) -> Option<CivilDateTime> {
+        Some(FileMeta::load(path).ok()?.modified_date())
+    }
+
+    pub fn with_preserve_permissions(mut self, enabled: bool) -> Self {
+        self.preserve_permissions = enabled;
+        self
+    }
+
+    #[cfg(unix)]
+    fn describe_permissions(permissions: &fs::Permissions) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        format!("{:o}", permissions.mode() & 0o7777)
+    }
+
+    #[cfg(not(unix))]
+    fn describe_permissions(permissions: &fs::Permissions) -> String {
+        if permissions.readonly() { "read-only" } else { "writable" }.to_string()
+    }
+
+    // Re-applies the source permissions if the move changed them and records the change
+    fn restore_permissions(&self, dest: &Path, original: fs::Permissions) -> Result<()> {
+        let current = self.file_system.permissions(dest)?;
+        if current == original {
+            return Ok(());
+        }
+        self.file_system.set_permissions(dest, original.clone())?;
+        let report = format!(
+            "Restored permissions on {} ({} would have become {})",
+            dest.file_name().unwrap_or_default().to_string_lossy(),
+            Self::describe_permissions(&original),
+            Self::describe_permissions(&current)
+        );
+        if self.verbose {
+            println!("[~] {}", report);
+        }
+        if let Ok(mut reports) = self.permission_reports.lock() {
+            reports.push(report);
+        }
+        Ok(())
+    }
+
+    fn document_subfolder(path: &Path) -> Option<&'static str> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "doc" | "docx" | "rtf" => Some("Word"),
+            "odt" => Some("OpenDocument"),
+            "txt" => Some("Plain"),
+            _ => None,
+        }
+    }
+
+    // Picks `folder`, or the first of `folder_2`, `folder_3`, ... that still has room
+    fn rollover_folder(&self, folder: PathBuf, counts: &mut HashMap<PathBuf, usize>) -> PathBuf {
+        let Some(max) = self.max_per_folder else {
+            return folder;
+        };
+        let name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let mut index = 1;
+        loop {
+            let candidate = if index == 1 {
+                folder.clone()
+            } else {
+                folder.with_file_name(format!("{}_{}", name, index))
+            };
+            let count = counts.entry(candidate.clone()).or_insert_with(|| {
+                self.file_system
+                    .read_dir(&candidate)
+                    .map(|entries| entries.len())
+                    .unwrap_or(0)
+            });
+            if *count < max {
+                *count += 1;
+                return candidate;
+            }
+            index += 1;
+        }
+    }
+
+    fn folder_label(root: &Path, folder: &Path) -> String {
+        folder.strip_prefix(root).unwrap_or(folder).display().to_string()
+    }
+
+    fn destination_folder(&self, entry: &FileEntry) -> PathBuf {
+        let mut folder = self.category_folder(entry.category.as_str());
+        if let Some(subfolder) = self
+            .rules
+            .first_match(&entry.path)
+            .and_then(|rule| rule.subfolder.as_ref())
+        {
+            folder = folder.join(subfolder);
+        } else if self.group_documents_by_type
+            && entry.category == FileCategory::Documents
+            && let Some(subfolder) = Self::document_subfolder(&entry.path)
+        {
+            folder = folder.join(subfolder);
+        } else if self.by_location && entry.category == FileCategory::Images {
+            folder = folder.join(location::location_folder(&entry.path));
+        }
+        let photo_dates = self.date_subfolders && entry.category == FileCategory::Images;
+        let by_date = photo_dates || self.organize_by == OrganizeBy::CategoryThenDate;
+        let format = match &self.date_folders {
+            Some(format) => Some(format.clone()),
+            None => by_date.then(|| DateGranularity::Month.format()),
+        };
+        let date = match photo_dates {
+            true => FileMeta::load(&entry.path).ok().map(|meta| meta.photo_date()),
+            false => Self::file_date(&entry.path),
+        };
+        if let Some(format) = format
+            && let Some(date) = date
+        {
+            folder = folder.join(format.render(&date));
+        }
+        folder
+    }
+
+    fn clean_filename(&self, filepath: &Path) -> PathBuf {
+        if let Some(file_name) = filepath.file_name().and_then(|n| n.to_str())
+            && let Some(stem_start) = file_name.rfind('.')
+        {
+            let stem = &file_name[..stem_start];
+            let ext = &file_name[stem_start..];
+
+            // Match patterns like "_1", "_2", etc. at the end of the filename
+            if let Ok(re) = Regex::new(r"_\d+$")
+                && re.is_match(stem)
+            {
+                let clean_stem = re.replace(stem, "").to_string();
+                return filepath.parent().unwrap().join(format!("{}{}", clean_stem, ext));
+            }
+        }
         filepath.to_path_buf()
     }
 
-    fn get_unique_path(&self, target_path: &Path) -> PathBuf {
-        let target_path = self.clean_filename(target_path);
+    // Paths already planned in this batch count as taken, so every file gets the same
+    // destination whether or not the whole plan is applied
+    fn get_unique_path(&self, target_path: &Path, planned: &HashSet<PathBuf>) -> PathBuf {
+        let target_path = self.clean_filename(&self.normalize_filename(target_path));
+        let is_free = |path: &Path| !self.file_system.exists(path) && !planned.contains(path);
 
-        if !target_path.exists() {
+        if is_free(&target_path) {
             return target_path;
         }
 
@@ -46,36 +925,450 @@ impl Organizer {
         loop {
             let stem = target_path.file_stem().unwrap_or_default().to_string_lossy();
             let ext = target_path.extension().unwrap_or_default().to_string_lossy();
-            let new_name = format!("{} ({}){}", stem, counter, if ext.is_empty() { String::new() } else { format!(".{}", ext) });
+            let new_name = format!(
+                "{}{}{}",
+                stem,
+                self.render_collision_suffix(counter),
+                if ext.is_empty() { String::new() } else { format!(".{}", ext) }
+            );
             let new_path = target_path.parent().unwrap().join(new_name);
 
-            if !new_path.exists() {
+            if is_free(&new_path) {
                 return new_path;
             }
-            counter += 1;
+            counter += 1;
+        }
+    }
+
+    // Where a file bound for `target` goes under the conflict strategy, or `None` to
+    // leave it in place. Overwriting only replaces files that were there before the run;
+    // two files of this run with the same name are still told apart by renaming.
+    fn resolve_conflict(&self, target: &Path, planned: &HashSet<PathBuf>) -> Option<PathBuf> {
+        let cleaned = self.clean_filename(&self.normalize_filename(target));
+        match self.conflict_strategy {
+            ConflictStrategy::Rename => Some(self.get_unique_path(target, planned)),
+            ConflictStrategy::Skip => {
+                (!self.file_system.exists(&cleaned) && !planned.contains(&cleaned)).then_some(cleaned)
+            }
+            ConflictStrategy::Overwrite if planned.contains(&cleaned) => {
+                Some(self.get_unique_path(target, planned))
+            }
+            ConflictStrategy::Overwrite => Some(cleaned),
+        }
+    }
+
+    fn skip_conflict(&self, entry: &FileEntry, folder: &Path, warnings: &mut Vec<Warning>) {
+        let label = Self::folder_label(&self.folder_path, folder);
+        if self.verbose {
+            println!("[~] {} already exists in {}, skipping", entry.path.display(), label);
+        }
+        warnings.push(Warning::Skipped(
+            entry.path.clone(),
+            format!("a file with the same name is already in {} folder", label),
+        ));
+    }
+
+    fn safe_move(&self, source: &Path, dest: &Path) -> Result<bool> {
+        #[cfg(unix)]
+        if self.file_system.metadata(source)?.is_symlink {
+            if self.symlinks_as_copies {
+                return self.materialize_symlink(source, dest);
+            }
+            return self.move_symlink(source, dest);
+        }
+
+        let source = self.file_system.canonicalize(source)?;
+        let dest = self
+            .file_system
+            .canonicalize(dest)
+            .unwrap_or_else(|_| dest.to_path_buf());
+
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = dest.parent() {
+            self.create_folder(parent)?;
+        }
+
+        let original_permissions = if self.preserve_permissions {
+            Some(self.file_system.permissions(&source)?)
+        } else {
+            None
+        };
+
+        if self.safe_mode {
+            self.copy_verify_delete(&source, &dest)?;
+        } else if self.copying() {
+            self.copy_into_place(&source, &dest)?;
+        } else if let Err(e) = self.file_system.rename(&source, &dest) {
+            if e.kind() != std::io::ErrorKind::CrossesDevices {
+                return Err(e.into());
+            }
+            if self.verbose {
+                println!("[~] {} is on another device, copying instead", source.display());
+            }
+            self.move_across_devices(&source, &dest)?;
+        }
+
+        if let Some(permissions) = original_permissions {
+            self.restore_permissions(&dest, permissions)?;
+        }
+        Ok(true)
+    }
+
+    // Moves the link itself rather than its target. A relative target is rewritten
+    // so the link still points at the same file from its new folder.
+    #[cfg(unix)]
+    fn move_symlink(&self, source: &Path, dest: &Path) -> Result<bool> {
+        let link_target = self.file_system.read_link(source)?;
+        let source_dir = self
+            .file_system
+            .canonicalize(source.parent().unwrap_or_else(|| Path::new(".")))?;
+        let dest_dir = dest.parent().unwrap_or_else(|| Path::new("."));
+        self.file_system.create_dir_all(dest_dir)?;
+        let dest_dir = self.file_system.canonicalize(dest_dir)?;
+
+        let new_target = if link_target.is_relative() {
+            let absolute = Self::normalize_lexically(&source_dir.join(&link_target));
+            Self::relative_path(&dest_dir, &absolute)
+        } else {
+            link_target
+        };
+        self.file_system.symlink(&new_target, dest)?;
+        if !self.copying() {
+            self.file_system.remove_file(source)?;
+        }
+        Ok(true)
+    }
+
+    // Copying reads through the link, so `dest` gets the target's contents
+    #[cfg(unix)]
+    fn materialize_symlink(&self, link: &Path, dest: &Path) -> Result<bool> {
+        if let Some(parent) = dest.parent() {
+            self.create_folder(parent)?;
+        }
+        self.copy_into_place(link, dest)?;
+        if !self.copying() {
+            self.file_system.remove_file(link)?;
+        }
+        Ok(true)
+    }
+
+    #[cfg(unix)]
+    fn normalize_lexically(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                std::path::Component::ParentDir => {
+                    normalized.pop();
+                }
+                std::path::Component::CurDir => {}
+                other => normalized.push(other),
+            }
+        }
+        normalized
+    }
+
+    // Both paths must be absolute and normalized
+    #[cfg(unix)]
+    fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+        let from: Vec<_> = from_dir.components().collect();
+        let to_components: Vec<_> = to.components().collect();
+        let common = from
+            .iter()
+            .zip(&to_components)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut relative = PathBuf::new();
+        for _ in common..from.len() {
+            relative.push("..");
+        }
+        for component in &to_components[common..] {
+            relative.push(component);
+        }
+        relative
+    }
+
+    fn temp_path_for(dest: &Path) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!(".desktidy-tmp-{:x}-{:04x}", std::process::id(), n);
+        dest.parent().unwrap_or_else(|| Path::new(".")).join(name)
+    }
+
+    fn copy_file(&self, source: &Path, dest: &Path) -> std::io::Result<()> {
+        match self.throttle.as_deref() {
+            None => self.file_system.copy(source, dest).map(|_| ()),
+            Some(throttle) => {
+                let mut reader = self.file_system.open(source)?;
+                let mut writer = self.file_system.create(dest)?;
+                Throttle::copy(Some(throttle), &mut reader, &mut writer)?;
+                drop(writer);
+                self.file_system
+                    .set_permissions(dest, self.file_system.permissions(source)?)
+            }
+        }
+    }
+
+    // Copies into a temp file next to `dest` and renames it into place, so the final
+    // path never holds a partially written file
+    fn copy_into_place(&self, source: &Path, dest: &Path) -> Result<()> {
+        let temp = Self::temp_path_for(dest);
+        let result = self
+            .copy_file(source, &temp)
+            .and_then(|_| self.file_system.rename(&temp, dest));
+        if let Err(e) = result {
+            let _ = self.file_system.remove_file(&temp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    // A rename cannot cross file systems, so the file is copied over and the source only
+    // removed once the copy has its full size
+    fn move_across_devices(&self, source: &Path, dest: &Path) -> Result<()> {
+        self.copy_into_place(source, dest)?;
+        let expected = self.file_system.metadata(source)?.len;
+        let copied = self.file_system.metadata(dest)?.len;
+        if copied != expected {
+            let _ = self.file_system.remove_file(dest);
+            bail!(
+                "copy of {} has {} bytes, expected {}",
+                source.display(),
+                copied,
+                expected
+            );
+        }
+        self.file_system.remove_file(source)?;
+        Ok(())
+    }
+
+    fn verification_key(&self, path: &Path) -> Result<String> {
+        match &self.verifier {
+            Some(verifier) => verifier.key(path),
+            None => {
+                let mut reader = self.file_system.open(path)?;
+                let (md5, sha256) = DuplicateFinder::checksums_of(&mut reader, self.throttle.as_deref())?;
+                Ok(format!("{}_{}", md5, sha256))
+            }
+        }
+    }
+
+    // Sizes first, then the checksums, both read through the file system
+    fn same_contents(&self, a: &Path, b: &Path) -> Result<bool> {
+        let size = |path: &Path| -> Result<u64> {
+            let resolved = self.file_system.canonicalize(path)?;
+            Ok(self.file_system.metadata(&resolved)?.len)
+        };
+        if size(a)? != size(b)? {
+            return Ok(false);
+        }
+        let checksums = |path: &Path| {
+            DuplicateFinder::checksums_of(&mut self.file_system.open(path)?, self.throttle.as_deref())
+        };
+        Ok(checksums(a)? == checksums(b)?)
+    }
+
+    // The safe-mode move: the copy is checked against the source before it is renamed
+    // into place, and a mismatch discards the copy and keeps the source untouched
+    fn copy_verify_delete(&self, source: &Path, dest: &Path) -> Result<()> {
+        let temp = Self::temp_path_for(dest);
+        let result = self
+            .copy_file(source, &temp)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| {
+                if self.verification_key(source)? != self.verification_key(&temp)? {
+                    bail!("verification failed: copy does not match the source");
+                }
+                self.file_system.rename(&temp, dest)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            let _ = self.file_system.remove_file(&temp);
+            return Err(e);
+        }
+        if self.verbose {
+            println!("[~] Verified copy of {}", source.display());
+        }
+        if !self.copying() {
+            self.file_system.remove_file(source)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn link_duplicate(&self, duplicate: &Path, link: &Path, target: &Path) -> Result<bool> {
+        if let Some(parent) = link.parent() {
+            self.file_system.create_dir_all(parent)?;
+        }
+        self.file_system.symlink(target, link)?;
+        if !self.copying() {
+            self.file_system.remove_file(duplicate)?;
+        }
+        Ok(true)
+    }
+
+    #[cfg(not(unix))]
+    fn link_duplicate(&self, duplicate: &Path, link: &Path, _target: &Path) -> Result<bool> {
+        self.safe_move(duplicate, link)
+    }
+
+    // Undoes a linked duplicate: the original the link points at is copied back to the
+    // duplicate's place and the link removed
+    fn restore_linked(&self, link: &Path, source: &Path) -> Result<bool> {
+        if !self.file_system.exists(link) {
+            bail!("the original {} links to no longer exists", link.display());
+        }
+        if let Some(parent) = source.parent() {
+            self.create_folder(parent)?;
+        }
+        self.copy_into_place(link, source)?;
+        self.file_system.remove_file(link)?;
+        Ok(true)
+    }
+
+    fn archive_entries(&self, entries: &[&FileEntry], dry_run: bool) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+        let mut by_archive: HashMap<PathBuf, Vec<&FileEntry>> = HashMap::new();
+        for entry in entries {
+            let archive_path = self
+                .folder_path
+                .join(format!("{}.zip", entry.category.as_str()));
+            by_archive.entry(archive_path).or_default().push(entry);
+        }
+
+        let mut archive_paths: Vec<_> = by_archive.keys().cloned().collect();
+        archive_paths.sort();
+        for archive_path in archive_paths {
+            let archive_name = archive_path.file_name().unwrap_or_default().to_string_lossy();
+            let members = &by_archive[&archive_path];
+
+            if dry_run {
+                for entry in members {
+                    actions.push(format!(
+                        "Would archive {} into {}",
+                        entry.path.file_name().unwrap_or_default().to_string_lossy(),
+                        archive_name
+                    ));
+                }
+                continue;
+            }
+
+            let mut existing_names = HashSet::new();
+            let mut writer = if self.file_system.exists(&archive_path) {
+                let mut file = self.file_system.open_rw(&archive_path)?;
+                existing_names.extend(ZipArchive::new(&mut file)?.file_names().map(String::from));
+                ZipWriter::new_append(file)?
+            } else {
+                ZipWriter::new(self.file_system.open_rw(&archive_path)?)
+            };
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+            for entry in members {
+                let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+                let mut member_name = file_name.to_string();
+                let mut counter = 1;
+                while existing_names.contains(&member_name) {
+                    let path = Path::new(file_name.as_ref());
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                    let suffix = self.render_collision_suffix(counter);
+                    member_name = match path.extension() {
+                        Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+                        None => format!("{}{}", stem, suffix),
+                    };
+                    counter += 1;
+                }
+
+                writer.start_file(member_name.as_str(), options)?;
+                std::io::copy(&mut self.file_system.open(&entry.path)?, &mut writer)?;
+                existing_names.insert(member_name);
+                actions.push(format!("Archived {} into {}", file_name, archive_name));
+            }
+            writer.finish()?;
+
+            // Only remove originals once the archive has been written successfully
+            if !self.copying() {
+                for entry in members {
+                    self.file_system.remove_file(&entry.path)?;
+                }
+            }
+        }
+
+        Ok(actions)
+    }
+
+    fn collect_index_lines(&self, root: &Path, dir: &Path, lines: &mut Vec<String>) -> Result<()> {
+        let mut children = self.file_system.read_dir(dir)?;
+        children.sort();
+        for path in children {
+            if path.file_name().is_some_and(|name| name == INDEX_FILE_NAME) {
+                continue;
+            }
+            if self.file_system.is_dir(&path) {
+                self.collect_index_lines(root, &path, lines)?;
+            } else {
+                let size = self.file_system.metadata(&path).map(|m| m.len).unwrap_or(0);
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                lines.push(format!("{}\t{} bytes", relative.display(), size));
+            }
         }
+        Ok(())
     }
 
-    fn safe_move(&self, source: &Path, dest: &Path) -> Result<bool> {
-        let source = source.canonicalize()?;
-        let dest = dest.canonicalize().unwrap_or_else(|_| dest.to_path_buf());
+    // Writes `_index.txt` into every category folder listing what it contains
+    pub fn write_category_indexes(&self) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+        let timestamp = CivilDateTime::now().to_utc_string();
 
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = dest.parent() {
-            fs::create_dir_all(parent)?;
+        for category in self.categories.order() {
+            let category_folder = self.category_folder(category.as_str());
+            if !self.file_system.is_dir(&category_folder) {
+                continue;
+            }
+
+            let mut files = Vec::new();
+            self.collect_index_lines(&category_folder, &category_folder, &mut files)?;
+            let mut contents = format!(
+                "{} - organized by desktidy on {}\n{} files\n\n",
+                category.as_str(),
+                timestamp,
+                files.len()
+            );
+            for line in files {
+                contents.push_str(&line);
+                contents.push('\n');
+            }
+
+            self.file_system
+                .write(&category_folder.join(INDEX_FILE_NAME), contents.as_bytes())?;
+            actions.push(format!("Wrote index for {} folder", category.as_str()));
         }
 
-        fs::rename(&source, &dest)?;
-        Ok(true)
+        Ok(actions)
+    }
+
+    pub fn delete_files(&self, entries: &[FileEntry], dry_run: bool) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+        for entry in entries {
+            let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+            if dry_run {
+                actions.push(format!("Would delete {} (matched hash denylist)", name));
+            } else {
+                self.file_system.remove_file(&entry.path)?;
+                actions.push(format!("Deleted {} (matched hash denylist)", name));
+            }
+        }
+        Ok(actions)
     }
 
     pub fn create_category_folders(&self, categories: &[&str]) -> Result<Vec<String>> {
         let mut actions = Vec::new();
 
         for category in categories {
-            let category_folder = self.folder_path.join(category);
-            if !category_folder.exists() {
-                fs::create_dir_all(&category_folder)?;
+            let category_folder = self.category_folder(category);
+            let existed = self.file_system.exists(&category_folder);
+            self.create_folder(&category_folder)?;
+            if !existed {
                 actions.push(format!("Created category folder: {}", category));
             }
         }
@@ -83,6 +1376,53 @@ impl Organizer {
         Ok(actions)
     }
 
+    // Like `create_dir_all`, but a regular file standing where one of the folders
+    // should be is reported by name instead of as a bare "File exists"
+    fn create_folder(&self, folder: &Path) -> Result<()> {
+        for ancestor in folder.ancestors().take_while(|a| *a != self.folder_path) {
+            if let Ok(meta) = self.file_system.metadata(ancestor)
+                && !meta.is_dir
+                && !(meta.is_symlink && self.file_system.is_dir(ancestor))
+            {
+                bail!(
+                    "cannot create {} folder: a file with that name exists",
+                    Self::folder_label(&self.folder_path, ancestor)
+                );
+            }
+        }
+        self.file_system.create_dir_all(folder)?;
+        Ok(())
+    }
+
+    // Moves every file recorded since the last checkpoint back, newest first, and
+    // returns the sources that were restored along with their sizes
+    fn roll_back(
+        &self,
+        journal: &mut Journal,
+        actions: &mut Vec<String>,
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<(PathBuf, u64)> {
+        let mut restored = Vec::new();
+        for (source, dest, size) in journal.since_checkpoint.drain(..).rev() {
+            // A copy is rolled back by removing it; the original never left
+            let reverted = match self.copying() {
+                true => self.file_system.remove_file(&dest).map_err(anyhow::Error::from),
+                false => self.safe_move(&dest, &source).map(|_| ()),
+            };
+            match reverted {
+                Ok(_) => {
+                    actions.push(format!(
+                        "Rolled back {} to its original location",
+                        source.file_name().unwrap_or_default().to_string_lossy()
+                    ));
+                    restored.push((source, size));
+                }
+                Err(e) => warnings.push(Warning::MoveFailed(dest, e.to_string())),
+            }
+        }
+        restored
+    }
+
     pub fn organize_files(
         &self,
         entries: &[FileEntry],
@@ -90,6 +1430,22 @@ impl Organizer {
         dry_run: bool,
     ) -> Result<OrganizationSummary> {
         let mut actions_taken = Vec::new();
+        let mut warnings = Vec::new();
+        let mut files_moved = 0;
+        let mut bytes_moved = 0;
+        let mut folder_counts = HashMap::new();
+        let mut organized_paths: HashMap<PathBuf, PathBuf> = HashMap::new();
+        let mut planned = HashSet::new();
+        let mut journal = self.checkpoint_every.map(Journal::new);
+        // Sources moved (or, in a dry run, that would be moved) by this run
+        let mut changed = Vec::new();
+        let mut skipped_files = Vec::new();
+        let mut planned_actions = Vec::new();
+        let mut undo_log = match self.undo_log {
+            true => Some(UndoLog::open_in(self.file_system.clone(), &self.undo_log_path(), dry_run)?),
+            false => None,
+        };
+        let mut aborted = false;
 
         // Create a set of files to skip (duplicates that will be moved)
         let mut files_to_skip = std::collections::HashSet::new();
@@ -98,60 +1454,317 @@ impl Organizer {
                 files_to_skip.insert(entry.path.clone());
             }
         }
+        files_to_skip.extend(
+            entries
+                .iter()
+                .filter(|e| self.is_ignored(&e.path))
+                .map(|e| e.path.clone()),
+        );
+
+        for entry in entries.iter().filter(|e| self.is_ignored(&e.path)) {
+            warnings.push(Warning::Skipped(entry.path.clone(), "ignored extension".to_string()));
+        }
+
+        if self.archive {
+            let to_archive: Vec<&FileEntry> = entries
+                .iter()
+                .filter(|e| !files_to_skip.contains(&e.path) && self.is_selected(e))
+                .collect();
+            actions_taken.extend(self.archive_entries(&to_archive, dry_run)?);
+            changed.extend(to_archive.iter().map(|e| e.path.clone()));
+        }
+
+        let sequence_names = match self.date_sequence_names && !self.archive {
+            true => self.date_sequence_names(
+                &entries
+                    .iter()
+                    .filter(|e| !files_to_skip.contains(&e.path))
+                    .collect::<Vec<_>>(),
+            ),
+            false => HashMap::new(),
+        };
 
         // Move files to category folders
         for entry in entries {
-            if files_to_skip.contains(&entry.path) {
+            if aborted {
+                break;
+            }
+            if self.archive || files_to_skip.contains(&entry.path) {
                 continue;
             }
 
-            let category_folder = self.folder_path.join(entry.category.as_str());
+            let category_folder = self.destination_folder(entry);
+            // Files already in their category folder stay as they are, suffix and all
             if entry.path.parent() != Some(&category_folder) {
-                let new_path = self.get_unique_path(&category_folder.join(entry.path.file_name().unwrap()));
+                let category_folder = self.rollover_folder(category_folder, &mut folder_counts);
+                if self.is_self_targeting(&entry.path, &category_folder) {
+                    if self.verbose {
+                        println!("[!] Destination of {} resolves to itself", entry.path.display());
+                    }
+                    warnings.push(Warning::Skipped(
+                        entry.path.clone(),
+                        "destination resolves to its own location".to_string(),
+                    ));
+                    continue;
+                }
+                // Sequence names are kept as they are: collision handling would strip
+                // their `_NNN` suffix
+                let new_path = match sequence_names.get(&entry.path) {
+                    Some(name)
+                        if !self.file_system.exists(&category_folder.join(name))
+                            && !planned.contains(&category_folder.join(name)) =>
+                    {
+                        category_folder.join(name)
+                    }
+                    _ => match self.resolve_conflict(
+                        &category_folder.join(entry.path.file_name().unwrap()),
+                        &planned,
+                    ) {
+                        Some(path) => path,
+                        None => {
+                            if self.is_selected(entry) {
+                                self.skip_conflict(entry, &category_folder, &mut warnings);
+                                skipped_files.push(entry.path.clone());
+                            }
+                            continue;
+                        }
+                    },
+                };
+                let replacing = self.file_system.exists(&new_path);
+                planned.insert(new_path.clone());
+                organized_paths.insert(entry.path.clone(), new_path.clone());
+                if !self.is_selected(entry) {
+                    continue;
+                }
 
+                if let Some(log) = &mut undo_log {
+                    log.record(&entry.path, &new_path, self.copying());
+                }
                 if !dry_run {
-                    if let Ok(true) = self.safe_move(&entry.path, &new_path) {
-                        actions_taken.push(format!(
-                            "Moved {} to {} folder",
-                            entry.path.file_name().unwrap_or_default().to_string_lossy(),
-                            entry.category.as_str()
-                        ));
+                    let size = self.file_system.metadata(&entry.path).map(|m| m.len).unwrap_or(0);
+                    match self.safe_move(&entry.path, &new_path) {
+                        Ok(true) => {
+                            files_moved += 1;
+                            bytes_moved += size;
+                            if let Some(journal) = &mut journal
+                                && journal.record(&entry.path, &new_path, size)
+                                && let Some(log) = &mut undo_log
+                            {
+                                log.save()?;
+                            }
+                            changed.push(entry.path.clone());
+                            actions_taken.push(format!(
+                                "{} {} {} {} folder",
+                                match (replacing, self.copying()) {
+                                    (true, _) => "Replaced",
+                                    (false, true) => "Copied",
+                                    (false, false) => "Moved",
+                                },
+                                entry.path.file_name().unwrap_or_default().to_string_lossy(),
+                                if replacing { "in" } else { "to" },
+                                Self::folder_label(&self.folder_path, &category_folder)
+                            ));
+                        }
+                        Ok(false) => {
+                            if let Some(log) = &mut undo_log {
+                                log.forget(&entry.path);
+                            }
+                        }
+                        Err(e) => {
+                            warnings.push(Warning::MoveFailed(entry.path.clone(), e.to_string()));
+                            if let Some(log) = &mut undo_log {
+                                log.forget(&entry.path);
+                            }
+                            if let Some(journal) = &mut journal {
+                                for (source, size) in
+                                    self.roll_back(journal, &mut actions_taken, &mut warnings)
+                                {
+                                    files_moved -= 1;
+                                    bytes_moved -= size;
+                                    changed.retain(|path| path != &source);
+                                    if let Some(log) = &mut undo_log {
+                                        log.forget(&source);
+                                    }
+                                }
+                                aborted = true;
+                            }
+                        }
                     }
                 } else {
+                    self.plan_directory(&category_folder, &mut planned_actions);
+                    planned_actions.push(PlannedAction::MoveFile {
+                        from: entry.path.clone(),
+                        to: new_path.clone(),
+                    });
+                    changed.push(entry.path.clone());
                     actions_taken.push(format!(
-                        "Would move {} to {} folder",
+                        "Would {} {} {} {} folder",
+                        match (replacing, self.copying()) {
+                            (true, _) => "replace",
+                            (false, true) => "copy",
+                            (false, false) => "move",
+                        },
                         entry.path.file_name().unwrap_or_default().to_string_lossy(),
-                        entry.category.as_str()
+                        if replacing { "in" } else { "to" },
+                        Self::folder_label(&self.folder_path, &category_folder)
                     ));
                 }
             }
         }
 
         // Organize duplicates
-        if !duplicates.is_empty() {
+        if self.quiet_duplicates && !aborted {
+            actions_taken.push(self.log_duplicates(duplicates, &organized_paths, dry_run)?);
+        } else if self.duplicate_action == DuplicateAction::Trash && !aborted {
+            actions_taken.extend(self.trash_duplicates(
+                duplicates,
+                &organized_paths,
+                dry_run,
+                &mut warnings,
+                &mut changed,
+            ));
+        } else if !duplicates.is_empty() && !aborted {
             let dup_folder = self.folder_path.join("Duplicates");
             if !dry_run {
-                fs::create_dir_all(&dup_folder)?;
+                self.create_folder(&dup_folder)?;
             }
 
             for dup_group in duplicates {
                 for entry in &dup_group.files[1..] {
+                    if aborted {
+                        break;
+                    }
+                    if self.is_ignored(&entry.path) {
+                        continue;
+                    }
+                    if self.is_self_targeting(&entry.path, &dup_folder) {
+                        warnings.push(Warning::Skipped(
+                            entry.path.clone(),
+                            "destination resolves to its own location".to_string(),
+                        ));
+                        continue;
+                    }
                     if entry.path.parent() != Some(&dup_folder) {
-                        let new_path = self.get_unique_path(&dup_folder.join(entry.path.file_name().unwrap()));
-
-                        if !dry_run {
-                            if let Ok(true) = self.safe_move(&entry.path, &new_path) {
-                                let original = &dup_group.files[0];
+                        let Some(new_path) = self.resolve_conflict(
+                            &dup_folder.join(entry.path.file_name().unwrap()),
+                            &planned,
+                        ) else {
+                            if self.is_selected(entry) {
+                                self.skip_conflict(entry, &dup_folder, &mut warnings);
+                                skipped_files.push(entry.path.clone());
+                            }
+                            continue;
+                        };
+                        planned.insert(new_path.clone());
+                        if !self.is_selected(entry) {
+                            continue;
+                        }
+                        let original = &dup_group.files[0];
+
+                        let target = organized_paths
+                            .get(&original.path)
+                            .cloned()
+                            .unwrap_or_else(|| original.path.clone());
+                        // Linking removes the duplicate, so probable matches get a full
+                        // comparison first and are only moved if it fails
+                        let verified = dup_group.confirmed || {
+                            let current = if self.file_system.exists(&target) { &target } else { &original.path };
+                            self.same_contents(current, &entry.path).unwrap_or(false)
+                        };
+
+                        if self.link_duplicates && cfg!(unix) && verified {
+                            let verb = if dry_run { "Would link" } else { "Linked" };
+                            if let Some(log) = &mut undo_log {
+                                log.record_link(&entry.path, &new_path, self.copying());
+                            }
+                            let linked = dry_run
+                                || match self.link_duplicate(&entry.path, &new_path, &target) {
+                                    Ok(linked) => linked,
+                                    Err(e) => {
+                                        warnings.push(Warning::MoveFailed(
+                                            entry.path.clone(),
+                                            e.to_string(),
+                                        ));
+                                        false
+                                    }
+                                };
+                            if !linked && let Some(log) = &mut undo_log {
+                                log.forget(&entry.path);
+                            }
+                            if linked {
+                                changed.push(entry.path.clone());
                                 actions_taken.push(format!(
-                                    "Moved duplicate {} to Duplicates folder (identical to {})",
+                                    "{} duplicate {} in Duplicates folder to {}",
+                                    verb,
                                     entry.path.file_name().unwrap_or_default().to_string_lossy(),
-                                    original.path.file_name().unwrap_or_default().to_string_lossy()
+                                    target
+                                        .strip_prefix(&self.folder_path)
+                                        .unwrap_or(&target)
+                                        .display()
                                 ));
                             }
+                        } else if !dry_run {
+                            let size = self.file_system.metadata(&entry.path).map(|m| m.len).unwrap_or(0);
+                            if let Some(log) = &mut undo_log {
+                                log.record(&entry.path, &new_path, self.copying());
+                            }
+                            match self.safe_move(&entry.path, &new_path) {
+                                Ok(true) => {
+                                    files_moved += 1;
+                                    bytes_moved += size;
+                                    if let Some(journal) = &mut journal
+                                        && journal.record(&entry.path, &new_path, size)
+                                        && let Some(log) = &mut undo_log
+                                    {
+                                        log.save()?;
+                                    }
+                                    changed.push(entry.path.clone());
+                                    actions_taken.push(format!(
+                                        "{} duplicate {} to Duplicates folder (identical to {})",
+                                        if self.copying() { "Copied" } else { "Moved" },
+                                        entry.path.file_name().unwrap_or_default().to_string_lossy(),
+                                        original.path.file_name().unwrap_or_default().to_string_lossy()
+                                    ));
+                                }
+                                Ok(false) => {
+                                    if let Some(log) = &mut undo_log {
+                                        log.forget(&entry.path);
+                                    }
+                                }
+                                Err(e) => {
+                                    warnings.push(Warning::MoveFailed(entry.path.clone(), e.to_string()));
+                                    if let Some(log) = &mut undo_log {
+                                        log.forget(&entry.path);
+                                    }
+                                    if let Some(journal) = &mut journal {
+                                        for (source, size) in
+                                            self.roll_back(journal, &mut actions_taken, &mut warnings)
+                                        {
+                                            files_moved -= 1;
+                                            bytes_moved -= size;
+                                            changed.retain(|path| path != &source);
+                                            if let Some(log) = &mut undo_log {
+                                                log.forget(&source);
+                                            }
+                                        }
+                                        aborted = true;
+                                    }
+                                }
+                            }
                         } else {
-                            let original = &dup_group.files[0];
+                            if let Some(log) = &mut undo_log {
+                                log.record(&entry.path, &new_path, self.copying());
+                            }
+                            self.plan_directory(&dup_folder, &mut planned_actions);
+                            planned_actions.push(PlannedAction::MoveDuplicate {
+                                from: entry.path.clone(),
+                                to: new_path.clone(),
+                                original: original.path.clone(),
+                            });
+                            changed.push(entry.path.clone());
                             actions_taken.push(format!(
-                                "Would move duplicate {} to Duplicates folder (identical to {})",
+                                "Would {} duplicate {} to Duplicates folder (identical to {})",
+                                if self.copying() { "copy" } else { "move" },
                                 entry.path.file_name().unwrap_or_default().to_string_lossy(),
                                 original.path.file_name().unwrap_or_default().to_string_lossy()
                             ));
@@ -161,11 +1774,85 @@ impl Organizer {
             }
         }
 
+        if let Some(log) = &mut undo_log {
+            log.save()?;
+        }
+        if let Ok(mut reports) = self.permission_reports.lock() {
+            actions_taken.append(&mut reports);
+        }
+
+        // Every transfer in a run is of the same kind
+        let (files_moved, files_copied) = match self.copying() {
+            true => (0, files_moved),
+            false => (files_moved, 0),
+        };
         Ok(OrganizationSummary {
             actions_taken,
             duplicates_found: duplicates.to_vec(),
+            warnings,
+            files_moved,
+            files_copied,
+            bytes_moved,
+            changed,
+            skipped_files,
+            planned_actions,
         })
     }
+
+    // Plans a folder's creation the first time a dry run would move something into it
+    fn plan_directory(&self, folder: &Path, planned_actions: &mut Vec<PlannedAction>) {
+        let planned = planned_actions
+            .iter()
+            .any(|a| matches!(a, PlannedAction::CreateDirectory { path } if path == folder));
+        if !planned && !self.file_system.exists(folder) {
+            planned_actions.push(PlannedAction::CreateDirectory {
+                path: folder.to_path_buf(),
+            });
+        }
+    }
+}
+
+// Builds an Organizer for a folder that is checked to exist. Anything not set here
+// can still be set with the `with_` methods on the organizer it returns.
+pub struct OrganizerBuilder {
+    folder_path: PathBuf,
+    verbose: bool,
+    conflict_strategy: ConflictStrategy,
+    mode: MoveMode,
+}
+
+impl OrganizerBuilder {
+    pub fn new(folder_path: PathBuf) -> Self {
+        Self {
+            folder_path,
+            verbose: false,
+            conflict_strategy: ConflictStrategy::default(),
+            mode: MoveMode::default(),
+        }
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn conflict_strategy(mut self, strategy: ConflictStrategy) -> Self {
+        self.conflict_strategy = strategy;
+        self
+    }
+
+    pub fn mode(mut self, mode: MoveMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn build(self) -> Result<Organizer> {
+        check_folder(&self.folder_path)?;
+        Ok(
+            Organizer::new_with_strategy(self.folder_path, self.verbose, self.conflict_strategy)
+                .with_mode(self.mode),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -175,75 +1862,558 @@ mod tests {
     use tempfile::TempDir;
 
     #[test]
-    fn test_clean_filename() -> Result<()> {
+    fn test_clean_filename() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+
+        let path = PathBuf::from("/path/to/file_1.txt");
+        let cleaned = organizer.clean_filename(&path);
+        assert_eq!(cleaned.file_name().unwrap(), "file.txt");
+
+        let path2 = PathBuf::from("/path/to/file.txt");
+        let cleaned2 = organizer.clean_filename(&path2);
+        assert_eq!(cleaned2.file_name().unwrap(), "file.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_unique_path() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+
+        let file1 = temp_dir.path().join("test.txt");
+        File::create(&file1)?;
+
+        let unique_path = organizer.get_unique_path(&file1, &HashSet::new());
+        assert_eq!(unique_path.file_name().unwrap(), "test (1).txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_unique_path_multiple_conflicts() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+
+        let file1 = temp_dir.path().join("test.txt");
+        let file2 = temp_dir.path().join("test (1).txt");
+        let file3 = temp_dir.path().join("test (2).txt");
+
+        File::create(&file1)?;
+        File::create(&file2)?;
+        File::create(&file3)?;
+
+        let unique_path = organizer.get_unique_path(&file1, &HashSet::new());
+        assert_eq!(unique_path.file_name().unwrap(), "test (3).txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_unique_path_underscore_suffix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer =
+            Organizer::new(temp_dir.path().to_path_buf(), false).with_collision_suffix("_{n}");
+
+        let file1 = temp_dir.path().join("test.txt");
+        File::create(&file1)?;
+
+        let unique_path = organizer.get_unique_path(&file1, &HashSet::new());
+        assert_eq!(unique_path.file_name().unwrap(), "test_1.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_unique_path_timestamp_suffix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_collision_suffix("-{timestamp}");
+
+        let file1 = temp_dir.path().join("test.txt");
+        File::create(&file1)?;
+        let first = organizer.get_unique_path(&file1, &HashSet::new());
+        File::create(&first)?;
+        let second = organizer.get_unique_path(&file1, &HashSet::new());
+
+        let name = first.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("test-"));
+        assert!(name.ends_with(".txt"));
+        assert!(name["test-".len()..name.len() - 4].chars().all(|c| c.is_ascii_digit()));
+        assert_ne!(first, second);
+        assert!(!second.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_folder_files_keep_their_suffix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        let pdfs = root.join("PDFs");
+        fs::create_dir(&pdfs)?;
+        fs::write(pdfs.join("report_1.pdf"), "report")?;
+        fs::write(pdfs.join("notes.pdf"), "notes")?;
+        fs::write(pdfs.join("notes_1.pdf"), "other notes")?;
+        let entries: Vec<FileEntry> = ["report_1.pdf", "notes.pdf", "notes_1.pdf"]
+            .iter()
+            .map(|name| FileEntry {
+                path: pdfs.join(name),
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            })
+            .collect();
+
+        let summary = Organizer::new(root.clone(), false).organize_files(&entries, &[], false)?;
+
+        // Files already in their category folder keep their names
+        assert_eq!(fs::read_to_string(pdfs.join("report_1.pdf"))?, "report");
+        assert!(!pdfs.join("report.pdf").exists());
+        assert_eq!(fs::read_to_string(pdfs.join("notes_1.pdf"))?, "other notes");
+        assert!(summary.actions_taken.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_filename_with_multiple_numbers() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+
+        let path = PathBuf::from("/path/to/file_123.txt");
+        let cleaned = organizer.clean_filename(&path);
+        assert_eq!(cleaned.file_name().unwrap(), "file.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_filename_no_extension() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+
+        let path = PathBuf::from("/path/to/file_1");
+        let cleaned = organizer.clean_filename(&path);
+        // Files without extensions won't match the regex pattern, so they stay unchanged
+        assert_eq!(cleaned.file_name().unwrap(), "file_1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_into_place_leaves_no_temp_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let source = temp_dir.path().join("source.pdf");
+        fs::write(&source, b"full content")?;
+        let dest_dir = temp_dir.path().join("PDFs");
+        fs::create_dir(&dest_dir)?;
+        let dest = dest_dir.join("source.pdf");
+
+        Organizer::new(temp_dir.path().to_path_buf(), false).copy_into_place(&source, &dest)?;
+
+        assert_eq!(fs::read(&dest)?, b"full content");
+        let leftovers: Vec<_> = fs::read_dir(&dest_dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(".desktidy-tmp-"))
+            .collect();
+        assert!(leftovers.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_mode_keeps_source_when_verification_fails() -> Result<()> {
+        // Gives every path its own key, so no copy ever matches its source
+        struct MismatchKeyer;
+        impl DuplicateKeyer for MismatchKeyer {
+            fn key(&self, path: &Path) -> Result<String> {
+                Ok(path.display().to_string())
+            }
+        }
+
+        let temp_dir = TempDir::new()?;
+        let source = temp_dir.path().join("thesis.pdf");
+        fs::write(&source, b"irreplaceable")?;
+        let entry = FileEntry {
+            path: source.clone(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_safe_mode(true)
+            .with_verifier(Box::new(MismatchKeyer));
+        let summary = organizer.organize_files(&[entry], &[], false)?;
+
+        assert_eq!(fs::read(&source)?, b"irreplaceable");
+        assert_eq!(summary.files_moved, 0);
+        assert!(matches!(&summary.warnings[..], [Warning::MoveFailed(path, _)] if path == &source));
+        let pdfs = temp_dir.path().join("PDFs");
+        assert!(!pdfs.exists() || fs::read_dir(&pdfs)?.next().is_none());
+
+        // With the real checksums the copy verifies and the source is removed
+        let verified = Organizer::new(temp_dir.path().to_path_buf(), false).with_safe_mode(true);
+        assert!(verified.safe_move(&source, &pdfs.join("thesis.pdf"))?);
+        assert!(!source.exists());
+        assert_eq!(fs::read(pdfs.join("thesis.pdf"))?, b"irreplaceable");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_in_memory_file_system() -> Result<()> {
+        use crate::filesystem::MemoryFileSystem;
+
+        // A root that does not exist on disk, so any real I/O would fail
+        let root = PathBuf::from("/desktidy-in-memory/desk");
+        let memory = Arc::new(
+            MemoryFileSystem::new()
+                .with_file(root.join("report.pdf"), "report")
+                .with_file(root.join("report (copy).pdf"), "report")
+                .with_file(root.join("PDFs/summary.pdf"), "older")
+                .with_file(root.join("summary.pdf"), "newer"),
+        );
+        let entry = |name: &str| FileEntry {
+            path: root.join(name),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let duplicates = vec![DuplicateGroup {
+            checksum_key: "report".to_string(),
+            files: vec![entry("report.pdf"), entry("report (copy).pdf")],
+            confirmed: true,
+            space_wasted_bytes: 0,
+        }];
+        let entries = [entry("report.pdf"), entry("report (copy).pdf"), entry("summary.pdf")];
+
+        let organizer = Organizer::new(root.clone(), false).with_file_system(memory.clone());
+        let summary = organizer.organize_files(&entries, &duplicates, false)?;
+
+        assert_eq!(summary.files_moved, 3);
+        assert_eq!(summary.bytes_moved, 17);
+        assert_eq!(
+            memory.files(),
+            vec![
+                root.join("Duplicates/report (copy).pdf"),
+                root.join("PDFs/report.pdf"),
+                root.join("PDFs/summary (1).pdf"),
+                root.join("PDFs/summary.pdf"),
+            ]
+        );
+        assert_eq!(memory.read(&root.join("PDFs/summary (1).pdf")), Some(b"newer".to_vec()));
+        assert!(!root.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_and_archive_in_memory_file_system() -> Result<()> {
+        use crate::filesystem::MemoryFileSystem;
+
+        let root = PathBuf::from("/desktidy-in-memory/desk");
+        let memory = Arc::new(
+            MemoryFileSystem::new()
+                .with_file(root.join("report.pdf"), "report")
+                .with_file(root.join("letter.docx"), "first letter"),
+        );
+        let entry = |name: &str, category| FileEntry {
+            path: root.join(name),
+            category,
+            content_category: None,
+            size_bytes: 0,
+        };
+
+        let organizer = Organizer::new(root.clone(), false)
+            .with_file_system(memory.clone())
+            .with_undo_log(true);
+        organizer.organize_files(&[entry("report.pdf", FileCategory::PDFs)], &[], false)?;
+        assert!(memory.exists(&organizer.undo_log_path()));
+        let undone = organizer.undo()?;
+        assert_eq!(undone.files_moved, 1);
+        assert_eq!(memory.read(&root.join("report.pdf")), Some(b"report".to_vec()));
+        assert!(!memory.exists(&organizer.undo_log_path()));
+
+        // A second run appends to the archive the first one wrote
+        let archiver = Organizer::new(root.clone(), false)
+            .with_file_system(memory.clone())
+            .with_archive(true);
+        let letter = [entry("letter.docx", FileCategory::Documents)];
+        archiver.organize_files(&letter, &[], false)?;
+        memory.create(&root.join("letter.docx"))?.write_all(b"second letter")?;
+        archiver.organize_files(&letter, &[], false)?;
+
+        let zip = memory.read(&root.join("Documents.zip")).unwrap();
+        let mut archive = ZipArchive::new(std::io::Cursor::new(zip))?;
+        assert_eq!(archive.len(), 2);
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("letter.docx")?, &mut contents)?;
+        assert_eq!(contents, "first letter");
+        assert!(!memory.exists(&root.join("letter.docx")));
+        assert!(!root.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_mode_links_and_indexes_in_memory_file_system() -> Result<()> {
+        use crate::filesystem::MemoryFileSystem;
+
+        let root = PathBuf::from("/desktidy-in-memory/desk");
+        let memory = Arc::new(
+            MemoryFileSystem::new()
+                .with_file(root.join("thesis.pdf"), "irreplaceable")
+                .with_file(root.join("notes/draft.pdf"), "draft"),
+        );
+        memory.symlink(Path::new("notes/draft.pdf"), &root.join("draft.pdf"))?;
+
+        let organizer = Organizer::new(root.clone(), false)
+            .with_file_system(memory.clone())
+            .with_safe_mode(true);
+        assert!(organizer.safe_move(&root.join("thesis.pdf"), &root.join("PDFs/thesis.pdf"))?);
+        assert_eq!(memory.read(&root.join("PDFs/thesis.pdf")), Some(b"irreplaceable".to_vec()));
+        assert!(!memory.exists(&root.join("thesis.pdf")));
+
+        #[cfg(unix)]
+        {
+            assert!(organizer.safe_move(&root.join("draft.pdf"), &root.join("PDFs/draft.pdf"))?);
+            assert_eq!(memory.read_link(&root.join("PDFs/draft.pdf"))?, Path::new("../notes/draft.pdf"));
+            assert!(memory.metadata(&root.join("draft.pdf")).is_err());
+        }
+
+        organizer.write_category_indexes()?;
+        let index = String::from_utf8(memory.read(&root.join("PDFs").join(INDEX_FILE_NAME)).unwrap())?;
+        assert!(index.contains("thesis.pdf\t13 bytes"));
+        assert!(!root.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_device_move_copies_then_deletes() -> Result<()> {
+        use crate::filesystem::MemoryFileSystem;
+
+        let root = PathBuf::from("/desktidy-in-memory/desk");
+        let memory = Arc::new(
+            MemoryFileSystem::new()
+                .with_file(root.join("movie.mp4"), "frames")
+                .with_file(root.join("notes.pdf"), "notes")
+                .with_device(root.join("Videos"))
+                .with_read_only(root.join("PDFs")),
+        );
+        let organizer = Organizer::new(root.clone(), false).with_file_system(memory.clone());
+
+        assert!(organizer.safe_move(&root.join("movie.mp4"), &root.join("Videos/movie.mp4"))?);
+        assert_eq!(memory.read(&root.join("Videos/movie.mp4")), Some(b"frames".to_vec()));
+        assert!(!memory.exists(&root.join("movie.mp4")));
+        assert_eq!(memory.files().len(), 2);
+
+        // Other errors are not retried as a copy
+        let denied = organizer.safe_move(&root.join("notes.pdf"), &root.join("PDFs/notes.pdf"));
+        let error = denied.unwrap_err().downcast::<std::io::Error>()?;
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(memory.exists(&root.join("notes.pdf")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_duplicates() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        // Sandboxes and CI machines may have no usable trash
+        fs::write(root.join("probe.txt"), "probe")?;
+        if trash::delete(root.join("probe.txt")).is_err() {
+            eprintln!("no system trash available, skipping");
+            return Ok(());
+        }
+
+        fs::write(root.join("report.pdf"), "report")?;
+        fs::write(root.join("report copy.pdf"), "report")?;
+        let entry = |name: &str| FileEntry {
+            path: root.join(name),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let duplicates = vec![DuplicateGroup {
+            checksum_key: "report".to_string(),
+            files: vec![entry("report.pdf"), entry("report copy.pdf")],
+            confirmed: true,
+            space_wasted_bytes: 0,
+        }];
+
+        let summary = Organizer::new(root.clone(), false)
+            .with_duplicate_action(DuplicateAction::Trash)
+            .organize_files(&[entry("report.pdf"), entry("report copy.pdf")], &duplicates, false)?;
+
+        assert!(!root.join("report copy.pdf").exists());
+        assert!(!root.join("Duplicates").exists());
+        assert!(root.join("PDFs/report.pdf").exists());
+        assert!(summary.actions_taken.contains(&"Sent duplicate report copy.pdf to trash".to_string()));
+
+        // Leave the real trash as it was
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            let trashed = trash::os_limited::list()?
+                .into_iter()
+                .filter(|item| item.original_parent == root);
+            trash::os_limited::purge_all(trashed)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_checks_folder() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("notes.txt"), "notes")?;
+
+        let organizer = Organizer::builder(temp_dir.path())
+            .verbose(true)
+            .conflict_strategy(ConflictStrategy::Skip)
+            .mode(MoveMode::Copy)
+            .build()?;
+        assert!(organizer.copying());
+        assert_eq!(organizer.conflict_strategy, ConflictStrategy::Skip);
+        assert!(Organizer::builder(temp_dir.path().join("missing")).build().is_err());
+        assert!(Organizer::builder(temp_dir.path().join("notes.txt")).build().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_root_outside_scan_folder() -> Result<()> {
         let temp_dir = TempDir::new()?;
-        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
-
-        let path = PathBuf::from("/path/to/file_1.txt");
-        let cleaned = organizer.clean_filename(&path);
-        assert_eq!(cleaned.file_name().unwrap(), "file.txt");
-
-        let path2 = PathBuf::from("/path/to/file.txt");
-        let cleaned2 = organizer.clean_filename(&path2);
-        assert_eq!(cleaned2.file_name().unwrap(), "file.txt");
+        let photos_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("beach.jpg"), "sand")?;
+        fs::write(temp_dir.path().join("notes.docx"), "notes")?;
+        let entries = [
+            FileEntry {
+                path: temp_dir.path().join("beach.jpg"),
+                category: FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            },
+            FileEntry {
+                path: temp_dir.path().join("notes.docx"),
+                category: FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ];
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false).with_category_roots(
+            HashMap::from([(FileCategory::Images, photos_dir.path().join("Photos"))]),
+        );
+        organizer.create_category_folders(&["Images", "Documents"])?;
+        organizer.organize_files(&entries, &[], false)?;
+
+        assert_eq!(fs::read(photos_dir.path().join("Photos/beach.jpg"))?, b"sand");
+        assert_eq!(fs::read(temp_dir.path().join("Documents/notes.docx"))?, b"notes");
+        assert!(!temp_dir.path().join("Images").exists());
+        assert!(!temp_dir.path().join("beach.jpg").exists());
 
         Ok(())
     }
 
     #[test]
-    fn test_get_unique_path() -> Result<()> {
-        let temp_dir = TempDir::new()?;
-        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+    fn test_category_root_on_another_device() -> Result<()> {
+        use crate::filesystem::MemoryFileSystem;
+
+        let root = PathBuf::from("/desktidy-in-memory/desk");
+        let photos = PathBuf::from("/desktidy-in-memory/photos");
+        let memory = Arc::new(
+            MemoryFileSystem::new()
+                .with_file(root.join("beach.jpg"), "sand")
+                .with_device(photos.clone()),
+        );
+        let entry = FileEntry {
+            path: root.join("beach.jpg"),
+            category: FileCategory::Images,
+            content_category: None,
+            size_bytes: 0,
+        };
 
-        let file1 = temp_dir.path().join("test.txt");
-        File::create(&file1)?;
+        let organizer = Organizer::new(root.clone(), false)
+            .with_file_system(memory.clone())
+            .with_category_roots(HashMap::from([(FileCategory::Images, photos.clone())]));
+        let summary = organizer.organize_files(&[entry], &[], false)?;
 
-        let unique_path = organizer.get_unique_path(&file1);
-        assert_eq!(unique_path.file_name().unwrap(), "test (1).txt");
+        assert_eq!(summary.files_moved, 1);
+        assert_eq!(memory.files(), vec![photos.join("beach.jpg")]);
 
         Ok(())
     }
 
     #[test]
-    fn test_get_unique_path_multiple_conflicts() -> Result<()> {
+    fn test_throttled_copy_respects_rate() -> Result<()> {
         let temp_dir = TempDir::new()?;
-        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
-
-        let file1 = temp_dir.path().join("test.txt");
-        let file2 = temp_dir.path().join("test (1).txt");
-        let file3 = temp_dir.path().join("test (2).txt");
+        let source = temp_dir.path().join("large.mp4");
+        fs::write(&source, vec![7u8; 500_000])?;
+        let dest = temp_dir.path().join("copy.mp4");
 
-        File::create(&file1)?;
-        File::create(&file2)?;
-        File::create(&file3)?;
+        // 500 KB at 1 MB/s, less the 0.1 s burst allowance
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_throttle(Some(Arc::new(Throttle::new(1.0))));
+        let started = std::time::Instant::now();
+        organizer.copy_into_place(&source, &dest)?;
+        let elapsed = started.elapsed().as_secs_f64();
 
-        let unique_path = organizer.get_unique_path(&file1);
-        assert_eq!(unique_path.file_name().unwrap(), "test (3).txt");
+        assert_eq!(fs::metadata(&dest)?.len(), 500_000);
+        assert!(elapsed >= 0.35, "copy finished too quickly: {elapsed:.2}s");
+        assert!(elapsed < 3.0, "copy was throttled too hard: {elapsed:.2}s");
 
         Ok(())
     }
 
     #[test]
-    fn test_clean_filename_with_multiple_numbers() -> Result<()> {
+    fn test_safe_move_full_content_no_temp_files() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+        let source = temp_dir.path().join("doc.docx");
+        fs::write(&source, b"document body")?;
+        let dest = temp_dir.path().join("Documents").join("doc.docx");
 
-        let path = PathBuf::from("/path/to/file_123.txt");
-        let cleaned = organizer.clean_filename(&path);
-        assert_eq!(cleaned.file_name().unwrap(), "file.txt");
+        assert!(organizer.safe_move(&source, &dest)?);
+
+        assert!(!source.exists());
+        assert_eq!(fs::read(&dest)?, b"document body");
+        let leftovers = fs::read_dir(temp_dir.path().join("Documents"))?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(".desktidy-tmp-"))
+            .count();
+        assert_eq!(leftovers, 0);
 
         Ok(())
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_clean_filename_no_extension() -> Result<()> {
-        let temp_dir = TempDir::new()?;
-        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+    fn test_preserve_permissions_keeps_mode() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
 
-        let path = PathBuf::from("/path/to/file_1");
-        let cleaned = organizer.clean_filename(&path);
-        // Files without extensions won't match the regex pattern, so they stay unchanged
-        assert_eq!(cleaned.file_name().unwrap(), "file_1");
+        let temp_dir = TempDir::new()?;
+        let source = temp_dir.path().join("private.pdf");
+        fs::write(&source, b"secret")?;
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o640))?;
+        let dest = temp_dir.path().join("PDFs").join("private.pdf");
+
+        let organizer =
+            Organizer::new(temp_dir.path().to_path_buf(), false).with_preserve_permissions(true);
+        organizer.safe_move(&source, &dest)?;
+        assert_eq!(fs::metadata(&dest)?.permissions().mode() & 0o7777, 0o640);
+
+        // A destination whose mode drifted is restored and reported
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o600))?;
+        organizer.restore_permissions(&dest, fs::Permissions::from_mode(0o640))?;
+        assert_eq!(fs::metadata(&dest)?.permissions().mode() & 0o7777, 0o640);
+        let reports = organizer.permission_reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].contains("640 would have become 600"));
 
         Ok(())
     }
@@ -264,6 +2434,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_category_name_taken_by_a_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join("Documents"), "not a folder")?;
+        fs::write(root.join("letter.docx"), "letter")?;
+        let organizer = Organizer::new(root.to_path_buf(), false);
+
+        let error = organizer.create_category_folders(&["Images", "Documents"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot create Documents folder: a file with that name exists"
+        );
+
+        // Created on demand, the failure is a warning for that file alone
+        let entry = FileEntry {
+            path: root.join("letter.docx"),
+            category: FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let summary = organizer.organize_files(&[entry], &[], false)?;
+        assert!(matches!(
+            &summary.warnings[..],
+            [Warning::MoveFailed(_, reason)] if reason.contains("a file with that name exists")
+        ));
+        assert!(root.join("letter.docx").exists());
+        assert_eq!(fs::read_to_string(root.join("Documents"))?, "not a folder");
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_existing_category_folders() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -290,6 +2492,8 @@ mod tests {
         let entry = FileEntry {
             path: file1.clone(),
             category: crate::types::FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
         };
 
         let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
@@ -304,6 +2508,94 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_quiet_duplicates_are_logged_not_moved() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join("a.jpg"), "same")?;
+        fs::write(root.join("a (1).jpg"), "same")?;
+        fs::write(root.join("notes.pdf"), "notes")?;
+        let entry = |name: &str, category: FileCategory| FileEntry {
+            path: root.join(name),
+            category,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let duplicates = vec![DuplicateGroup {
+            checksum_key: "same".to_string(),
+            files: vec![entry("a.jpg", FileCategory::Images), entry("a (1).jpg", FileCategory::Images)],
+            confirmed: true,
+            space_wasted_bytes: 0,
+        }];
+        let entries = [
+            entry("a.jpg", FileCategory::Images),
+            entry("a (1).jpg", FileCategory::Images),
+            entry("notes.pdf", FileCategory::PDFs),
+        ];
+
+        let summary = Organizer::new(root.to_path_buf(), false)
+            .with_quiet_duplicates(true)
+            .organize_files(&entries, &duplicates, false)?;
+
+        assert!(!root.join("Duplicates").exists());
+        assert!(root.join("Images/a.jpg").exists());
+        assert!(root.join("PDFs/notes.pdf").exists());
+        assert!(root.join("a (1).jpg").exists());
+        assert_eq!(
+            fs::read_to_string(root.join(DUPLICATES_LOG_FILE_NAME))?,
+            format!("a (1).jpg\t{}\n", Path::new("Images").join("a.jpg").display())
+        );
+        assert_eq!(summary.files_moved, 2);
+        assert!(summary.actions_taken.contains(&"Logged 1 duplicate in duplicates.log".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run_plans_structured_actions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join("a.jpg"), "same")?;
+        fs::write(root.join("b.jpg"), "same")?;
+        fs::write(root.join("c.jpg"), "other")?;
+        let entry = |name: &str| FileEntry {
+            path: root.join(name),
+            category: FileCategory::Images,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let duplicates = vec![DuplicateGroup {
+            checksum_key: "same".to_string(),
+            files: vec![entry("a.jpg"), entry("b.jpg")],
+            confirmed: true,
+            space_wasted_bytes: 0,
+        }];
+
+        let organizer = Organizer::new(root.to_path_buf(), false);
+        let entries = [entry("a.jpg"), entry("b.jpg"), entry("c.jpg")];
+        let summary = organizer.organize_files(&entries, &duplicates, true)?;
+        assert_eq!(
+            summary.planned_actions,
+            vec![
+                PlannedAction::CreateDirectory { path: root.join("Images") },
+                PlannedAction::MoveFile { from: root.join("a.jpg"), to: root.join("Images/a.jpg") },
+                PlannedAction::MoveFile { from: root.join("c.jpg"), to: root.join("Images/c.jpg") },
+                PlannedAction::CreateDirectory { path: root.join("Duplicates") },
+                PlannedAction::MoveDuplicate {
+                    from: root.join("b.jpg"),
+                    to: root.join("Duplicates/b.jpg"),
+                    original: root.join("a.jpg"),
+                },
+            ]
+        );
+        assert_eq!(summary.actions_taken.len(), 3);
+
+        let summary = organizer.organize_files(&entries, &duplicates, false)?;
+        assert!(summary.planned_actions.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_organize_files_actual_move() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -313,6 +2605,8 @@ mod tests {
         let entry = FileEntry {
             path: file1.clone(),
             category: crate::types::FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
         };
 
         let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
@@ -328,6 +2622,749 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_group_documents_by_type() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let docx = temp_dir.path().join("letter.docx");
+        let txt = temp_dir.path().join("notes.txt");
+        File::create(&docx)?;
+        File::create(&txt)?;
+
+        let entries = vec![
+            FileEntry {
+                path: docx,
+                category: FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            },
+            FileEntry {
+                path: txt,
+                category: FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ];
+
+        let organizer =
+            Organizer::new(temp_dir.path().to_path_buf(), false).with_group_documents_by_type(true);
+        organizer.organize_files(&entries, &[], false)?;
+
+        let documents = temp_dir.path().join("Documents");
+        assert!(documents.join("Word").join("letter.docx").exists());
+        assert!(documents.join("Plain").join("notes.txt").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_per_folder_rollover() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for i in 1..=5 {
+            let path = temp_dir.path().join(format!("photo{}.jpg", i));
+            File::create(&path)?;
+            entries.push(FileEntry {
+                path,
+                category: FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        let organizer =
+            Organizer::new(temp_dir.path().to_path_buf(), false).with_max_per_folder(Some(2));
+        organizer.organize_files(&entries, &[], false)?;
+
+        let count = |name: &str| fs::read_dir(temp_dir.path().join(name)).unwrap().count();
+        assert_eq!(count("Images"), 2);
+        assert_eq!(count("Images_2"), 2);
+        assert_eq!(count("Images_3"), 1);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_duplicates_points_to_organized_original() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let original = temp_dir.path().join("original.pdf");
+        let duplicate = temp_dir.path().join("copy.pdf");
+        fs::write(&original, b"same bytes")?;
+        fs::write(&duplicate, b"same bytes")?;
+
+        let entry1 = FileEntry {
+            path: original,
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let entry2 = FileEntry {
+            path: duplicate.clone(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let dup_group = DuplicateGroup {
+            checksum_key: "test_key".to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
+            files: vec![entry1.clone(), entry2.clone()],
+        };
+
+        let organizer =
+            Organizer::new(temp_dir.path().to_path_buf(), false).with_link_duplicates(true);
+        organizer.organize_files(&[entry1, entry2], &[dup_group], false)?;
+
+        let organized = organizer.folder_path.join("PDFs").join("original.pdf");
+        let link = temp_dir.path().join("Duplicates").join("copy.pdf");
+        assert!(organized.exists());
+        assert!(!duplicate.exists());
+        assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+        assert_eq!(fs::read_link(&link)?, organized);
+        assert_eq!(fs::read(&link)?, b"same bytes");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_restores_linked_duplicate_as_a_copy() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let original = temp_dir.path().join("original.pdf");
+        let duplicate = temp_dir.path().join("copy.pdf");
+        fs::write(&original, b"same bytes")?;
+        fs::write(&duplicate, b"same bytes")?;
+        let entry = |path: &Path| FileEntry {
+            path: path.to_path_buf(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let dup_group = DuplicateGroup::new(
+            "test_key".to_string(),
+            vec![entry(&original), entry(&duplicate)],
+            true,
+        );
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_link_duplicates(true)
+            .with_undo_log(true);
+        organizer.organize_files(&[entry(&original), entry(&duplicate)], &[dup_group], false)?;
+        assert!(!duplicate.exists());
+
+        let summary = organizer.undo()?;
+        assert_eq!(summary.files_moved, 2);
+        assert_eq!(fs::read(&original)?, b"same bytes");
+        assert_eq!(fs::read(&duplicate)?, b"same bytes");
+        // The duplicate is a file of its own again, not a link
+        assert!(fs::symlink_metadata(&duplicate)?.file_type().is_file());
+        assert!(fs::symlink_metadata(temp_dir.path().join("Duplicates").join("copy.pdf")).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_preview_lists_links_a_dry_run_would_make() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let original = temp_dir.path().join("original.pdf");
+        let duplicate = temp_dir.path().join("copy.pdf");
+        fs::write(&original, b"same bytes")?;
+        fs::write(&duplicate, b"same bytes")?;
+        let entry = |path: &Path| FileEntry {
+            path: path.to_path_buf(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let dup_group = DuplicateGroup::new(
+            "test_key".to_string(),
+            vec![entry(&original), entry(&duplicate)],
+            true,
+        );
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_link_duplicates(true)
+            .with_undo_log(true);
+        organizer.organize_files(&[entry(&original), entry(&duplicate)], &[dup_group], true)?;
+        assert!(fs::symlink_metadata(&duplicate)?.file_type().is_file());
+
+        let preview = UndoManifest::load(&organizer.undo_log_path())?;
+        assert!(preview.dry_run);
+        let link = preview.moves.iter().find(|m| m.source == duplicate).unwrap();
+        assert!(link.linked);
+        assert_eq!(link.destination, organizer.folder_path.join("Duplicates").join("copy.pdf"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_mode_creates_category_zip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let doc1 = temp_dir.path().join("letter.docx");
+        let doc2 = temp_dir.path().join("notes.txt");
+        fs::write(&doc1, b"letter body")?;
+        fs::write(&doc2, b"some notes")?;
+
+        let entries = vec![
+            FileEntry {
+                path: doc1.clone(),
+                category: FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            },
+            FileEntry {
+                path: doc2.clone(),
+                category: FileCategory::Documents,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ];
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false).with_archive(true);
+        let summary = organizer.organize_files(&entries, &[], false)?;
+
+        let archive_path = temp_dir.path().join("Documents.zip");
+        assert!(archive_path.exists());
+        assert!(!doc1.exists());
+        assert!(!doc2.exists());
+        assert!(!temp_dir.path().join("Documents").exists());
+        assert_eq!(summary.actions_taken.len(), 2);
+
+        let mut archive = ZipArchive::new(File::open(&archive_path)?)?;
+        let mut names: Vec<_> = archive.file_names().map(String::from).collect();
+        names.sort();
+        assert_eq!(names, vec!["letter.docx", "notes.txt"]);
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("letter.docx")?, &mut contents)?;
+        assert_eq!(contents, "letter body");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_category_indexes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let doc = temp_dir.path().join("report.docx");
+        fs::write(&doc, b"12345")?;
+        let entry = FileEntry {
+            path: doc,
+            category: FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
+        };
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
+        organizer.organize_files(&[entry], &[], false)?;
+        let actions = organizer.write_category_indexes()?;
+
+        let index = fs::read_to_string(temp_dir.path().join("Documents").join(INDEX_FILE_NAME))?;
+        assert_eq!(actions.len(), 1);
+        assert!(index.contains("organized by desktidy on"));
+        assert!(index.contains("report.docx\t5 bytes"));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_self_targeting_move_is_skipped() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        let photo = root.join("photo.jpg");
+        std::fs::write(&photo, b"pixels")?;
+        // The category folder loops back to the scan root
+        std::os::unix::fs::symlink(&root, root.join("Images"))?;
+
+        let entry = FileEntry {
+            path: photo.clone(),
+            category: FileCategory::Images,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let organizer = Organizer::new(root.clone(), false);
+        let summary = organizer.organize_files(&[entry], &[], false)?;
+
+        assert!(photo.exists());
+        assert_eq!(std::fs::read_dir(&root)?.count(), 2);
+        assert_eq!(summary.files_moved, 0);
+        assert!(matches!(&summary.warnings[..], [Warning::Skipped(path, _)] if path == &photo));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_only_keeps_full_plan_names() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        let mut entries = Vec::new();
+        for (name, category) in [
+            ("photo.jpg", FileCategory::Images),
+            ("photo_1.jpg", FileCategory::Images),
+            ("report.pdf", FileCategory::PDFs),
+        ] {
+            File::create(root.join(name))?;
+            entries.push(FileEntry {
+                path: root.join(name),
+                category,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        // In the full plan photo.jpg claims Images/photo.jpg first
+        let filter = PlanFilter::parse("photo_1*", &CategorySet::default())?;
+        let organizer = Organizer::new(root.clone(), false).with_apply_only(Some(filter));
+        organizer.organize_files(&entries, &[], false)?;
+
+        assert!(root.join("Images").join("photo (1).jpg").exists());
+        assert!(root.join("photo.jpg").exists());
+        assert!(root.join("report.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_unicode_nfd_to_nfc() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let nfd_name = "Cafe\u{301}.pdf";
+        let source = temp_dir.path().join(nfd_name);
+        File::create(&source)?;
+
+        let entry = FileEntry {
+            path: source.clone(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_normalize_unicode(Some(UnicodeForm::Nfc));
+        organizer.organize_files(&[entry], &[], false)?;
+
+        let names: Vec<String> = fs::read_dir(temp_dir.path().join("PDFs"))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["Caf\u{e9}.pdf".to_string()]);
+        assert!(!source.exists());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relative_symlink_still_resolves_after_move() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        fs::create_dir(root.join("library"))?;
+        fs::write(root.join("library").join("manual.pdf"), b"manual")?;
+        let link = root.join("manual-link.pdf");
+        std::os::unix::fs::symlink("library/manual.pdf", &link)?;
+
+        let entry = FileEntry {
+            path: link.clone(),
+            category: FileCategory::PDFs,
+            content_category: None,
+            size_bytes: 0,
+        };
+        Organizer::new(root.clone(), false).organize_files(&[entry], &[], false)?;
+
+        let moved = root.join("PDFs").join("manual-link.pdf");
+        assert!(fs::symlink_metadata(&moved)?.file_type().is_symlink());
+        assert_eq!(fs::read_link(&moved)?, PathBuf::from("../library/manual.pdf"));
+        assert_eq!(fs::read(&moved)?, b"manual");
+        assert!(root.join("library").join("manual.pdf").exists());
+        assert!(fs::symlink_metadata(&link).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_as_copies_materialize_targets() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let elsewhere = TempDir::new()?;
+        let target = elsewhere.path().join("album.mp3");
+        fs::write(&target, b"music")?;
+        let link = temp_dir.path().join("album.mp3");
+        std::os::unix::fs::symlink(&target, &link)?;
+
+        let entry = FileEntry {
+            path: link.clone(),
+            category: FileCategory::Audio,
+            content_category: None,
+            size_bytes: 0,
+        };
+        Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_symlinks_as_copies(true)
+            .organize_files(&[entry], &[], false)?;
+
+        let copy = temp_dir.path().join("Audio").join("album.mp3");
+        assert!(fs::symlink_metadata(&copy)?.file_type().is_file());
+        assert_eq!(fs::read(&copy)?, b"music");
+        assert_eq!(fs::read(&target)?, b"music");
+        assert!(fs::symlink_metadata(&link).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_folders_decade_bucket() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let photo = temp_dir.path().join("photo.jpg");
+        let file = File::create(&photo)?;
+        // 1995-07-14
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(805_680_000))?;
+        drop(file);
+
+        let entry = FileEntry {
+            path: photo,
+            category: FileCategory::Images,
+            content_category: None,
+            size_bytes: 0,
+        };
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_date_folders(Some(crate::dates::DateGranularity::Decade.format()));
+        organizer.organize_files(&[entry], &[], false)?;
+
+        assert!(temp_dir.path().join("Images").join("1990s").join("photo.jpg").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_subfolders_use_capture_date_for_images_only() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("beach.jpg"),
+            crate::location::tests::jpeg_with_fields(&[exif::Field {
+                tag: exif::Tag::DateTimeOriginal,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Ascii(vec![b"2023:07:14 09:30:05".to_vec()]),
+            }]),
+        )?;
+        // 1995-07-14, with no EXIF data to go by
+        fs::write(root.join("scan.jpg"), "scanned")?;
+        fs::write(root.join("notes.pdf"), "notes")?;
+        for name in ["scan.jpg", "notes.pdf"] {
+            File::options()
+                .write(true)
+                .open(root.join(name))?
+                .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(805_680_000))?;
+        }
+        let entry = |name: &str, category: FileCategory| FileEntry {
+            path: root.join(name),
+            category,
+            content_category: None,
+            size_bytes: 0,
+        };
+
+        Organizer::new(root.to_path_buf(), false)
+            .with_date_subfolders(true)
+            .organize_files(
+                &[
+                    entry("beach.jpg", FileCategory::Images),
+                    entry("scan.jpg", FileCategory::Images),
+                    entry("notes.pdf", FileCategory::PDFs),
+                ],
+                &[],
+                false,
+            )?;
+
+        assert!(root.join("Images/2023/07/beach.jpg").exists());
+        assert!(root.join("Images/1995/07/scan.jpg").exists());
+        assert!(root.join("PDFs/notes.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_date_nests_every_category_by_mtime() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let june_2023 = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_686_832_496);
+        for (name, contents) in [("photo.jpg", "new photo"), ("report.pdf", "report")] {
+            fs::write(root.join(name), contents)?;
+            File::options().write(true).open(root.join(name))?.set_modified(june_2023)?;
+        }
+        // Already organized last time, so the new photo needs another name
+        fs::create_dir_all(root.join("Images/2023/06"))?;
+        fs::write(root.join("Images/2023/06/photo.jpg"), "old photo")?;
+        let entries = [
+            FileEntry {
+                path: root.join("photo.jpg"),
+                category: FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            },
+            FileEntry {
+                path: root.join("report.pdf"),
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            },
+        ];
+
+        let organizer = Organizer::new(root.to_path_buf(), false)
+            .with_organize_by(OrganizeBy::CategoryThenDate);
+        organizer.create_category_folders(&["Images", "PDFs"])?;
+        organizer.organize_files(&entries, &[], false)?;
+
+        assert_eq!(fs::read_to_string(root.join("Images/2023/06/photo (1).jpg"))?, "new photo");
+        assert_eq!(fs::read_to_string(root.join("Images/2023/06/photo.jpg"))?, "old photo");
+        assert!(root.join("PDFs/2023/06/report.pdf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_sequence_names_follow_modification_order() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        // 2023-06-01 00:00 UTC
+        let day = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_685_577_600);
+        let mut entries = Vec::new();
+        for (name, hour) in [("scan_b.pdf", 10), ("scan_a.pdf", 9)] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, name)?;
+            File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(day + std::time::Duration::from_secs(hour * 3600))?;
+            entries.push(FileEntry {
+                path,
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+
+        Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_date_sequence_names(true)
+            .organize_files(&entries, &[], false)?;
+
+        let pdfs = temp_dir.path().join("PDFs");
+        assert_eq!(fs::read(pdfs.join("2023-06-01_001.pdf"))?, b"scan_a.pdf");
+        assert_eq!(fs::read(pdfs.join("2023-06-01_002.pdf"))?, b"scan_b.pdf");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflict_strategies() -> Result<()> {
+        let run = |strategy: ConflictStrategy| -> Result<(TempDir, OrganizationSummary)> {
+            let temp_dir = TempDir::new()?;
+            fs::create_dir(temp_dir.path().join("PDFs"))?;
+            fs::write(temp_dir.path().join("PDFs").join("report.pdf"), b"old")?;
+            let path = temp_dir.path().join("report.pdf");
+            fs::write(&path, b"new")?;
+            let entry = FileEntry {
+                path,
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            };
+            let summary = Organizer::new_with_strategy(temp_dir.path().to_path_buf(), false, strategy)
+                .organize_files(&[entry], &[], false)?;
+            Ok((temp_dir, summary))
+        };
+        let read = |dir: &TempDir, name: &str| fs::read(dir.path().join("PDFs").join(name));
+
+        let (dir, summary) = run(ConflictStrategy::Rename)?;
+        assert_eq!(read(&dir, "report.pdf")?, b"old");
+        assert_eq!(read(&dir, "report (1).pdf")?, b"new");
+        assert!(summary.skipped_files.is_empty());
+
+        let (dir, summary) = run(ConflictStrategy::Skip)?;
+        assert_eq!(read(&dir, "report.pdf")?, b"old");
+        assert!(dir.path().join("report.pdf").exists());
+        assert_eq!(summary.skipped_files, vec![dir.path().join("report.pdf")]);
+        assert_eq!(summary.files_moved, 0);
+
+        let (dir, summary) = run(ConflictStrategy::Overwrite)?;
+        assert_eq!(read(&dir, "report.pdf")?, b"new");
+        assert!(!dir.path().join("report.pdf").exists());
+        assert_eq!(summary.actions_taken, vec!["Replaced report.pdf in PDFs folder"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_mode_keeps_originals() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut entries = Vec::new();
+        for name in ["report.pdf", "report copy.pdf"] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, b"same")?;
+            entries.push(FileEntry {
+                path,
+                category: FileCategory::PDFs,
+                content_category: None,
+                size_bytes: 0,
+            });
+        }
+        let duplicates =
+            DuplicateFinder::new_with_strategy(false, crate::types::KeepStrategy::FirstFound)
+                .find_duplicates(&entries)?;
+
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_mode(MoveMode::Copy)
+            .with_undo_log(true);
+        let summary = organizer.organize_files(&entries, &duplicates, false)?;
+
+        assert!(entries.iter().all(|e| e.path.exists()));
+        assert_eq!((summary.files_moved, summary.files_copied), (0, 2));
+        assert!(temp_dir.path().join("PDFs").join("report.pdf").exists());
+        assert!(temp_dir.path().join("Duplicates").join("report copy.pdf").exists());
+        assert_eq!(
+            summary.actions_taken,
+            vec![
+                "Copied report.pdf to PDFs folder",
+                "Copied duplicate report copy.pdf to Duplicates folder (identical to report.pdf)",
+            ]
+        );
+
+        // Undoing a copy removes it and leaves the original alone
+        organizer.undo()?;
+        assert!(!temp_dir.path().join("PDFs").join("report.pdf").exists());
+        assert!(!temp_dir.path().join("Duplicates").join("report copy.pdf").exists());
+        assert!(entries.iter().all(|e| e.path.exists()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_files_land_in_code_folder() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        File::create(temp_dir.path().join("main.rs"))?;
+        File::create(temp_dir.path().join("script.py"))?;
+
+        let analysis = crate::FileAnalyzer::new(temp_dir.path().to_path_buf(), false).analyze()?;
+        let entries: Vec<FileEntry> = analysis.categories.values().flatten().cloned().collect();
+        Organizer::new(temp_dir.path().to_path_buf(), false).organize_files(&entries, &[], false)?;
+
+        assert!(temp_dir.path().join("Code").join("main.rs").exists());
+        assert!(temp_dir.path().join("Code").join("script.py").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_by_location_routes_photos_by_gps() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let tokyo = temp_dir.path().join("shibuya.jpg");
+        let no_gps = temp_dir.path().join("scan.jpg");
+        fs::write(&tokyo, crate::location::tests::jpeg_with_gps(35.6595, 139.7005))?;
+        fs::write(&no_gps, b"no exif here")?;
+        let entries: Vec<FileEntry> = [&tokyo, &no_gps]
+            .into_iter()
+            .map(|path| FileEntry {
+                path: path.clone(),
+                category: FileCategory::Images,
+                content_category: None,
+                size_bytes: 0,
+            })
+            .collect();
+
+        Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_by_location(true)
+            .organize_files(&entries, &[], false)?;
+
+        let images = temp_dir.path().join("Images");
+        assert!(images.join("Japan").join("Tokyo").join("shibuya.jpg").exists());
+        assert!(images.join("unknown").join("scan.jpg").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_limits_rollback_to_moves_after_it() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let names = ["a.pdf", "b.pdf", "c.pdf", "gone.pdf", "e.pdf"];
+        let entries: Vec<FileEntry> = names
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                fs::write(&path, name.as_bytes())?;
+                Ok(FileEntry {
+                    path,
+                    category: FileCategory::PDFs,
+                    content_category: None,
+                    size_bytes: 0,
+                })
+            })
+            .collect::<Result<_>>()?;
+        // Disappears between analysis and organization, so its move fails
+        fs::remove_file(temp_dir.path().join("gone.pdf"))?;
+
+        let summary = Organizer::new(temp_dir.path().to_path_buf(), false)
+            .with_checkpoint_every(Some(2))
+            .organize_files(&entries, &[], false)?;
+
+        let pdfs = temp_dir.path().join("PDFs");
+        // a and b were committed by the checkpoint; c was rolled back and e never moved
+        assert!(pdfs.join("a.pdf").exists());
+        assert!(pdfs.join("b.pdf").exists());
+        assert!(!pdfs.join("c.pdf").exists());
+        assert_eq!(fs::read(temp_dir.path().join("c.pdf"))?, b"c.pdf");
+        assert!(temp_dir.path().join("e.pdf").exists());
+        assert_eq!(summary.files_moved, 2);
+        assert!(summary
+            .actions_taken
+            .contains(&"Rolled back c.pdf to its original location".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_restores_moves_but_not_over_newer_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entries: Vec<FileEntry> = ["a.pdf", "b.pdf"]
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                fs::write(&path, name.as_bytes())?;
+                Ok(FileEntry {
+                    path,
+                    category: FileCategory::PDFs,
+                    content_category: None,
+                    size_bytes: 0,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let organizer = Organizer::new(temp_dir.path().to_path_buf(), false).with_undo_log(true);
+        let manifest = organizer.undo_log_path();
+
+        // A dry run writes a preview that cannot be undone
+        organizer.organize_files(&entries, &[], true)?;
+        assert!(UndoManifest::load(&manifest)?.dry_run);
+        assert!(organizer.undo().is_err());
+
+        organizer.organize_files(&entries, &[], false)?;
+        assert_eq!(UndoManifest::load(&manifest)?.moves.len(), 2);
+        // A new a.pdf appeared after the run, so the old one stays put
+        fs::write(temp_dir.path().join("a.pdf"), b"newer")?;
+        fs::remove_dir(temp_dir.path().join("PDFs")).ok();
+
+        let summary = organizer.undo()?;
+        assert_eq!(fs::read(temp_dir.path().join("b.pdf"))?, b"b.pdf");
+        assert_eq!(fs::read(temp_dir.path().join("a.pdf"))?, b"newer");
+        assert_eq!(fs::read(temp_dir.path().join("PDFs").join("a.pdf"))?, b"a.pdf");
+        assert_eq!(summary.files_moved, 1);
+        assert_eq!(summary.warnings.len(), 1);
+        let remaining = UndoManifest::load(&manifest)?.moves;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].source, temp_dir.path().join("a.pdf"));
+
+        // Once the newer file is out of the way, the rest is undone and the manifest removed
+        fs::remove_file(temp_dir.path().join("a.pdf"))?;
+        organizer.undo()?;
+        assert_eq!(fs::read(temp_dir.path().join("a.pdf"))?, b"a.pdf");
+        assert!(!manifest.exists());
+
+        Ok(())
+    }
+
     #[test]
     fn test_organize_files_skip_duplicates() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -339,20 +3376,26 @@ mod tests {
         let entry1 = FileEntry {
             path: file1.clone(),
             category: crate::types::FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
         };
         let entry2 = FileEntry {
             path: file2.clone(),
             category: crate::types::FileCategory::Documents,
+            content_category: None,
+            size_bytes: 0,
         };
 
         let dup_group = crate::types::DuplicateGroup {
             checksum_key: "test_key".to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
             files: vec![entry1.clone(), entry2.clone()],
         };
 
         let organizer = Organizer::new(temp_dir.path().to_path_buf(), false);
         fs::create_dir(temp_dir.path().join("Documents"))?;
-        let summary = organizer.organize_files(&[entry1, entry2], &[dup_group], false)?;
+        let _summary = organizer.organize_files(&[entry1, entry2], &[dup_group], false)?;
 
         // Only original should be in Documents, duplicate in Duplicates
         assert!(temp_dir.path().join("Documents").join("original.txt").exists());
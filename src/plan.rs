@@ -0,0 +1,160 @@
+use crate::types::{MoveMode, PlannedAction};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// A dry run's actions saved for later approval. Every destination is spelled out,
+// so applying the plan repeats exactly these steps without analyzing the folder again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub folder: PathBuf,
+    pub mode: MoveMode,
+    pub actions: Vec<PlannedAction>,
+    // Category folders configured outside `folder`; the only other places files may go
+    #[serde(default)]
+    pub category_roots: Vec<PathBuf>,
+}
+
+impl Plan {
+    pub fn new(folder: PathBuf, mode: MoveMode, actions: Vec<PlannedAction>) -> Self {
+        Self {
+            folder,
+            mode,
+            actions,
+            category_roots: Vec::new(),
+        }
+    }
+
+    pub fn with_category_roots(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.category_roots = roots
+            .into_iter()
+            .filter(|root| !root.starts_with(&self.folder))
+            .collect();
+        self.category_roots.sort();
+        self
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write plan {}", path.display()))
+    }
+
+    // Rejects plans that would move files from outside their folder, or into anywhere
+    // but that folder and the category folders the plan lists. Paths are compared as
+    // written, so any with `.` or `..` parts are refused rather than resolved.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read plan {}", path.display()))?;
+        let plan: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid plan {}", path.display()))?;
+        for root in std::iter::once(&plan.folder).chain(&plan.category_roots) {
+            Self::check_plain(root)?;
+        }
+        for action in &plan.actions {
+            let (source, dest) = match action {
+                PlannedAction::CreateDirectory { path } => (None, path),
+                PlannedAction::MoveFile { from, to } | PlannedAction::MoveDuplicate { from, to, .. } => {
+                    (Some(from), to)
+                }
+            };
+            if let Some(source) = source {
+                Self::check_plain(source)?;
+                if !source.starts_with(&plan.folder) {
+                    bail!(
+                        "{} is outside {}, the folder the plan was made for",
+                        source.display(),
+                        plan.folder.display()
+                    );
+                }
+            }
+            Self::check_plain(dest)?;
+            if !std::iter::once(&plan.folder)
+                .chain(&plan.category_roots)
+                .any(|root| dest.starts_with(root))
+            {
+                bail!(
+                    "{} is outside {} and its category folders",
+                    dest.display(),
+                    plan.folder.display()
+                );
+            }
+        }
+        Ok(plan)
+    }
+
+    fn check_plain(path: &Path) -> Result<()> {
+        if path
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::CurDir))
+        {
+            bail!("{} has `.` or `..` parts, which a plan may not use", path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plan_round_trip_and_outside_paths() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let plan_path = temp_dir.path().join("plan.json");
+        let root = PathBuf::from("/desk");
+        let mut plan = Plan::new(
+            root.clone(),
+            MoveMode::Move,
+            vec![
+                PlannedAction::CreateDirectory { path: root.join("PDFs") },
+                PlannedAction::MoveFile { from: root.join("a.pdf"), to: root.join("PDFs/a.pdf") },
+            ],
+        );
+        plan.save(&plan_path)?;
+        assert_eq!(Plan::load(&plan_path)?, plan);
+
+        let rejected = |action: PlannedAction| -> Result<String> {
+            let mut plan = plan.clone();
+            plan.actions.push(action);
+            plan.save(&plan_path)?;
+            Ok(Plan::load(&plan_path).unwrap_err().to_string())
+        };
+        let err = rejected(PlannedAction::MoveFile {
+            from: PathBuf::from("/elsewhere/b.pdf"),
+            to: root.join("PDFs/b.pdf"),
+        })?;
+        assert!(err.contains("/elsewhere/b.pdf is outside /desk"));
+
+        // `..` would pass a plain prefix check, so it is refused on either side
+        let err = rejected(PlannedAction::MoveFile {
+            from: PathBuf::from("/desk/../etc/passwd"),
+            to: root.join("PDFs/passwd"),
+        })?;
+        assert!(err.contains("/desk/../etc/passwd has `.` or `..` parts"));
+        let err = rejected(PlannedAction::MoveFile {
+            from: root.join("c.pdf"),
+            to: PathBuf::from("/desk/PDFs/../../etc/c.pdf"),
+        })?;
+        assert!(err.contains("has `.` or `..` parts"));
+
+        // Destinations outside the folder must be in one of the plan's category folders
+        let err = rejected(PlannedAction::MoveFile {
+            from: root.join("d.jpg"),
+            to: PathBuf::from("/elsewhere/d.jpg"),
+        })?;
+        assert!(err.contains("/elsewhere/d.jpg is outside /desk and its category folders"));
+        plan = plan.with_category_roots([PathBuf::from("/photos"), root.join("Images")]);
+        assert_eq!(plan.category_roots, vec![PathBuf::from("/photos")]);
+        plan.actions.push(PlannedAction::CreateDirectory { path: PathBuf::from("/photos") });
+        plan.actions.push(PlannedAction::MoveFile {
+            from: root.join("d.jpg"),
+            to: PathBuf::from("/photos/d.jpg"),
+        });
+        plan.save(&plan_path)?;
+        assert_eq!(Plan::load(&plan_path)?, plan);
+
+        Ok(())
+    }
+}
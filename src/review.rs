@@ -0,0 +1,267 @@
+use crate::types::{DuplicateGroup, FileEntry};
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, terminal};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Keep,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewOutcome {
+    Continue,
+    Apply,
+    Cancel,
+}
+
+// Browsing state for the duplicate review, kept apart from the terminal so key
+// handling can be exercised directly. Every group starts with its first file kept
+// and the rest marked for removal, matching what the organizer would do unreviewed.
+pub struct ReviewState {
+    groups: Vec<DuplicateGroup>,
+    decisions: Vec<Vec<Decision>>,
+    group: usize,
+    file: usize,
+}
+
+impl ReviewState {
+    pub fn new(groups: Vec<DuplicateGroup>) -> Self {
+        let decisions = groups
+            .iter()
+            .map(|g| {
+                (0..g.files.len())
+                    .map(|i| if i == 0 { Decision::Keep } else { Decision::Remove })
+                    .collect()
+            })
+            .collect();
+        Self {
+            groups,
+            decisions,
+            group: 0,
+            file: 0,
+        }
+    }
+
+    // ↑/↓ pick a file, ←/→ switch group, k/r/space mark it, Enter applies, Esc or q cancels
+    pub fn handle_key(&mut self, key: KeyCode) -> ReviewOutcome {
+        if self.groups.is_empty() {
+            return match key {
+                KeyCode::Enter => ReviewOutcome::Apply,
+                KeyCode::Esc | KeyCode::Char('q') => ReviewOutcome::Cancel,
+                _ => ReviewOutcome::Continue,
+            };
+        }
+        let file_count = self.groups[self.group].files.len();
+        match key {
+            KeyCode::Up => self.file = self.file.saturating_sub(1),
+            KeyCode::Down => self.file = (self.file + 1).min(file_count - 1),
+            KeyCode::Left => self.select_group(self.group.saturating_sub(1)),
+            KeyCode::Right | KeyCode::Tab => {
+                self.select_group((self.group + 1).min(self.groups.len() - 1))
+            }
+            KeyCode::Char('k') => self.mark(Decision::Keep),
+            KeyCode::Char('r') => self.mark(Decision::Remove),
+            KeyCode::Char(' ') => match self.decisions[self.group][self.file] {
+                Decision::Keep => self.mark(Decision::Remove),
+                Decision::Remove => self.mark(Decision::Keep),
+            },
+            KeyCode::Enter => return ReviewOutcome::Apply,
+            KeyCode::Esc | KeyCode::Char('q') => return ReviewOutcome::Cancel,
+            _ => {}
+        }
+        ReviewOutcome::Continue
+    }
+
+    fn select_group(&mut self, group: usize) {
+        if group != self.group {
+            self.group = group;
+            self.file = 0;
+        }
+    }
+
+    // A group always keeps at least one file, so the last kept file cannot be removed
+    fn mark(&mut self, decision: Decision) {
+        let decisions = &mut self.decisions[self.group];
+        let kept = decisions.iter().filter(|d| **d == Decision::Keep).count();
+        if decision == Decision::Remove && decisions[self.file] == Decision::Keep && kept == 1 {
+            return;
+        }
+        decisions[self.file] = decision;
+    }
+
+    pub fn decision(&self, group: usize, file: usize) -> Decision {
+        self.decisions[group][file]
+    }
+
+    // The groups to hand to the organizer: the first kept file is the original and the
+    // removed files are its copies. Other kept files are left out and organized as usual;
+    // groups with nothing removed are dropped.
+    pub fn decided_groups(&self) -> Vec<DuplicateGroup> {
+        self.groups
+            .iter()
+            .zip(&self.decisions)
+            .filter_map(|(group, decisions)| {
+                let pick = |wanted: Decision| {
+                    group
+                        .files
+                        .iter()
+                        .zip(decisions)
+                        .filter(move |(_, d)| **d == wanted)
+                        .map(|(f, _)| f.clone())
+                };
+                let original = pick(Decision::Keep).next()?;
+                let removed: Vec<FileEntry> = pick(Decision::Remove).collect();
+                (!removed.is_empty()).then(|| {
+                    DuplicateGroup::new(
+                        group.checksum_key.clone(),
+                        std::iter::once(original).chain(removed).collect(),
+                        group.confirmed,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![
+            "[*] Review duplicates: ↑/↓ file  ←/→ group  k keep  r remove  space toggle  Enter apply  Esc cancel".to_string(),
+            String::new(),
+        ];
+        let Some(group) = self.groups.get(self.group) else {
+            lines.push("[~] No duplicates to review.".to_string());
+            return lines;
+        };
+        lines.push(format!(
+            "[!] Group {}/{}: {} files ({})",
+            self.group + 1,
+            self.groups.len(),
+            group.files.len(),
+            if group.confirmed { "confirmed" } else { "probable" }
+        ));
+        for (i, entry) in group.files.iter().enumerate() {
+            let (size, modified) = match entry.meta() {
+                Ok(meta) => (
+                    format!("{} bytes", meta.size),
+                    meta.modified_date().to_utc_string(),
+                ),
+                Err(_) => ("unreadable".to_string(), String::new()),
+            };
+            lines.push(format!(
+                "{} [{}] {}  {}  {}",
+                if i == self.file { ">" } else { " " },
+                match self.decisions[self.group][i] {
+                    Decision::Keep => "keep  ",
+                    Decision::Remove => "remove",
+                },
+                entry.path.display(),
+                size,
+                modified
+            ));
+        }
+        lines
+    }
+
+    // Runs the review in the alternate screen; `None` means the user cancelled
+    pub fn run(mut self) -> Result<Option<Vec<DuplicateGroup>>> {
+        let mut stdout = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let result = (|| -> Result<ReviewOutcome> {
+            loop {
+                execute!(
+                    stdout,
+                    terminal::Clear(terminal::ClearType::All),
+                    cursor::MoveTo(0, 0)
+                )?;
+                for line in self.render() {
+                    write!(stdout, "{}\r\n", line)?;
+                }
+                stdout.flush()?;
+                if let Event::Key(key) = event::read()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    match self.handle_key(key.code) {
+                        ReviewOutcome::Continue => {}
+                        outcome => return Ok(outcome),
+                    }
+                }
+            }
+        })();
+
+        execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        Ok(match result? {
+            ReviewOutcome::Apply => Some(self.decided_groups()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FileCategory;
+    use std::path::PathBuf;
+
+    fn group(key: &str, names: &[&str]) -> DuplicateGroup {
+        DuplicateGroup {
+            checksum_key: key.to_string(),
+            confirmed: true,
+            space_wasted_bytes: 0,
+            files: names
+                .iter()
+                .map(|name| FileEntry {
+                    path: PathBuf::from("/tmp/desk").join(name),
+                    category: FileCategory::Images,
+                    content_category: None,
+                    size_bytes: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn names(group: &DuplicateGroup) -> Vec<String> {
+        group
+            .files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_keypresses_produce_decisions() {
+        let mut state = ReviewState::new(vec![
+            group("aaaa", &["a.jpg", "a (1).jpg", "a (2).jpg"]),
+            group("bbbb", &["b.jpg", "b (1).jpg"]),
+        ]);
+
+        // Group 1: keep the second copy as well, then swap which file is the original
+        for key in [KeyCode::Down, KeyCode::Char('k'), KeyCode::Up, KeyCode::Char('r')] {
+            assert_eq!(state.handle_key(key), ReviewOutcome::Continue);
+        }
+        assert_eq!(state.decision(0, 0), Decision::Remove);
+        assert_eq!(state.decision(0, 1), Decision::Keep);
+
+        // Group 2: keep both, which leaves nothing to remove
+        state.handle_key(KeyCode::Right);
+        state.handle_key(KeyCode::Down);
+        state.handle_key(KeyCode::Char(' '));
+        assert_eq!(state.handle_key(KeyCode::Enter), ReviewOutcome::Apply);
+
+        let decided = state.decided_groups();
+        assert_eq!(decided.len(), 1);
+        assert_eq!(names(&decided[0]), vec!["a (1).jpg", "a.jpg", "a (2).jpg"]);
+    }
+
+    #[test]
+    fn test_last_kept_file_cannot_be_removed_and_escape_cancels() {
+        let mut state = ReviewState::new(vec![group("aaaa", &["a.jpg", "a (1).jpg"])]);
+        state.handle_key(KeyCode::Char('r'));
+        assert_eq!(state.decision(0, 0), Decision::Keep);
+        assert_eq!(state.handle_key(KeyCode::Esc), ReviewOutcome::Cancel);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::metadata::FileMeta;
+use crate::types::FileCategory;
+use anyhow::{Context, Result, bail};
+use glob::Pattern;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<RawRule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    #[serde(default)]
+    when: RawConditions,
+    then: RawAction,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConditions {
+    glob: Option<String>,
+    mime: Option<String>,
+    size_gt: Option<u64>,
+    name_contains: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAction {
+    category: String,
+    subfolder: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    glob: Option<Pattern>,
+    mime: Option<String>,
+    size_gt: Option<u64>,
+    name_contains: Option<String>,
+    pub category: FileCategory,
+    pub subfolder: Option<String>,
+}
+
+impl Rule {
+    // Every condition that is present must hold; a rule with no conditions matches everything
+    fn matches(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if let Some(pattern) = &self.glob
+            && !pattern.matches(&name)
+        {
+            return false;
+        }
+        if let Some(needle) = &self.name_contains
+            && !name.to_lowercase().contains(needle.as_str())
+        {
+            return false;
+        }
+        if self.size_gt.is_none() && self.mime.is_none() {
+            return true;
+        }
+        let Ok(meta) = FileMeta::load(path) else {
+            return false;
+        };
+        if let Some(limit) = self.size_gt
+            && meta.size <= limit
+        {
+            return false;
+        }
+        if let Some(prefix) = &self.mime
+            && !meta.mime_type().is_some_and(|mime| mime.starts_with(prefix.as_str()))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+// Routing rules evaluated top to bottom; the first matching rule wins and files
+// that match no rule fall through to the usual extension-based categorization
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid rules file {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content)?;
+        let mut rules = Vec::new();
+        for (index, raw) in file.rule.into_iter().enumerate() {
+            let number = index + 1;
+            let category = FileCategory::from_name(&raw.then.category).with_context(|| {
+                format!("rule {}: unknown category '{}'", number, raw.then.category)
+            })?;
+            if let Some(subfolder) = &raw.then.subfolder {
+                let relative = Path::new(subfolder);
+                if subfolder.is_empty()
+                    || relative
+                        .components()
+                        .any(|c| !matches!(c, Component::Normal(_)))
+                {
+                    bail!("rule {}: subfolder '{}' must be a relative folder name", number, subfolder);
+                }
+            }
+            let glob = match &raw.when.glob {
+                Some(pattern) => Some(
+                    Pattern::new(pattern)
+                        .with_context(|| format!("rule {}: invalid glob '{}'", number, pattern))?,
+                ),
+                None => None,
+            };
+            rules.push(Rule {
+                glob,
+                mime: raw.when.mime,
+                size_gt: raw.when.size_gt,
+                name_contains: raw.when.name_contains.map(|n| n.to_lowercase()),
+                category,
+                subfolder: raw.then.subfolder,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn first_match(&self, path: &Path) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.matches(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const RULES: &str = r#"
+[[rule]]
+when = { name_contains = "invoice" }
+then = { category = "Documents", subfolder = "Invoices" }
+
+[[rule]]
+when = { glob = "*.mp4", size_gt = 1024 }
+then = { category = "Videos", subfolder = "Large" }
+"#;
+
+    #[test]
+    fn test_name_rule_takes_precedence() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let invoice = temp_dir.path().join("Invoice_march.mp4");
+        fs::write(&invoice, vec![0u8; 4096])?;
+
+        // Both rules match; the first one listed wins
+        let rules = RuleSet::parse(RULES)?;
+        let rule = rules.first_match(&invoice).unwrap();
+        assert_eq!(rule.category, FileCategory::Documents);
+        assert_eq!(rule.subfolder.as_deref(), Some("Invoices"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_rule_and_fall_through() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let large = temp_dir.path().join("holiday.mp4");
+        let small = temp_dir.path().join("clip.mp4");
+        fs::write(&large, vec![0u8; 4096])?;
+        fs::write(&small, b"tiny")?;
+
+        let rules = RuleSet::parse(RULES)?;
+        let rule = rules.first_match(&large).unwrap();
+        assert_eq!(rule.category, FileCategory::Videos);
+        assert_eq!(rule.subfolder.as_deref(), Some("Large"));
+        assert!(rules.first_match(&small).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        let unknown_category = "[[rule]]\nthen = { category = \"Misc\" }\n";
+        assert!(RuleSet::parse(unknown_category).is_err());
+        let escaping = "[[rule]]\nthen = { category = \"Images\", subfolder = \"../out\" }\n";
+        assert!(RuleSet::parse(escaping).is_err());
+        let unknown_condition = "[[rule]]\nwhen = { size_lt = 5 }\nthen = { category = \"Images\" }\n";
+        assert!(RuleSet::parse(unknown_condition).is_err());
+    }
+}
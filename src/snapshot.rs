@@ -0,0 +1,91 @@
+use crate::config::FOLDER_CONFIG_FILE_NAME;
+use crate::duplicate_finder::DuplicateFinder;
+use crate::state::STATE_FILE_NAME;
+use crate::undo::UNDO_LOG_FILE_NAME;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SNAPSHOT_BEFORE_FILE_NAME: &str = "before.json";
+pub const SNAPSHOT_AFTER_FILE_NAME: &str = "after.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    // Relative to the snapshot root
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+// Every file under a folder with its size and content hash, sorted by path so two
+// snapshots can be compared line by line. desktidy's own bookkeeping files at the
+// root are left out, as a run rewrites them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub files: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    pub fn capture(root: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        Self::walk(root, root, &mut files)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self { files })
+    }
+
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<SnapshotEntry>) -> Result<()> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?;
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let is_symlink = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+            if path.is_dir() {
+                // Linked folders are recorded where they live, not followed
+                if !is_symlink {
+                    Self::walk(root, &path, files)?;
+                }
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            if dir == root && Self::is_bookkeeping(&relative) {
+                continue;
+            }
+            // A dangling link has nothing to hash
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let (_, sha256) = DuplicateFinder::read_checksums(&path, None)
+                .with_context(|| format!("Failed to hash {}", path.display()))?;
+            files.push(SnapshotEntry {
+                path: relative,
+                size: metadata.len(),
+                sha256,
+            });
+        }
+        Ok(())
+    }
+
+    fn is_bookkeeping(name: &Path) -> bool {
+        [
+            SNAPSHOT_BEFORE_FILE_NAME,
+            SNAPSHOT_AFTER_FILE_NAME,
+            STATE_FILE_NAME,
+            UNDO_LOG_FILE_NAME,
+            FOLDER_CONFIG_FILE_NAME,
+        ]
+        .iter()
+        .any(|bookkeeping| name == Path::new(bookkeeping))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write snapshot {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid snapshot {}", path.display()))
+    }
+}
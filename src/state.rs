@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const STATE_FILE_NAME: &str = ".desktidy-state.json";
+
+// Per-folder bookkeeping kept between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunState {
+    last_run_unix_nanos: Option<u64>,
+}
+
+impl RunState {
+    pub fn path_for(folder_path: &Path) -> PathBuf {
+        folder_path.join(STATE_FILE_NAME)
+    }
+
+    // A missing state file means the folder has never been organized
+    pub fn load(folder_path: &Path) -> Result<Self> {
+        let path = Self::path_for(folder_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid state file {}", path.display()))
+    }
+
+    pub fn save(&self, folder_path: &Path) -> Result<()> {
+        fs::write(Self::path_for(folder_path), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn last_run(&self) -> Option<SystemTime> {
+        self.last_run_unix_nanos
+            .map(|nanos| UNIX_EPOCH + Duration::from_nanos(nanos))
+    }
+
+    pub fn set_last_run(&mut self, time: SystemTime) {
+        self.last_run_unix_nanos = time
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_nanos() as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_state_round_trip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        assert!(RunState::load(temp_dir.path())?.last_run().is_none());
+
+        let now = UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789);
+        let mut state = RunState::default();
+        state.set_last_run(now);
+        state.save(temp_dir.path())?;
+
+        assert_eq!(RunState::load(temp_dir.path())?.last_run(), Some(now));
+        Ok(())
+    }
+}
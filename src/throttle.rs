@@ -0,0 +1,77 @@
+use std::io::{self, Read, Write};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BYTES_PER_MEGABYTE: f64 = 1_000_000.0;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+// Token-bucket rate limiter shared by every reader and copier in a run. The bucket
+// holds a tenth of a second of bytes, so short bursts are allowed but the
+// sustained rate stays at the limit; callers that overdraw sleep off the debt.
+pub struct Throttle {
+    bytes_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl Throttle {
+    pub fn new(megabytes_per_sec: f64) -> Self {
+        let bytes_per_sec = megabytes_per_sec * BYTES_PER_MEGABYTE;
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                tokens: bytes_per_sec / 10.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn consume(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last_refill).as_secs_f64() * self.bytes_per_sec;
+            bucket.tokens = (bucket.tokens + refill).min(self.bytes_per_sec / 10.0);
+            bucket.last_refill = now;
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+
+    // Calls `f` with each chunk of `reader`, waiting for tokens before handing it over
+    pub fn read_chunks(
+        throttle: Option<&Throttle>,
+        reader: &mut impl Read,
+        mut f: impl FnMut(&[u8]) -> io::Result<()>,
+    ) -> io::Result<u64> {
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let mut total = 0;
+        loop {
+            let bytes_read = reader.read(&mut buffer)?;
+            if bytes_read == 0 {
+                return Ok(total);
+            }
+            if let Some(throttle) = throttle {
+                throttle.consume(bytes_read);
+            }
+            f(&buffer[..bytes_read])?;
+            total += bytes_read as u64;
+        }
+    }
+
+    pub fn copy(throttle: Option<&Throttle>, source: &mut impl Read, dest: &mut impl Write) -> io::Result<u64> {
+        Self::read_chunks(throttle, source, |chunk| dest.write_all(chunk))
+    }
+}
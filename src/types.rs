@@ -1,4 +1,8 @@
+use crate::metadata::FileMeta;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -10,10 +14,21 @@ pub enum FileCategory {
     Images,
     Videos,
     Audio,
+    Code,
+    Archives,
+    // A user-defined category from a categories config
+    Custom(String),
+}
+
+// Written by name, so a category can also key a JSON object
+impl Serialize for FileCategory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 impl FileCategory {
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             FileCategory::Documents => "Documents",
             FileCategory::PDFs => "PDFs",
@@ -22,9 +37,18 @@ impl FileCategory {
             FileCategory::Images => "Images",
             FileCategory::Videos => "Videos",
             FileCategory::Audio => "Audio",
+            FileCategory::Code => "Code",
+            FileCategory::Archives => "Archives",
+            FileCategory::Custom(name) => name,
         }
     }
 
+    pub fn from_name(name: &str) -> Option<FileCategory> {
+        Self::order()
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(name))
+    }
+
     pub fn color_code(&self) -> &'static str {
         match self {
             FileCategory::Documents => "blue",
@@ -34,6 +58,9 @@ impl FileCategory {
             FileCategory::Images => "cyan",
             FileCategory::Videos => "yellow",
             FileCategory::Audio => "red",
+            FileCategory::Code => "green",
+            FileCategory::Archives => "magenta",
+            FileCategory::Custom(_) => "white",
         }
     }
 
@@ -46,29 +73,172 @@ impl FileCategory {
             FileCategory::Images,
             FileCategory::Videos,
             FileCategory::Audio,
+            FileCategory::Code,
+            FileCategory::Archives,
         ]
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ClassifyBy {
+    #[default]
+    Extension,
+    Mime,
+}
+
+// Which file of a duplicate group is kept as the original; the rest go to Duplicates.
+// Ties, and `FirstFound`, fall back to the order the files were scanned in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum KeepStrategy {
+    #[default]
+    Oldest,
+    Newest,
+    Largest,
+    Smallest,
+    FirstFound,
+}
+
+// What to do when a file's destination name is already taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ConflictStrategy {
+    // Add a collision suffix such as ` (1)`
+    #[default]
+    Rename,
+    // Leave the file where it is
+    Skip,
+    // Replace the file already at the destination
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct FileEntry {
     pub path: PathBuf,
     pub category: FileCategory,
+    // What the file's leading bytes say it is, when that disagrees with its extension
+    pub content_category: Option<FileCategory>,
+    // Read once during analysis; 0 when the size could not be read
+    pub size_bytes: u64,
+}
+
+impl FileEntry {
+    pub fn meta(&self) -> anyhow::Result<FileMeta> {
+        FileMeta::load(&self.path)
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DuplicateGroup {
     pub checksum_key: String,
     pub files: Vec<FileEntry>,
+    // False when the group comes from a custom keyer and the contents were not compared
+    pub confirmed: bool,
+    // Sizes of every file but the kept original, the space removing the copies frees
+    pub space_wasted_bytes: u64,
+}
+
+impl DuplicateGroup {
+    pub fn new(checksum_key: String, files: Vec<FileEntry>, confirmed: bool) -> Self {
+        let space_wasted_bytes = files.iter().skip(1).map(|e| e.size_bytes).sum();
+        Self {
+            checksum_key,
+            files,
+            confirmed,
+            space_wasted_bytes,
+        }
+    }
+
+    pub fn total_wasted_bytes(groups: &[DuplicateGroup]) -> u64 {
+        groups.iter().map(|g| g.space_wasted_bytes).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    Unreadable(PathBuf),
+    Skipped(PathBuf, String),
+    MoveFailed(PathBuf, String),
+    // A file whose contents disagree with its extension, organized by the detected type
+    Reclassified(PathBuf, FileCategory, String),
 }
 
+// Written as the message shown in the warnings report
+impl Serialize for Warning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Unreadable(path) => write!(f, "Could not read {}", path.display()),
+            Warning::Skipped(path, reason) => write!(f, "Skipped {} ({})", path.display(), reason),
+            Warning::MoveFailed(path, reason) => {
+                write!(f, "Failed to move {}: {}", path.display(), reason)
+            }
+            Warning::Reclassified(path, category, mime) => write!(
+                f,
+                "Moved {} to {} — detected as {}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                category.as_str(),
+                mime
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct AnalysisResult {
     pub total_files: usize,
     pub supported_files: usize,
+    // Scanned files of a type desktidy does not recognize
+    pub unsupported_files: usize,
     pub categories: HashMap<FileCategory, Vec<FileEntry>>,
+    // Sizes of the files in `categories`
+    pub total_bytes: u64,
+    pub bytes_by_category: HashMap<FileCategory, u64>,
+    // Supported hidden files left out of `categories`, kept for deduplication
+    pub hidden: Vec<FileEntry>,
+    // Files already in the category folders, gathered only to deduplicate the
+    // Duplicates folder against
+    pub organized: Vec<FileEntry>,
+    pub warnings: Vec<Warning>,
+    // Extension to category mappings learned from file contents during this run
+    pub learned_extensions: HashMap<String, FileCategory>,
+}
+
+// Whether organizing moves files or builds an organized copy, leaving the originals
+// where they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveMode {
+    #[default]
+    Move,
+    Copy,
+}
+
+// One step of a dry run, for callers that want more than the action descriptions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlannedAction {
+    CreateDirectory { path: PathBuf },
+    MoveFile { from: PathBuf, to: PathBuf },
+    MoveDuplicate { from: PathBuf, to: PathBuf, original: PathBuf },
 }
 
+#[derive(Serialize)]
 pub struct OrganizationSummary {
     pub actions_taken: Vec<String>,
     pub duplicates_found: Vec<DuplicateGroup>,
+    pub warnings: Vec<Warning>,
+    pub files_moved: usize,
+    // Files copied with `MoveMode::Copy`; these are not counted in `files_moved`
+    pub files_copied: usize,
+    pub bytes_moved: u64,
+    // Original paths of the files this run moved, linked or archived
+    pub changed: Vec<PathBuf>,
+    // Files left in place because their destination name was taken
+    pub skipped_files: Vec<PathBuf>,
+    // What a dry run would do, in order; empty after a real run
+    pub planned_actions: Vec<PlannedAction>,
 }
@@ -0,0 +1,212 @@
+use crate::filesystem::{FileSystem, OsFileSystem};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const UNDO_LOG_FILE_NAME: &str = ".desktidy-undo.json";
+pub const UNDO_LOG_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    // Made with --copy: undoing removes the copy, as the source was never moved
+    #[serde(default)]
+    pub copied: bool,
+    // The duplicate was replaced by a symlink to its original at `destination`: undoing
+    // writes a copy of the original back to `source` and removes the link
+    #[serde(default)]
+    pub linked: bool,
+}
+
+// The moves desktidy made, oldest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UndoManifest {
+    pub version: u32,
+    // Written by a dry run: a preview of the moves, which cannot be undone
+    #[serde(default)]
+    pub dry_run: bool,
+    pub moves: Vec<UndoEntry>,
+}
+
+impl UndoManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_from(&OsFileSystem, path)
+    }
+
+    pub fn load_from(file_system: &dyn FileSystem, path: &Path) -> Result<Self> {
+        let mut content = String::new();
+        file_system
+            .open(path)
+            .and_then(|mut reader| reader.read_to_string(&mut content))
+            .with_context(|| format!("Failed to read undo manifest {}", path.display()))?;
+        let manifest: UndoManifest = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid undo manifest {}", path.display()))?;
+        if manifest.version != UNDO_LOG_VERSION {
+            bail!(
+                "undo manifest {} has version {}, expected {}",
+                path.display(),
+                manifest.version,
+                UNDO_LOG_VERSION
+            );
+        }
+        Ok(manifest)
+    }
+}
+
+// Appends to the manifest at `path`. Moves from earlier runs are kept so they can all
+// be undone, except those whose file has since gone and a leftover dry-run preview,
+// which is replaced by real moves. Changes are buffered and written by `save`, which
+// the organizer calls at each checkpoint and at the end of a run; a log dropped with
+// unsaved changes (say, when a run fails part way) saves them then.
+pub struct UndoLog {
+    file_system: Arc<dyn FileSystem>,
+    path: PathBuf,
+    manifest: UndoManifest,
+    dirty: bool,
+}
+
+impl UndoLog {
+    pub fn open(path: &Path, dry_run: bool) -> Result<Self> {
+        Self::open_in(Arc::new(OsFileSystem), path, dry_run)
+    }
+
+    pub fn open_in(file_system: Arc<dyn FileSystem>, path: &Path, dry_run: bool) -> Result<Self> {
+        let existing = match file_system.exists(path) {
+            true => Some(UndoManifest::load_from(file_system.as_ref(), path)?),
+            false => None,
+        };
+        let moves = match existing {
+            Some(manifest) if !manifest.dry_run && !dry_run => manifest
+                .moves
+                .into_iter()
+                .filter(|m| file_system.metadata(&m.destination).is_ok())
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            file_system,
+            path: path.to_path_buf(),
+            manifest: UndoManifest {
+                version: UNDO_LOG_VERSION,
+                dry_run,
+                moves,
+            },
+            dirty: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, source: &Path, destination: &Path, copied: bool) {
+        self.manifest.moves.push(UndoEntry {
+            source: source.to_path_buf(),
+            destination: destination.to_path_buf(),
+            copied,
+            linked: false,
+        });
+        self.dirty = true;
+    }
+
+    // A duplicate at `source` replaced by the symlink at `link`
+    pub fn record_link(&mut self, source: &Path, link: &Path, copied: bool) {
+        self.manifest.moves.push(UndoEntry {
+            source: source.to_path_buf(),
+            destination: link.to_path_buf(),
+            copied,
+            linked: true,
+        });
+        self.dirty = true;
+    }
+
+    // Drops the entry for a move that failed or was rolled back
+    pub fn forget(&mut self, source: &Path) {
+        if let Some(index) = self.manifest.moves.iter().rposition(|m| m.source == source) {
+            self.manifest.moves.remove(index);
+            self.dirty = true;
+        }
+    }
+
+    pub fn replace(&mut self, moves: Vec<UndoEntry>) {
+        self.manifest.moves = moves;
+        self.dirty = true;
+    }
+
+    // Writes any buffered changes. A dry run never replaces a manifest of real moves.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        if self.manifest.dry_run
+            && UndoManifest::load_from(self.file_system.as_ref(), &self.path)
+                .is_ok_and(|existing| !existing.dry_run)
+        {
+            return Ok(());
+        }
+        let temp = self.path.with_extension("json.tmp");
+        self.file_system
+            .write(&temp, serde_json::to_string(&self.manifest)?.as_bytes())?;
+        self.file_system.rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for UndoLog {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_log_appends_across_runs_and_rejects_other_versions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join(UNDO_LOG_FILE_NAME);
+        let dest = |name: &str| -> Result<PathBuf> {
+            let dest = temp_dir.path().join(name);
+            fs::write(&dest, name)?;
+            Ok(dest)
+        };
+
+        let mut first = UndoLog::open(&path, false)?;
+        first.record(Path::new("/d/a.pdf"), &dest("a.pdf")?, false);
+        first.record(Path::new("/d/gone.pdf"), Path::new("/d/PDFs/gone.pdf"), false);
+        // Nothing is written until the log is saved
+        assert!(!path.exists());
+        first.save()?;
+        assert_eq!(UndoManifest::load(&path)?.moves.len(), 2);
+
+        // Moves whose file has gone are dropped when the log is reopened
+        let mut second = UndoLog::open(&path, false)?;
+        second.record(Path::new("/d/b.jpg"), &dest("b.jpg")?, false);
+        second.record(Path::new("/d/c.jpg"), &dest("c.jpg")?, false);
+        second.forget(Path::new("/d/c.jpg"));
+        drop(second);
+
+        let manifest = UndoManifest::load(&path)?;
+        assert_eq!(manifest.version, UNDO_LOG_VERSION);
+        assert_eq!(manifest.moves.len(), 2);
+        assert_eq!(manifest.moves[1].source, Path::new("/d/b.jpg"));
+
+        // A preview leaves the real manifest alone
+        let mut preview = UndoLog::open(&path, true)?;
+        preview.record(Path::new("/d/e.mp3"), &dest("e.mp3")?, false);
+        preview.save()?;
+        assert_eq!(UndoManifest::load(&path)?.moves.len(), 2);
+
+        fs::write(&path, r#"{"version": 99, "moves": []}"#)?;
+        assert!(UndoManifest::load(&path).is_err());
+
+        Ok(())
+    }
+}
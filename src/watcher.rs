@@ -0,0 +1,228 @@
+use crate::config::FOLDER_CONFIG_FILE_NAME;
+use crate::organizer::DUPLICATES_LOG_FILE_NAME;
+use crate::snapshot::{SNAPSHOT_AFTER_FILE_NAME, SNAPSHOT_BEFORE_FILE_NAME};
+use crate::state::STATE_FILE_NAME;
+use crate::undo::UNDO_LOG_FILE_NAME;
+use anyhow::{Context, Result};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+// Files still being written keep producing events, so a batch is only handed on
+// once the folder has been quiet for this long
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// How often the loop wakes to check for Ctrl-C and settled batches
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Collects the paths seen since the last batch and releases them once no event has
+// arrived for the quiet period
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    pending: BTreeSet<PathBuf>,
+    last_event: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            pending: BTreeSet::new(),
+            last_event: None,
+        }
+    }
+
+    pub fn record(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path);
+        self.last_event = Some(now);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn ready(&self, now: Instant) -> bool {
+        self.last_event
+            .is_some_and(|last| now.saturating_duration_since(last) >= self.quiet)
+    }
+
+    pub fn take(&mut self) -> Vec<PathBuf> {
+        self.last_event = None;
+        std::mem::take(&mut self.pending).into_iter().collect()
+    }
+}
+
+// Keeps a folder organized: subscribes to its file system events and calls back
+// with the new files once they have settled, until Ctrl-C
+pub struct FolderWatcher {
+    folder_path: PathBuf,
+    // The folder as events name it: absolute, and resolved on platforms that do
+    roots: Vec<PathBuf>,
+    verbose: bool,
+    recursive: bool,
+}
+
+impl FolderWatcher {
+    pub fn new(folder_path: PathBuf, verbose: bool) -> Self {
+        let roots = [std::path::absolute(&folder_path), folder_path.canonicalize()]
+            .into_iter()
+            .filter_map(|root| root.ok())
+            .collect();
+        Self {
+            folder_path,
+            roots,
+            verbose,
+            recursive: false,
+        }
+    }
+
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    // Blocks until Ctrl-C, calling `run` with each settled batch of new files. A
+    // failed run is reported and watching carries on; a batch still pending when
+    // Ctrl-C arrives is run before returning.
+    pub fn watch(&self, mut run: impl FnMut(&[PathBuf]) -> Result<()>) -> Result<()> {
+        let stop = Arc::new(AtomicBool::new(false));
+        for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&stop))
+                .context("Failed to install the Ctrl-C handler")?;
+        }
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to start the file watcher")?;
+        let mode = match self.recursive {
+            true => RecursiveMode::Recursive,
+            false => RecursiveMode::NonRecursive,
+        };
+        watcher
+            .watch(&self.folder_path, mode)
+            .with_context(|| format!("Failed to watch {}", self.folder_path.display()))?;
+        println!(
+            "[*] Watching {} for new files (Ctrl-C to stop)",
+            self.folder_path.display()
+        );
+
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        while !stop.load(Ordering::Relaxed) {
+            match events.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) if Self::is_addition(&event.kind) => {
+                    for path in event.paths.into_iter().filter(|p| self.is_candidate(p)) {
+                        debouncer.record(path, Instant::now());
+                    }
+                }
+                Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Ok(Err(err)) => println!("[!] Watch error: {}", err),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if debouncer.ready(Instant::now()) {
+                self.run_batch(debouncer.take(), &mut run);
+            }
+        }
+
+        if !debouncer.is_empty() {
+            self.run_batch(debouncer.take(), &mut run);
+        }
+        println!("[*] Stopped watching {}", self.folder_path.display());
+        Ok(())
+    }
+
+    fn run_batch(&self, paths: Vec<PathBuf>, run: &mut impl FnMut(&[PathBuf]) -> Result<()>) {
+        // Files that were renamed away or deleted before settling need no run
+        let paths: Vec<_> = paths.into_iter().filter(|p| p.is_file()).collect();
+        if paths.is_empty() {
+            return;
+        }
+        if self.verbose {
+            for path in &paths {
+                println!("[*] New file: {}", path.display());
+            }
+        }
+        if let Err(err) = run(&paths) {
+            println!("[!] {:#}", err);
+        }
+    }
+
+    // Creations, writes and renames into the folder; removals and reads are ignored
+    fn is_addition(kind: &EventKind) -> bool {
+        matches!(
+            kind,
+            EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+        )
+    }
+
+    // Hidden files, desktidy's own bookkeeping and, unless recursive, anything below
+    // the root (where the organizer puts files) would only trigger needless runs
+    fn is_candidate(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let at_root = path
+            .parent()
+            .is_some_and(|parent| self.roots.iter().any(|root| root == parent));
+        if !at_root && !self.recursive {
+            return false;
+        }
+        let bookkeeping = [
+            STATE_FILE_NAME,
+            UNDO_LOG_FILE_NAME,
+            FOLDER_CONFIG_FILE_NAME,
+            SNAPSHOT_BEFORE_FILE_NAME,
+            SNAPSHOT_AFTER_FILE_NAME,
+            DUPLICATES_LOG_FILE_NAME,
+        ];
+        let is_bookkeeping = at_root && bookkeeping.iter().any(|file| name == *file);
+        !name.to_string_lossy().starts_with('.') && !is_bookkeeping
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(DEBOUNCE);
+        assert!(!debouncer.ready(start));
+
+        debouncer.record(PathBuf::from("/desk/a.pdf"), start);
+        assert!(!debouncer.ready(start + Duration::from_millis(300)));
+
+        // A write in progress pushes the batch back
+        debouncer.record(PathBuf::from("/desk/a.pdf"), start + Duration::from_millis(400));
+        debouncer.record(PathBuf::from("/desk/b.jpg"), start + Duration::from_millis(450));
+        assert!(!debouncer.ready(start + Duration::from_millis(800)));
+        assert!(debouncer.ready(start + Duration::from_millis(950)));
+
+        assert_eq!(
+            debouncer.take(),
+            vec![PathBuf::from("/desk/a.pdf"), PathBuf::from("/desk/b.jpg")]
+        );
+        assert!(debouncer.is_empty());
+        assert!(!debouncer.ready(start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_watcher_ignores_organized_and_bookkeeping_files() {
+        let watcher = FolderWatcher::new(PathBuf::from("/desk"), false);
+        assert!(watcher.is_candidate(Path::new("/desk/report.pdf")));
+        assert!(!watcher.is_candidate(Path::new("/desk/PDFs/report.pdf")));
+        assert!(!watcher.is_candidate(Path::new("/desk/.hidden.txt")));
+        assert!(!watcher.is_candidate(&Path::new("/desk").join(UNDO_LOG_FILE_NAME)));
+        assert!(!watcher.is_candidate(&Path::new("/desk").join(DUPLICATES_LOG_FILE_NAME)));
+
+        let recursive = FolderWatcher::new(PathBuf::from("/desk"), false).with_recursive(true);
+        assert!(recursive.is_candidate(Path::new("/desk/inbox/report.pdf")));
+    }
+}
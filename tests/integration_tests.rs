@@ -1,5 +1,6 @@
 use desktidy::{
     file_analyzer::FileAnalyzer, duplicate_finder::DuplicateFinder, organizer::Organizer,
+    plan::Plan, types::PlannedAction,
 };
 use std::fs::{self, File};
 use std::io::Write;
@@ -265,7 +266,7 @@ fn test_filename_conflict_handling() -> anyhow::Result<()> {
 
     // Organize
     let organizer = Organizer::new(temp_path.to_path_buf(), false);
-    let summary = organizer.organize_files(&all_entries, &[], false)?;
+    let _summary = organizer.organize_files(&all_entries, &[], false)?;
 
     // Verify conflict was handled
     assert!(temp_path.join("Documents").join("doc.docx").exists());
@@ -362,3 +363,816 @@ fn test_all_supported_file_types() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(unix)]
+#[test]
+fn test_symlinked_scan_folder_resolution() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let real_dir = temp_dir.path().join("real");
+    fs::create_dir(&real_dir)?;
+    File::create(real_dir.join("doc.docx"))?;
+    fs::create_dir(real_dir.join("Duplicates"))?;
+    let link_dir = temp_dir.path().join("link");
+    std::os::unix::fs::symlink(&real_dir, &link_dir)?;
+
+    // Default: the symlink is resolved to the real folder
+    let analysis = FileAnalyzer::new(link_dir.clone(), false).analyze()?;
+    let entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+    assert_eq!(analysis.total_files, 1);
+    assert!(entries[0].path.starts_with(real_dir.canonicalize()?));
+
+    // --no-resolve-root: paths stay under the link
+    let analysis = FileAnalyzer::new(link_dir.clone(), false)
+        .with_resolve_root(false)
+        .analyze()?;
+    let entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+    assert_eq!(analysis.total_files, 1);
+    assert!(entries[0].path.starts_with(&link_dir));
+
+    let organizer = Organizer::new(link_dir.clone(), false).with_resolve_root(false);
+    organizer.organize_files(&entries, &[], false)?;
+    assert!(link_dir.join("Documents").join("doc.docx").exists());
+    assert!(real_dir.join("Documents").join("doc.docx").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_hashes_removes_matching_file() -> anyhow::Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+
+    fs::write(temp_path.join("tracking.png"), b"junk pixel")?;
+    fs::write(temp_path.join("photo.jpg"), b"real photo")?;
+    fs::write(temp_path.join("notes.docx"), b"real notes")?;
+
+    let junk_hash = format!("{:x}", Sha256::digest(b"junk pixel"));
+    let hash_file = temp_path.join("denylist.txt");
+    fs::write(&hash_file, format!("# known junk\n{}\n", junk_hash.to_uppercase()))?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false).analyze()?;
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+
+    let denylist = DuplicateFinder::load_hash_list(&hash_file)?;
+    let denylisted = DuplicateFinder::new(false).find_denylisted(&all_entries, &denylist)?;
+    assert_eq!(denylisted.len(), 1);
+
+    let organizer = Organizer::new(temp_path.to_path_buf(), false);
+    let actions = organizer.delete_files(&denylisted, false)?;
+
+    assert_eq!(actions.len(), 1);
+    assert!(!temp_path.join("tracking.png").exists());
+    assert!(temp_path.join("photo.jpg").exists());
+    assert!(temp_path.join("notes.docx").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_ignore_extension_leaves_files_in_place() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+
+    File::create(temp_path.join("manual.pdf"))?;
+    File::create(temp_path.join("letter.docx"))?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false).analyze()?;
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+    assert_eq!(analysis.supported_files, 2);
+
+    let organizer = Organizer::new(temp_path.to_path_buf(), false)
+        .with_ignored_extensions(&["PDF".to_string()]);
+    let summary = organizer.organize_files(&all_entries, &[], false)?;
+
+    assert!(temp_path.join("manual.pdf").exists());
+    assert!(!temp_path.join("PDFs").exists());
+    assert!(temp_path.join("Documents").join("letter.docx").exists());
+    assert_eq!(summary.actions_taken.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_dedup_include_hidden_without_categorizing() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+
+    fs::write(temp_path.join(".thumb_a.jpg"), b"cached thumbnail")?;
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    fs::write(temp_path.join(".thumb_b.jpg"), b"cached thumbnail")?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false).analyze()?;
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+    assert!(all_entries.is_empty());
+
+    // Hidden files are only fed to the dedup phase
+    let mut dedup_entries = all_entries.clone();
+    dedup_entries.extend(analysis.hidden.iter().cloned());
+    let duplicates = DuplicateFinder::new(false).find_duplicates(&dedup_entries)?;
+    assert_eq!(duplicates.len(), 1);
+
+    let organizer = Organizer::new(temp_path.to_path_buf(), false);
+    organizer.organize_files(&all_entries, &duplicates, false)?;
+
+    assert!(temp_path.join("Duplicates").join(".thumb_b.jpg").exists());
+    assert!(temp_path.join(".thumb_a.jpg").exists());
+    assert!(!temp_path.join("Images").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_rules_file_routes_before_extension() -> anyhow::Result<()> {
+    use desktidy::rules::RuleSet;
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("invoice-042.pdf"), b"invoice")?;
+    fs::write(temp_path.join("manual.pdf"), b"manual")?;
+
+    let rules = RuleSet::parse(
+        "[[rule]]\nwhen = { name_contains = \"invoice\" }\nthen = { category = \"Documents\", subfolder = \"Invoices\" }\n",
+    )?;
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false)
+        .with_rules(rules.clone())
+        .analyze()?;
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+
+    let organizer = Organizer::new(temp_path.to_path_buf(), false).with_rules(rules);
+    organizer.organize_files(&all_entries, &[], false)?;
+
+    assert!(temp_path.join("Documents").join("Invoices").join("invoice-042.pdf").exists());
+    assert!(temp_path.join("PDFs").join("manual.pdf").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_porcelain_writes_json_summary_to_stderr() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("report.pdf"), b"report")?;
+    fs::write(temp_path.join("copy.pdf"), b"report")?;
+    fs::write(temp_path.join("notes.xyz"), b"notes")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--porcelain")
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr)?;
+    let summary: serde_json::Value = serde_json::from_str(stderr.lines().last().unwrap())?;
+    assert_eq!(summary["moved"], 2);
+    assert_eq!(summary["skipped"], 1);
+    assert_eq!(summary["duplicates"], 1);
+    assert_eq!(summary["bytes_moved"], 12);
+    assert_eq!(summary["errors"], 0);
+    assert!(!String::from_utf8(output.stdout)?.trim_start().starts_with('{'));
+
+    Ok(())
+}
+
+#[test]
+fn test_categories_config_replaces_builtins() -> anyhow::Result<()> {
+    use desktidy::categories::CategorySet;
+    use desktidy::FileCategory;
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    File::create(temp_path.join("budget.xlsx"))?;
+    File::create(temp_path.join("holiday.jpg"))?;
+    File::create(temp_path.join("manual.pdf"))?;
+
+    let categories = CategorySet::parse(
+        "[[category]]\nname = \"Work\"\nextensions = [\"xlsx\"]\n\n[[category]]\nname = \"Personal\"\nextensions = [\"jpg\"]\n",
+    )?;
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false)
+        .with_categories(categories.clone())
+        .analyze()?;
+
+    let mut found: Vec<_> = analysis.categories.keys().map(|c| c.as_str().to_string()).collect();
+    found.sort();
+    assert_eq!(found, vec!["Personal", "Work"]);
+    assert_eq!(categories.order().len(), 2);
+    assert!(!analysis.categories.contains_key(&FileCategory::PDFs));
+
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+    let organizer = Organizer::new(temp_path.to_path_buf(), false).with_categories(categories);
+    organizer.create_category_folders(&["Work", "Personal"])?;
+    organizer.organize_files(&all_entries, &[], false)?;
+
+    assert!(temp_path.join("Work").join("budget.xlsx").exists());
+    assert!(temp_path.join("Personal").join("holiday.jpg").exists());
+    assert!(temp_path.join("manual.pdf").exists());
+    assert!(!temp_path.join("PDFs").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_folder_config_adds_books_category() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(
+        temp_path.join("desktidy.toml"),
+        "[categories]\nBooks = [\"epub\"]\nImages = [\"psd\"]\n",
+    )?;
+    fs::write(temp_path.join("novel.epub"), b"novel")?;
+    fs::write(temp_path.join("poster.psd"), b"poster")?;
+    fs::write(temp_path.join("notes.pdf"), b"notes")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("-q")
+        .arg(temp_path)
+        .env("XDG_CONFIG_HOME", temp_path.join("no-user-config"))
+        .output()?;
+    assert!(output.status.success());
+
+    assert!(temp_path.join("Books").join("novel.epub").exists());
+    assert!(temp_path.join("Images").join("poster.psd").exists());
+    assert!(temp_path.join("PDFs").join("notes.pdf").exists());
+    assert!(temp_path.join("desktidy.toml").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_snapshots_differ_by_moved_files() -> anyhow::Result<()> {
+    use desktidy::snapshot::Snapshot;
+    use std::path::{Path, PathBuf};
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("photo.jpg"), b"photo")?;
+    fs::write(temp_path.join("notes.txt"), b"notes")?;
+    fs::write(temp_path.join("random.xyz"), b"unsupported")?;
+    fs::create_dir(temp_path.join("PDFs"))?;
+    fs::write(temp_path.join("PDFs").join("old.pdf"), b"already organized")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .args(["--snapshot", "-q"])
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let before = Snapshot::load(&temp_path.join("before.json"))?;
+    let after = Snapshot::load(&temp_path.join("after.json"))?;
+    let removed: Vec<_> = before.files.iter().filter(|f| !after.files.contains(f)).collect();
+    let added: Vec<_> = after.files.iter().filter(|f| !before.files.contains(f)).collect();
+    let names = |files: &[&desktidy::snapshot::SnapshotEntry]| {
+        files.iter().map(|f| f.path.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(names(&removed), vec![PathBuf::from("notes.txt"), PathBuf::from("photo.jpg")]);
+    assert_eq!(
+        names(&added),
+        vec![Path::new("Documents").join("notes.txt"), Path::new("Images").join("photo.jpg")]
+    );
+    // Same contents, new places
+    assert_eq!(removed[1].sha256, added[1].sha256);
+    assert_eq!(before.files.len(), after.files.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_since_last_run_only_processes_new_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("first.pdf"), b"first")?;
+
+    let run = || {
+        std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+            .args(["--since-last-run", "-q"])
+            .arg(temp_path)
+            .output()
+    };
+    assert!(run()?.status.success());
+    assert!(temp_path.join("PDFs").join("first.pdf").exists());
+    assert!(temp_path.join(".desktidy-state.json").exists());
+
+    // A file last modified long before the previous run is left alone
+    let stale = File::create(temp_path.join("stale.jpg"))?;
+    stale.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000))?;
+    drop(stale);
+    // File times come from a coarse clock that can trail the recorded run time by a tick
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    fs::write(temp_path.join("new.pdf"), b"new")?;
+
+    let output = run()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains("Organized 1 files"));
+    assert!(temp_path.join("PDFs").join("new.pdf").exists());
+    assert!(temp_path.join("stale.jpg").exists());
+    assert!(!temp_path.join("Images").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_apply_only_images_subset() -> anyhow::Result<()> {
+    use desktidy::categories::CategorySet;
+    use desktidy::organizer::PlanFilter;
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    File::create(temp_path.join("beach.jpg"))?;
+    File::create(temp_path.join("sunset.png"))?;
+    File::create(temp_path.join("report.pdf"))?;
+    File::create(temp_path.join("song.mp3"))?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false).analyze()?;
+    let all_entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+
+    // The full plan covers every file
+    let plan = Organizer::new(temp_path.to_path_buf(), false).organize_files(&all_entries, &[], true)?;
+    assert_eq!(plan.actions_taken.len(), 4);
+
+    let filter = PlanFilter::parse("images", &CategorySet::default())?;
+    let summary = Organizer::new(temp_path.to_path_buf(), false)
+        .with_apply_only(Some(filter))
+        .organize_files(&all_entries, &[], false)?;
+
+    assert_eq!(summary.files_moved, 2);
+    assert!(temp_path.join("Images").join("beach.jpg").exists());
+    assert!(temp_path.join("Images").join("sunset.png").exists());
+    assert!(temp_path.join("report.pdf").exists());
+    assert!(temp_path.join("song.mp3").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_dedup_group_limit_bounds_groups_and_moves_every_copy() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    for i in 0..25 {
+        fs::write(temp_path.join(format!("tiny{:02}.txt", i)), b"same")?;
+    }
+    fs::write(temp_path.join("other1.txt"), b"pair")?;
+    fs::write(temp_path.join("other2.txt"), b"pair")?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false).analyze()?;
+    let entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+
+    let finder = DuplicateFinder::new(false).with_group_limit(Some(4));
+    // Streamed groups never hold more than 4 entries, yet cover every copy once
+    let mut most_held = 0;
+    let mut copies = std::collections::HashSet::new();
+    let mut originals = std::collections::HashSet::new();
+    finder.find_duplicates_streaming(&entries, |group| {
+        most_held = most_held.max(group.files.len());
+        originals.insert(group.files[0].path.clone());
+        copies.extend(group.files[1..].iter().map(|e| e.path.clone()));
+    })?;
+    assert_eq!(most_held, 4);
+    assert_eq!(copies.len(), 25);
+    assert_eq!(originals.len(), 2);
+    let duplicates = finder.find_duplicates(&entries)?;
+
+    let (capped, stopped) = DuplicateFinder::new(false)
+        .with_max_groups(Some(1))
+        .find_duplicates_capped(&entries)?;
+    assert_eq!(capped.len(), 1);
+    assert!(stopped);
+    let (_, stopped) = DuplicateFinder::new(false)
+        .with_max_groups(Some(2))
+        .find_duplicates_capped(&entries)?;
+    assert!(!stopped);
+
+    let summary = Organizer::new(temp_path.to_path_buf(), false)
+        .organize_files(&entries, &duplicates, false)?;
+    assert_eq!(summary.files_moved, 27);
+    assert_eq!(fs::read_dir(temp_path.join("Duplicates"))?.count(), 25);
+    assert_eq!(fs::read_dir(temp_path.join("Documents"))?.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_recursive_two_level_tree_moves_into_top_level_folders() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    let level1 = temp_path.join("projects");
+    let level2 = level1.join("2024");
+    fs::create_dir_all(&level2)?;
+    fs::create_dir(temp_path.join("Images"))?;
+    File::create(temp_path.join("notes.txt"))?;
+    File::create(level1.join("slides.pptx"))?;
+    File::create(level2.join("photo.jpg"))?;
+    // Already organized by an earlier run
+    File::create(temp_path.join("Images").join("old.jpg"))?;
+    // A symlink back up the tree must not be followed forever
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(temp_path, level2.join("loop"))?;
+
+    let analysis = FileAnalyzer::new(temp_path.to_path_buf(), false)
+        .with_recursive(0)
+        .analyze()?;
+    assert_eq!(analysis.supported_files, 3);
+    let entries: Vec<_> = analysis.categories.values().flatten().cloned().collect();
+
+    Organizer::new(temp_path.to_path_buf(), false).organize_files(&entries, &[], false)?;
+
+    assert!(temp_path.join("Documents").join("notes.txt").exists());
+    assert!(temp_path.join("Presentations").join("slides.pptx").exists());
+    assert!(temp_path.join("Images").join("photo.jpg").exists());
+    assert!(temp_path.join("Images").join("old.jpg").exists());
+    assert!(!level2.join("photo.jpg").exists());
+    assert!(!temp_path.join("Images").join("old (1).jpg").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_changes_only_second_run_reports_nothing() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("beach.jpg"), b"sand")?;
+    fs::write(temp_path.join("report.pdf"), b"numbers")?;
+
+    // Only images are organized, so report.pdf is found again on every run
+    let run = |changes_only: bool| -> anyhow::Result<String> {
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"));
+        command.args(["--apply-only", "images"]);
+        if changes_only {
+            command.arg("--changes-only");
+        }
+        let output = command.arg(temp_path).output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    let first = run(true)?;
+    assert!(first.contains("beach.jpg"));
+    assert!(!first.contains("report.pdf"));
+
+    assert!(run(false)?.contains("report.pdf"));
+    let second = run(true)?;
+    assert!(second.contains("No changes in this run."));
+    assert!(!second.contains("report.pdf"));
+
+    Ok(())
+}
+
+#[test]
+fn test_output_json_is_parseable() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("beach.jpg"), b"sand")?;
+    fs::write(temp_path.join("beach copy.jpg"), b"sand")?;
+    fs::write(temp_path.join("report.pdf"), b"numbers")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .args(["--analyze", "--output", "json"])
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["dry_run"], true);
+    assert_eq!(json["categories"]["Images"].as_array().unwrap().len(), 2);
+    assert_eq!(json["categories"]["PDFs"][0]["size"], 7);
+    assert_eq!(json["duplicates"].as_array().unwrap().len(), 1);
+    assert_eq!(json["actions"].as_array().unwrap().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_dedup_report_json_marks_kept_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let folder = temp_dir.path().join("desk");
+    fs::create_dir(&folder)?;
+    fs::write(folder.join("beach.jpg"), b"sand")?;
+    fs::write(folder.join("beach copy.jpg"), b"sand")?;
+    fs::write(folder.join("beach (2).jpg"), b"sand")?;
+    fs::write(folder.join("report.pdf"), b"numbers")?;
+    fs::write(folder.join("report copy.pdf"), b"numbers")?;
+    fs::write(folder.join("notes.txt"), b"unique")?;
+    let report_path = temp_dir.path().join("dupes.json");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--analyze")
+        .arg("--dedup-report-json")
+        .arg(&report_path)
+        .arg(&folder)
+        .output()?;
+    assert!(output.status.success());
+    // The main output is still the human-readable summary
+    assert!(serde_json::from_slice::<serde_json::Value>(&output.stdout).is_err());
+
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&report_path)?)?;
+    let groups = report["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    for group in groups {
+        let files = group["files"].as_array().unwrap();
+        let kept: Vec<_> = files.iter().filter(|f| f["kept"] == true).collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(files[0]["kept"], true);
+        let size = files[0]["size"].as_u64().unwrap();
+        assert_eq!(group["reclaimable_bytes"], size * (files.len() as u64 - 1));
+    }
+    assert_eq!(report["reclaimable_bytes"], 4 * 2 + 7);
+
+    Ok(())
+}
+
+#[test]
+fn test_format_json_counts_categories() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("beach.jpg"), b"sand")?;
+    fs::write(temp_path.join("sunset.png"), b"orange")?;
+    fs::write(temp_path.join("report.pdf"), b"numbers")?;
+    fs::write(temp_path.join("notes.xyz"), b"unknown")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .args(["--verbose", "--format", "json"])
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    // Verbose progress must not break the document
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["dry_run"], false);
+    assert_eq!(json["counts"]["Images"], 2);
+    assert_eq!(json["counts"]["PDFs"], 1);
+    assert_eq!(json["counts"].as_object().unwrap().len(), 2);
+    assert_eq!(json["total_bytes"], 17);
+    assert!(temp_path.join("PDFs").join("report.pdf").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_json_stays_valid_when_dedup_is_capped() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("a.pdf"), b"first")?;
+    fs::write(temp_path.join("a copy.pdf"), b"first")?;
+    fs::write(temp_path.join("b.jpg"), b"second photo")?;
+    fs::write(temp_path.join("b copy.jpg"), b"second photo")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .args(["--analyze", "--output", "json", "--dedup-max-groups", "1"])
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["dry_run"], true);
+    assert!(String::from_utf8(output.stderr)?.contains("Stopped after 1 duplicate groups"));
+
+    Ok(())
+}
+
+#[test]
+fn test_analyze_writes_undo_preview_only_when_asked() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("report.pdf"), b"report")?;
+    let manifest = temp_path.join(".desktidy-undo.json");
+
+    let run = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+            .args(args)
+            .arg(temp_path)
+            .status()
+    };
+    assert!(run(&["--analyze"])?.success());
+    assert!(!manifest.exists());
+
+    assert!(run(&["--analyze", "--undo-preview"])?.success());
+    assert!(desktidy::undo::UndoManifest::load(&manifest)?.dry_run);
+    assert!(temp_path.join("report.pdf").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_undo_puts_files_back_and_skips_deleted_ones() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("report.pdf"), b"report")?;
+    fs::write(temp_path.join("photo.jpg"), b"photo")?;
+    fs::write(temp_path.join("song.mp3"), b"song")?;
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("-q")
+        .arg(temp_path)
+        .status()?;
+    assert!(status.success());
+    assert!(temp_path.join("PDFs").join("report.pdf").exists());
+    // The user deletes one of the organized files before undoing
+    fs::remove_file(temp_path.join("Audio").join("song.mp3"))?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--undo")
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Restored report.pdf"));
+    assert!(stdout.contains("no longer exists"));
+    assert_eq!(fs::read(temp_path.join("report.pdf"))?, b"report");
+    assert_eq!(fs::read(temp_path.join("photo.jpg"))?, b"photo");
+    assert!(!temp_path.join("song.mp3").exists());
+    assert!(!temp_path.join(".desktidy-undo.json").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_process_duplicates_folder_promotes_unique_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    let duplicates = temp_path.join("Duplicates");
+    fs::create_dir(&duplicates)?;
+    fs::write(duplicates.join("unique.pdf"), b"only copy")?;
+    fs::write(duplicates.join("photo copy.jpg"), b"photo")?;
+    fs::write(temp_path.join("photo.jpg"), b"photo")?;
+
+    let run = |extra: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+            .arg("-q")
+            .args(extra)
+            .arg(temp_path)
+            .status()
+    };
+
+    // Without the flag the Duplicates folder is left alone
+    assert!(run(&[])?.success());
+    assert!(duplicates.join("unique.pdf").exists());
+
+    assert!(run(&["--process-duplicates-folder"])?.success());
+    assert_eq!(fs::read(temp_path.join("PDFs").join("unique.pdf"))?, b"only copy");
+    assert!(!duplicates.join("unique.pdf").exists());
+    // A real duplicate stays put, and the original outside is still the one organized
+    assert!(duplicates.join("photo copy.jpg").exists());
+    assert!(temp_path.join("Images").join("photo.jpg").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_cycle_skipped_with_warning() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("report.pdf"), b"report")?;
+    fs::write(temp_path.join("report copy.pdf"), b"report")?;
+    std::os::unix::fs::symlink("b.pdf", temp_path.join("a.pdf"))?;
+    std::os::unix::fs::symlink("a.pdf", temp_path.join("b.pdf"))?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .args(["--dedup-symlink-aware", "--organize-symlinks-as-copies"])
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("a.pdf (symlink cycle)"));
+    assert!(stdout.contains("b.pdf (symlink cycle)"));
+    assert_eq!(fs::read_dir(temp_path.join("PDFs"))?.count(), 1);
+    // The links are left where they are
+    assert!(fs::symlink_metadata(temp_path.join("a.pdf"))?.file_type().is_symlink());
+    assert!(fs::symlink_metadata(temp_path.join("b.pdf"))?.file_type().is_symlink());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_porcelain_counts_symlink_cycles_as_skipped() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("report.pdf"), b"report")?;
+    fs::write(temp_path.join("notes.xyz"), b"notes")?;
+    std::os::unix::fs::symlink("b.pdf", temp_path.join("a.pdf"))?;
+    std::os::unix::fs::symlink("a.pdf", temp_path.join("b.pdf"))?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--porcelain")
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr)?;
+    let summary: serde_json::Value = serde_json::from_str(stderr.lines().last().unwrap())?;
+    assert_eq!(summary["moved"], 1);
+    // notes.xyz is unsupported and both links are skipped as a cycle
+    assert_eq!(summary["skipped"], 3);
+    assert_eq!(summary["errors"], 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_export_plan_then_apply() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let folder = temp_dir.path().join("desk");
+    fs::create_dir(&folder)?;
+    fs::write(folder.join("report.pdf"), b"report")?;
+    fs::write(folder.join("report copy.pdf"), b"report")?;
+    fs::write(folder.join("beach.jpg"), b"sand")?;
+    let plan_path = temp_dir.path().join("plan.json");
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--export-plan")
+        .arg(&plan_path)
+        .arg(&folder)
+        .status()?;
+    assert!(status.success());
+    // Exporting moves nothing
+    assert!(folder.join("report.pdf").exists());
+    assert!(!folder.join("PDFs").exists());
+
+    let plan = Plan::load(&plan_path)?;
+    let moves: Vec<_> = plan
+        .actions
+        .iter()
+        .filter_map(|action| match action {
+            PlannedAction::MoveFile { from, to } | PlannedAction::MoveDuplicate { from, to, .. } => {
+                Some((from.clone(), to.clone()))
+            }
+            PlannedAction::CreateDirectory { .. } => None,
+        })
+        .collect();
+    assert_eq!(moves.len(), 3);
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("apply")
+        .arg(&plan_path)
+        .status()?;
+    assert!(status.success());
+    for (from, to) in &moves {
+        assert!(!from.exists(), "{} should have moved", from.display());
+        assert!(to.exists(), "{} should exist", to.display());
+    }
+    assert_eq!(fs::read(folder.join("Images").join("beach.jpg"))?, b"sand");
+
+    Ok(())
+}
+
+#[test]
+fn test_recursive_run_leaves_category_folder_names_alone() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("PDFs"))?;
+    fs::create_dir_all(temp_path.join("Images_2"))?;
+    fs::write(temp_path.join("PDFs").join("report_1.pdf"), b"report")?;
+    fs::write(temp_path.join("Images_2").join("beach_1.jpg"), b"sand")?;
+    fs::write(temp_path.join("notes_1.pdf"), b"notes")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--recursive")
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+
+    // Files coming in are cleaned; files already organized keep their names
+    assert_eq!(fs::read(temp_path.join("PDFs").join("notes.pdf"))?, b"notes");
+    assert!(temp_path.join("PDFs").join("report_1.pdf").exists());
+    assert!(!temp_path.join("PDFs").join("report.pdf").exists());
+    assert!(temp_path.join("Images_2").join("beach_1.jpg").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_date_folder_flags_cannot_be_combined() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("notes.pdf"), b"notes")?;
+
+    let combinations: [&[&str]; 5] = [
+        &["--by-date", "--date-folders"],
+        &["--by-date", "--date-granularity", "year"],
+        &["--by-date", "--date-format", "%Y"],
+        &["--date-folders", "--date-granularity", "year"],
+        &["--date-folders", "--date-format", "%Y"],
+    ];
+    for flags in combinations {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+            .args(flags)
+            .arg(temp_path)
+            .output()?;
+        assert!(!output.status.success(), "{:?} was accepted", flags);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+        assert!(temp_path.join("notes.pdf").exists());
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_desktidy"))
+        .arg("--by-date")
+        .arg(temp_path)
+        .output()?;
+    assert!(output.status.success());
+    let dated = fs::read_dir(temp_path.join("PDFs"))?.next().unwrap()?.path();
+    assert!(dated.is_dir());
+    assert!(!temp_path.join("notes.pdf").exists());
+
+    Ok(())
+}